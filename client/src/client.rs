@@ -1,7 +1,8 @@
-use std::fs;
-use std::path::Path;
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::sync::{Arc, Weak};
+use std::time::Duration;
 
+pub use archive::*;
 use async_trait::async_trait;
 pub use block_functions::*;
 pub use block_stream::*;
@@ -9,34 +10,74 @@ pub use builder::*;
 pub use callback::*;
 pub use connection::*;
 pub use error::*;
+pub use health::*;
 pub use interface::*;
-use rand::Rng;
-use tokio_retry::strategy::FixedInterval;
-use tokio_retry::RetryIf;
+pub use limiter::*;
+#[cfg(feature = "liteapi")]
+pub use lite_client::*;
+#[cfg(feature = "liteapi")]
+pub use lite_query::*;
+#[cfg(feature = "metrics")]
+pub use metrics::*;
+pub use mock::*;
+use pool::*;
+pub use recording::*;
+pub use retry::*;
+pub use time_index::*;
 pub use types::*;
 
+use crate::config::TonConfig;
 use crate::tl::*;
 
+mod archive;
 mod block_functions;
 mod block_stream;
 mod builder;
 mod callback;
 mod connection;
 mod error;
+mod health;
 mod interface;
+mod limiter;
+mod mock;
+mod pool;
+mod recording;
+mod retry;
+mod time_index;
 mod types;
 
+#[cfg(feature = "liteapi")]
+mod lite_client;
+#[cfg(feature = "liteapi")]
+mod lite_query;
+#[cfg(feature = "metrics")]
+mod metrics;
 #[cfg(feature = "liteapi")]
 mod recent_init_block;
 
 #[derive(Clone)]
 pub struct TonClient {
     inner: Arc<Inner>,
+    priority: RequestPriority,
+    caller: Option<Arc<str>>,
+}
+
+/// Optional features of `TonClient` connection pool
+#[derive(Default, Clone)]
+pub(crate) struct PoolOptions {
+    pub(crate) health_check: Option<HealthCheckParams>,
+    pub(crate) archive_routing: Option<ArchiveRoutingParams>,
+    pub(crate) request_timeout: Option<Duration>,
+    pub(crate) caller_budgets: HashMap<String, CallerBudget>,
 }
 
 struct Inner {
-    retry_strategy: RetryStrategy,
-    connections: Vec<TonConnection>,
+    retry_policy: Arc<dyn RetryPolicy>,
+    request_timeout: Option<Duration>,
+    budgets: CallerBudgets,
+    pool: ConnectionPool,
+    archive_pool: ConnectionPool,
+    archive_router: Option<ArchiveRouter>,
 }
 
 impl TonClient {
@@ -48,35 +89,86 @@ impl TonClient {
         callback: Arc<dyn TonConnectionCallback>,
         connection_check: ConnectionCheck,
     ) -> Result<TonClient, TonClientError> {
-        let patched_params = if params.update_init_block {
+        Self::new_with_pools(
+            pool_size,
+            params,
+            Arc::new(retry_strategy),
+            callback,
+            connection_check,
+            PoolOptions::default(),
+        )
+        .await
+    }
+
+    pub(crate) async fn new_with_pools(
+        pool_size: usize,
+        params: &TonConnectionParams,
+        retry_policy: Arc<dyn RetryPolicy>,
+        callback: Arc<dyn TonConnectionCallback>,
+        connection_check: ConnectionCheck,
+        options: PoolOptions,
+    ) -> Result<TonClient, TonClientError> {
+        // lite backend doesn't use init block
+        let patched_params = if params.update_init_block && params.backend == TonBackend::Tonlib {
             patch_init_block(params).await?
         } else {
             params.clone()
         };
-        let mut connections = Vec::with_capacity(pool_size);
-        for i in 0..pool_size {
-            let mut conn_params = patched_params.clone();
-            if let Some(dir) = &patched_params.keystore_dir {
-                let keystore_prefix = Path::new(dir.as_str());
-                let keystore_dir = keystore_prefix.join(format!("{}", i));
-                fs::create_dir_all(&keystore_dir)?;
-                let path_str = keystore_dir.into_os_string().into_string().map_err(|_| {
-                    TonClientError::InternalError("Error constructing keystore path".to_string())
-                })?;
-                conn_params.keystore_dir = Some(path_str)
-            };
-            let conn = TonConnection::new(connection_check.clone(), &conn_params, callback.clone())
+        let pool = ConnectionPool::connect(
+            0,
+            pool_size,
+            &patched_params,
+            connection_check,
+            callback.clone(),
+        )
+        .await?;
+        let (archive_pool, archive_router) = match &options.archive_routing {
+            Some(archive_routing) if archive_routing.pool_size > 0 => {
+                let archive_pool = ConnectionPool::connect(
+                    pool_size,
+                    archive_routing.pool_size,
+                    &patched_params,
+                    ConnectionCheck::Archive,
+                    callback,
+                )
                 .await?;
-
-            connections.push(conn);
+                (archive_pool, Some(ArchiveRouter::new(archive_routing)))
+            }
+            _ => (ConnectionPool::from_connections(vec![]), None),
+        };
+        let inner = Arc::new(Inner {
+            retry_policy,
+            request_timeout: options.request_timeout,
+            budgets: CallerBudgets::new(&options.caller_budgets),
+            pool,
+            archive_pool,
+            archive_router,
+        });
+        if let Some(health_check) = options.health_check {
+            tokio::spawn(run_health_check(Arc::downgrade(&inner), health_check));
         }
+        Ok(TonClient::from_inner(inner))
+    }
+
+    /// Creates a client with a single connection, e.g. one backed by `TonFunctionHandler`
+    pub(crate) fn from_connection(connection: TonConnection) -> TonClient {
         let inner = Inner {
-            retry_strategy,
-            connections,
+            retry_policy: Arc::new(RetryStrategy::default()),
+            request_timeout: None,
+            budgets: CallerBudgets::default(),
+            pool: ConnectionPool::from_connections(vec![connection]),
+            archive_pool: ConnectionPool::from_connections(vec![]),
+            archive_router: None,
         };
-        Ok(TonClient {
-            inner: Arc::new(inner),
-        })
+        TonClient::from_inner(Arc::new(inner))
+    }
+
+    fn from_inner(inner: Arc<Inner>) -> TonClient {
+        TonClient {
+            inner,
+            priority: RequestPriority::default(),
+            caller: None,
+        }
     }
 
     pub fn builder() -> TonClientBuilder {
@@ -87,39 +179,159 @@ impl TonClient {
         Self::builder().build().await
     }
 
-    #[allow(clippy::let_and_return)]
-    async fn retrying_invoke(
+    /// Returns a client sharing connections with this one, queuing its requests with `priority`
+    pub fn with_priority(&self, priority: RequestPriority) -> TonClient {
+        TonClient {
+            priority,
+            ..self.clone()
+        }
+    }
+
+    /// Returns a client sharing connections with this one, charging its requests to `caller` budget
+    ///
+    /// Budgets are configured by `TonClientBuilder::with_caller_budget`,
+    /// requests of callers without budget are not limited.
+    pub fn with_caller(&self, caller: &str) -> TonClient {
+        TonClient {
+            caller: Some(Arc::from(caller)),
+            ..self.clone()
+        }
+    }
+
+    /// Applies `config` to running client, e.g. with updated lite server list
+    ///
+    /// The config is validated and its init block updated before any connection is touched.
+    /// Then connections of both pools are rebuilt one at a time, waiting `interval` between them,
+    /// so the pool keeps serving requests. In-flight requests are completed on the old connections.
+    /// Connections failed to reconnect are listed in the report instead of aborting the reload.
+    pub async fn reload_config(
         &self,
-        function: &TonFunction,
-    ) -> Result<(TonConnection, TonResult), TonClientError> {
-        let fi = FixedInterval::from_millis(self.inner.retry_strategy.interval_ms);
-        let strategy = fi.take(self.inner.retry_strategy.max_retries);
-        let result = RetryIf::spawn(strategy, || self.do_invoke(function), retry_condition).await;
-        result
+        config: &str,
+        interval: Duration,
+    ) -> Result<ConfigReloadReport, TonClientError> {
+        let ton_config = TonConfig::from_json(config)
+            .map_err(|e| TonClientError::InvalidArgument(format!("Fail to parse config: {}", e)))?;
+        if ton_config.liteservers.is_empty() {
+            return Err(TonClientError::InvalidArgument(
+                "Config has no lite servers".to_string(),
+            ));
+        }
+        let inner = &self.inner;
+        let Some(mut params) = inner.pool.params().or_else(|| inner.archive_pool.params()) else {
+            return Err(TonClientError::InvalidArgument(
+                "Client connections can't be reconnected".to_string(),
+            ));
+        };
+        params.config = config.to_string();
+        if params.update_init_block && params.backend == TonBackend::Tonlib {
+            params = patch_init_block(&params).await?;
+        }
+
+        inner.pool.set_config(&params.config);
+        inner.archive_pool.set_config(&params.config);
+        let mut report = ConfigReloadReport::default();
+        for (archive, pool) in [(false, &inner.pool), (true, &inner.archive_pool)] {
+            let (reconnected, failures) = pool.reconnect_all(interval).await;
+            report.reconnected += reconnected;
+            report
+                .failures
+                .extend(failures.into_iter().map(|(index, error)| {
+                    log::warn!("Failed to reload config of connection {}: {}", index, error);
+                    ConnectionReloadFailure {
+                        archive,
+                        index,
+                        error,
+                    }
+                }));
+        }
+        Ok(report)
+    }
+
+    /// Sends a typed request to the lite server of a pool connection.
+    ///
+    /// Requests are charged to the caller budget, but not retried, see `TonConnection::lite_request`.
+    #[cfg(feature = "liteapi")]
+    pub async fn lite_request<R: LiteRequest>(
+        &self,
+        request: R,
+    ) -> Result<R::Response, TonClientError> {
+        if let Some(caller) = &self.caller {
+            self.inner.budgets.acquire(caller).await?;
+        }
+        self.get_connection().await?.lite_request(request).await
+    }
+
+    /// Returns current health scores of pool connections, lower score is better
+    pub fn connection_scores(&self) -> Vec<ConnectionScore> {
+        self.inner.pool.scores()
+    }
+
+    /// Returns current health scores of archive connections, lower score is better
+    pub fn archive_connection_scores(&self) -> Vec<ConnectionScore> {
+        self.inner.archive_pool.scores()
     }
 
-    async fn do_invoke(
+    /// Invokes `function` on `pool`, retrying on another connection according to retry policy
+    async fn retrying_invoke(
         &self,
+        pool: &ConnectionPool,
         function: &TonFunction,
+        retry_state_gc: bool,
     ) -> Result<(TonConnection, TonResult), TonClientError> {
-        let item = self.random_item();
-        let conn = item.get_connection().await?;
-        let res = conn.invoke(function).await;
-        match res {
-            Ok(result) => Ok((conn, result)),
-            Err(error) => Err(error),
+        let mut attempt = 0;
+        let mut failed = None;
+        loop {
+            let (index, entry) = pool.select(failed)?;
+            let invoke = entry.invoke(function, self.inner.request_timeout, self.priority);
+            let error = match invoke.await {
+                Ok(result) => return Ok(result),
+                Err(error) => error,
+            };
+            if !retry_state_gc && error.is_archive_required() {
+                return Err(error);
+            }
+            attempt += 1;
+            let Some(delay) = self
+                .inner
+                .retry_policy
+                .next_delay(function, &error, attempt)
+            else {
+                return Err(error);
+            };
+            log::debug!("Retrying {:?} in {:?}: {}", function, delay, error);
+            failed = Some(index);
+            tokio::time::sleep(delay).await;
         }
     }
 
-    #[allow(clippy::let_and_return)]
-    fn random_item(&self) -> &TonConnection {
-        let pos = {
-            let mut rng = rand::rng();
-            rng.random_range(0..self.inner.connections.len())
+    /// Invokes `function` on archive pool if it's required, or as a fallback for gc'd state
+    async fn routed_invoke(
+        &self,
+        router: &ArchiveRouter,
+        function: &TonFunction,
+    ) -> Result<(TonConnection, TonResult), TonClientError> {
+        let inner = &self.inner;
+        let result = if router.needs_archive(function, inner.pool.max_seqno()) {
+            self.retrying_invoke(&inner.archive_pool, function, true)
+                .await
+        } else {
+            let regular_result = self.retrying_invoke(&inner.pool, function, false).await;
+            match regular_result {
+                Err(e) if e.is_archive_required() => {
+                    log::debug!("Falling back to archive pool: {}", e);
+                    self.retrying_invoke(&inner.archive_pool, function, true)
+                        .await
+                }
+                r => r,
+            }
         };
-        &self.inner.connections[pos]
+        if let Ok((_, r)) = &result {
+            router.track(function, r);
+        }
+        result
     }
 
+    #[cfg(feature = "tonlib")]
     pub fn set_log_verbosity_level(verbosity_level: u32) {
         TlTonClient::set_log_verbosity_level(verbosity_level)
     }
@@ -128,8 +340,8 @@ impl TonClient {
 #[async_trait]
 impl TonClientInterface for TonClient {
     async fn get_connection(&self) -> Result<TonConnection, TonClientError> {
-        let item = self.random_item();
-        let conn = item.get_connection().await?;
+        let (_, item) = self.inner.pool.select(None)?;
+        let conn = item.connection().with_priority(self.priority);
         Ok(conn)
     }
 
@@ -137,23 +349,26 @@ impl TonClientInterface for TonClient {
         &self,
         function: &TonFunction,
     ) -> Result<(TonConnection, TonResult), TonClientError> {
-        self.retrying_invoke(function).await
-    }
-}
-
-fn maybe_error_code(error: &TonClientError) -> Option<i32> {
-    if let TonClientError::TonlibError { code, .. } = error {
-        Some(*code)
-    } else {
-        None
+        if let Some(caller) = &self.caller {
+            self.inner.budgets.acquire(caller).await?;
+        }
+        match &self.inner.archive_router {
+            Some(router) => self.routed_invoke(router, function).await,
+            None => self.retrying_invoke(&self.inner.pool, function, true).await,
+        }
     }
 }
 
-fn retry_condition(error: &TonClientError) -> bool {
-    if let Some(code) = maybe_error_code(error) {
-        code == 500
-    } else {
-        false
+/// Probes pool connections and reconnects unhealthy ones until the client is dropped
+async fn run_health_check(weak_inner: Weak<Inner>, params: HealthCheckParams) {
+    let interval = Duration::from_millis(params.probe_interval_ms);
+    loop {
+        tokio::time::sleep(interval).await;
+        let Some(inner) = weak_inner.upgrade() else {
+            break;
+        };
+        inner.pool.check_health(&params).await;
+        inner.archive_pool.check_health(&params).await;
     }
 }
 
@@ -169,8 +384,6 @@ async fn patch_init_block(
 async fn patch_init_block(
     params: &TonConnectionParams,
 ) -> Result<TonConnectionParams, TonClientError> {
-    use crate::config::TonConfig;
-
     let mut ton_config = TonConfig::from_json(&params.config).map_err(|e| {
         let msg = format!("Fail to parse config: {}", e);
         TonClientError::InternalError(msg)
@@ -0,0 +1,394 @@
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use moka::sync::Cache;
+use serde::{Deserialize, Serialize};
+use tonlib_core::TonAddress;
+
+use crate::tl::{AccountAddress, TonFunction, TonResult};
+
+const PAGING_CACHE_CAPACITY: u64 = 10000;
+const PAGING_CACHE_TIME_TO_LIVE: Duration = Duration::from_secs(3600);
+
+/// Parameters of the archive sub-pool of `TonClient`.
+///
+/// Queries for old state are sent to archive connections, other queries go to the regular pool
+/// and fall back to archive connections when the state is already garbage collected.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ArchiveRoutingParams {
+    /// Number of archive connections
+    pub pool_size: usize,
+    /// Lookups of masterchain blocks deeper than that are routed to archive connections
+    pub max_block_depth: i32,
+    /// Lookups of blocks by utime and of account state by transactions older than that
+    /// are routed to archive connections.
+    ///
+    /// Age of a transaction is only known if it has been returned by transaction paging,
+    /// state by other transactions is requested from regular connections first.
+    pub max_block_age_secs: u64,
+    /// Transaction pages of an account beyond that depth are routed to archive connections
+    pub max_transaction_pages: u32,
+}
+
+impl Default for ArchiveRoutingParams {
+    fn default() -> Self {
+        ArchiveRoutingParams {
+            pool_size: 1,
+            max_block_depth: 17280, // about a day of masterchain blocks
+            max_block_age_secs: 86400,
+            max_transaction_pages: 10,
+        }
+    }
+}
+
+pub(crate) struct ArchiveRouter {
+    params: ArchiveRoutingParams,
+    /// Depth of transaction pages by (account, from_lt)
+    pages: Cache<(String, i64), u32>,
+    /// Utime of transactions returned by paging, by (account, lt)
+    transaction_times: Cache<(String, i64), i64>,
+}
+
+impl ArchiveRouter {
+    pub(crate) fn new(params: &ArchiveRoutingParams) -> ArchiveRouter {
+        ArchiveRouter {
+            params: params.clone(),
+            pages: new_cache(),
+            transaction_times: new_cache(),
+        }
+    }
+
+    /// Checks if `function` requires archive node, `last_seqno` is the latest known masterchain seqno
+    pub(crate) fn needs_archive(&self, function: &TonFunction, last_seqno: Option<i32>) -> bool {
+        match function {
+            TonFunction::RawGetAccountStateByTransaction {
+                account_address,
+                transaction_id,
+            }
+            | TonFunction::SmcLoadByTransaction {
+                account_address,
+                transaction_id,
+            } => {
+                let key = (account_key(account_address), transaction_id.lt);
+                self.transaction_times
+                    .get(&key)
+                    .is_some_and(|utime| now_secs() - utime > self.params.max_block_age_secs as i64)
+            }
+            TonFunction::WithBlock { id, .. } => {
+                id.workchain == -1
+                    && last_seqno.is_some_and(|last| last - id.seqno > self.params.max_block_depth)
+            }
+            TonFunction::BlocksLookupBlock {
+                mode, id, utime, ..
+            } => {
+                if mode & 1 != 0 && id.workchain == -1 {
+                    last_seqno.is_some_and(|last| last - id.seqno > self.params.max_block_depth)
+                } else if mode & 4 != 0 {
+                    now_secs() - (*utime as i64) > self.params.max_block_age_secs as i64
+                } else {
+                    false
+                }
+            }
+            TonFunction::RawGetTransactions {
+                account_address,
+                from_transaction_id,
+            }
+            | TonFunction::RawGetTransactionsV2 {
+                account_address,
+                from_transaction_id,
+                ..
+            } => {
+                let key = (account_key(account_address), from_transaction_id.lt);
+                self.pages
+                    .get(&key)
+                    .is_some_and(|depth| depth >= self.params.max_transaction_pages)
+            }
+            _ => false,
+        }
+    }
+
+    /// Remembers the depth of the next transaction page and utime of the returned transactions
+    pub(crate) fn track(&self, function: &TonFunction, result: &TonResult) {
+        let (account_address, from_transaction_id) = match function {
+            TonFunction::RawGetTransactions {
+                account_address,
+                from_transaction_id,
+            }
+            | TonFunction::RawGetTransactionsV2 {
+                account_address,
+                from_transaction_id,
+                ..
+            } => (account_address, from_transaction_id),
+            _ => return,
+        };
+        let TonResult::RawTransactions(txs) = result else {
+            return;
+        };
+        let address = account_key(account_address);
+        for tx in &txs.transactions {
+            let key = (address.clone(), tx.transaction_id.lt);
+            self.transaction_times.insert(key, tx.utime);
+        }
+        if txs.previous_transaction_id.lt == 0 {
+            return;
+        }
+        let depth = self
+            .pages
+            .get(&(address.clone(), from_transaction_id.lt))
+            .unwrap_or(0);
+        self.pages
+            .insert((address, txs.previous_transaction_id.lt), depth + 1);
+    }
+}
+
+fn new_cache<V: Clone + Send + Sync + 'static>() -> Cache<(String, i64), V> {
+    Cache::builder()
+        .max_capacity(PAGING_CACHE_CAPACITY)
+        .time_to_live(PAGING_CACHE_TIME_TO_LIVE)
+        .build()
+}
+
+/// The same account has raw and user-friendly forms, so caches are keyed by the raw one
+fn account_key(account_address: &AccountAddress) -> String {
+    let address = &account_address.account_address;
+    TonAddress::from_str(address)
+        .map(|address| address.to_hex())
+        .unwrap_or_else(|_| address.clone())
+}
+
+fn now_secs() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::client::{
+        CallerBudgets, ConnectionPool, Inner, MockTonClient, RetryStrategy, TonClient,
+        TonClientInterface,
+    };
+    use crate::tl::{
+        BlockId, BlockIdExt, InternalTransactionId, RawTransaction, RawTransactions, SmcInfo,
+        SmcRunResult, TvmNumber, TvmStack, TvmStackEntry,
+    };
+    use crate::types::TonMethodId;
+
+    const ADDRESS: &str = "EQB3ncyBUTjZUA5EnFKR5_EnOMI9V1tTEAAPaiU71gc4TiUt";
+
+    fn account(address: &str) -> AccountAddress {
+        AccountAddress {
+            account_address: address.to_string(),
+        }
+    }
+
+    fn get_transactions(lt: i64) -> TonFunction {
+        get_transactions_of(ADDRESS, lt)
+    }
+
+    fn get_transactions_of(address: &str, lt: i64) -> TonFunction {
+        TonFunction::RawGetTransactionsV2 {
+            account_address: account(address),
+            from_transaction_id: InternalTransactionId {
+                lt,
+                hash: vec![0; 32],
+            },
+            count: 16,
+            try_decode_messages: false,
+        }
+    }
+
+    fn transactions(previous_lt: i64) -> TonResult {
+        transactions_with(vec![], previous_lt)
+    }
+
+    fn transactions_with(transactions: Vec<RawTransaction>, previous_lt: i64) -> TonResult {
+        TonResult::RawTransactions(RawTransactions {
+            transactions,
+            previous_transaction_id: InternalTransactionId {
+                lt: previous_lt,
+                hash: vec![0; 32],
+            },
+        })
+    }
+
+    fn transaction(lt: i64, utime: i64) -> RawTransaction {
+        RawTransaction {
+            address: account(ADDRESS),
+            utime,
+            data: vec![],
+            transaction_id: InternalTransactionId {
+                lt,
+                hash: vec![0; 32],
+            },
+            fee: 0,
+            storage_fee: 0,
+            other_fee: 0,
+            in_msg: None,
+            out_msgs: vec![],
+        }
+    }
+
+    fn load_by_transaction(address: &str, lt: i64) -> TonFunction {
+        TonFunction::SmcLoadByTransaction {
+            account_address: account(address),
+            transaction_id: InternalTransactionId {
+                lt,
+                hash: vec![0; 32],
+            },
+        }
+    }
+
+    #[test]
+    fn test_block_lookup_routing() {
+        let router = ArchiveRouter::new(&ArchiveRoutingParams::default());
+        let lookup = |seqno, mode, utime| TonFunction::BlocksLookupBlock {
+            mode,
+            id: BlockId {
+                workchain: -1,
+                shard: i64::MIN,
+                seqno,
+            },
+            lt: 0,
+            utime,
+        };
+        assert!(!router.needs_archive(&lookup(100000, 1, 0), Some(100010)));
+        assert!(router.needs_archive(&lookup(1, 1, 0), Some(100010)));
+        assert!(!router.needs_archive(&lookup(1, 1, 0), None));
+        assert!(router.needs_archive(&lookup(0, 4, 1), None));
+        assert!(!router.needs_archive(&lookup(0, 4, now_secs() as i32), None));
+
+        let with_block = |seqno| TonFunction::WithBlock {
+            id: BlockIdExt {
+                workchain: -1,
+                shard: i64::MIN,
+                seqno,
+                root_hash: vec![0; 32],
+                file_hash: vec![0; 32],
+            },
+            function: Box::new(TonFunction::BlocksGetMasterchainInfo {}),
+        };
+        assert!(!router.needs_archive(&with_block(100000), Some(100010)));
+        assert!(router.needs_archive(&with_block(1), Some(100010)));
+    }
+
+    #[test]
+    fn test_transaction_paging_routing() {
+        let params = ArchiveRoutingParams {
+            max_transaction_pages: 2,
+            ..Default::default()
+        };
+        let router = ArchiveRouter::new(&params);
+        let mut lt = 1000;
+        for _ in 0..params.max_transaction_pages {
+            assert!(!router.needs_archive(&get_transactions(lt), None));
+            router.track(&get_transactions(lt), &transactions(lt - 100));
+            lt -= 100;
+        }
+        assert!(router.needs_archive(&get_transactions(lt), None));
+
+        // the same account in the raw form shares the depth
+        let raw = TonAddress::from_str(ADDRESS).unwrap().to_hex();
+        assert!(router.needs_archive(&get_transactions_of(&raw, lt), None));
+    }
+
+    #[test]
+    fn test_transaction_age_routing() {
+        let router = ArchiveRouter::new(&ArchiveRoutingParams::default());
+        let raw = TonAddress::from_str(ADDRESS).unwrap().to_hex();
+        // state by unknown transactions is requested from regular connections first
+        assert!(!router.needs_archive(&load_by_transaction(ADDRESS, 900), None));
+
+        let page = transactions_with(
+            vec![transaction(1000, now_secs()), transaction(900, 1)],
+            800,
+        );
+        router.track(&get_transactions(1000), &page);
+        assert!(!router.needs_archive(&load_by_transaction(ADDRESS, 1000), None));
+        assert!(router.needs_archive(&load_by_transaction(ADDRESS, 900), None));
+        let get_state = TonFunction::RawGetAccountStateByTransaction {
+            account_address: account(&raw),
+            transaction_id: InternalTransactionId {
+                lt: 900,
+                hash: vec![0; 32],
+            },
+        };
+        assert!(router.needs_archive(&get_state, None));
+    }
+
+    async fn client_with_archive(regular: &MockTonClient, archive: &MockTonClient) -> TonClient {
+        let inner = Inner {
+            retry_policy: Arc::new(RetryStrategy::default()),
+            request_timeout: None,
+            budgets: CallerBudgets::default(),
+            pool: ConnectionPool::from_connections(vec![regular.get_connection().await.unwrap()]),
+            archive_pool: ConnectionPool::from_connections(vec![archive
+                .get_connection()
+                .await
+                .unwrap()]),
+            archive_router: Some(ArchiveRouter::new(&ArchiveRoutingParams::default())),
+        };
+        TonClient::from_inner(Arc::new(inner))
+    }
+
+    #[tokio::test]
+    async fn test_client_routes_to_archive_pool() {
+        let regular = MockTonClient::new();
+        let archive = MockTonClient::new();
+        let get_page = get_transactions(1000);
+        regular.add_response(
+            get_page.clone(),
+            transactions_with(vec![transaction(1000, 1)], 900),
+        );
+        let load = load_by_transaction(ADDRESS, 1000);
+        archive.add_response(load.clone(), TonResult::SmcInfo(SmcInfo { id: 7 }));
+        let method = TonMethodId::from("seqno");
+        let run_result = SmcRunResult {
+            gas_used: 10,
+            stack: TvmStack {
+                elements: vec![TvmStackEntry::Number {
+                    number: TvmNumber {
+                        number: "3".to_string(),
+                    },
+                }],
+            },
+            exit_code: 0,
+        };
+        archive.add_response(
+            TonFunction::SmcRunGetMethod {
+                id: 7,
+                method: (&method).into(),
+                stack: vec![],
+            },
+            TonResult::SmcRunResult(run_result.clone()),
+        );
+        let get_state = TonFunction::RawGetAccountState {
+            account_address: account(ADDRESS),
+        };
+        regular.add_response(
+            get_state.clone(),
+            TonResult::Error {
+                code: 500,
+                message: "LITE_SERVER_UNKNOWN: state already gc'd".to_string(),
+            },
+        );
+        archive.add_response(get_state.clone(), TonResult::Ok {});
+        let client = client_with_archive(&regular, &archive).await;
+
+        // the page tells that the transaction is old, so regular connections are skipped
+        client.invoke(&get_page).await.unwrap();
+        let (conn, result) = client.invoke_on_connection(&load).await.unwrap();
+        assert_eq!(result, TonResult::SmcInfo(SmcInfo { id: 7 }));
+        // get methods are run on the archive connection which loaded the contract
+        let result = conn.smc_run_get_method(7, &method, &[]).await.unwrap();
+        assert_eq!(result, run_result);
+
+        assert_eq!(client.invoke(&get_state).await.unwrap(), TonResult::Ok {});
+        assert_eq!(client.connection_scores()[0].consecutive_failures, 0);
+        assert_eq!(client.archive_connection_scores()[0].requests, 2);
+    }
+}
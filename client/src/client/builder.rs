@@ -1,17 +1,22 @@
 use std::sync::Arc;
+use std::time::Duration;
 
 use super::TonConnectionCallback;
 use crate::client::{
-    error, ConnectionCheck, MultiConnectionCallback, RetryStrategy, TonClient, TonConnectionParams,
-    LOGGING_CONNECTION_CALLBACK, NOOP_CONNECTION_CALLBACK,
+    error, ArchiveRoutingParams, BackoffRetryPolicy, CallerBudget, ConnectionCheck,
+    HealthCheckParams, MultiConnectionCallback, PoolOptions, PriorityWeights, RetryPolicy,
+    RetryStrategy, TonBackend, TonClient, TonConnectionParams, LOGGING_CONNECTION_CALLBACK,
+    NOOP_CONNECTION_CALLBACK,
 };
+use crate::config::TonNetwork;
 
 pub struct TonClientBuilder {
     pool_size: usize,
     connection_params: TonConnectionParams,
-    retry_strategy: RetryStrategy,
+    retry_policy: Arc<dyn RetryPolicy>,
     callback: Arc<dyn TonConnectionCallback>,
     connection_check: ConnectionCheck,
+    pool_options: PoolOptions,
 }
 
 impl TonClientBuilder {
@@ -19,9 +24,10 @@ impl TonClientBuilder {
         TonClientBuilder {
             pool_size: 1,
             connection_params: TonConnectionParams::default(),
-            retry_strategy: RetryStrategy::default(),
+            retry_policy: Arc::new(BackoffRetryPolicy::default()),
             callback: LOGGING_CONNECTION_CALLBACK.clone(),
             connection_check: ConnectionCheck::None,
+            pool_options: PoolOptions::default(),
         }
     }
 
@@ -40,8 +46,20 @@ impl TonClientBuilder {
         self
     }
 
+    /// Sets config and address testnet flag of `network`, see `TonNetwork` for loading custom networks.
+    pub fn with_network(&mut self, network: &TonNetwork) -> &mut Self {
+        self.connection_params.config = network.config();
+        self.connection_params.testnet = network.is_testnet();
+        self
+    }
+
     pub fn with_retry_strategy(&mut self, retry_strategy: &RetryStrategy) -> &mut Self {
-        self.retry_strategy = retry_strategy.clone();
+        self.retry_policy = Arc::new(retry_strategy.clone());
+        self
+    }
+
+    pub fn with_retry_policy(&mut self, retry_policy: Arc<dyn RetryPolicy>) -> &mut Self {
+        self.retry_policy = retry_policy;
         self
     }
 
@@ -80,13 +98,61 @@ impl TonClientBuilder {
         self
     }
 
+    /// Enables background probing of connections, unhealthy ones are reconnected.
+    pub fn with_health_check(&mut self, health_check: &HealthCheckParams) -> &mut Self {
+        self.pool_options.health_check = Some(health_check.clone());
+        self
+    }
+
+    /// Adds a sub-pool of archive connections serving queries for old state.
+    pub fn with_archive_routing(&mut self, archive_routing: &ArchiveRoutingParams) -> &mut Self {
+        self.pool_options.archive_routing = Some(archive_routing.clone());
+        self
+    }
+
+    /// Fails each attempt taking longer than `timeout` with `TonClientError::Timeout`.
+    ///
+    /// Timed out attempts are retried on another connection according to the retry policy.
+    pub fn with_request_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.pool_options.request_timeout = Some(timeout);
+        self
+    }
+
+    /// Sets shares of connection concurrency limit granted to request priorities under load.
+    pub fn with_priority_weights(&mut self, priority_weights: &PriorityWeights) -> &mut Self {
+        self.connection_params.priority_weights = priority_weights.clone();
+        self
+    }
+
+    /// Limits request rate of clients returned by `TonClient::with_caller(caller)`.
+    pub fn with_caller_budget(&mut self, caller: &str, budget: &CallerBudget) -> &mut Self {
+        self.pool_options
+            .caller_budgets
+            .insert(caller.to_string(), budget.clone());
+        self
+    }
+
+    pub fn with_backend(&mut self, backend: TonBackend) -> &mut Self {
+        self.connection_params.backend = backend;
+        self
+    }
+
+    /// Enables proof checks of lite server responses starting from `init_block` of the config.
+    ///
+    /// Tonlib backend always checks proofs, so it's used with `TonBackend::LiteApi` only.
+    pub fn with_proof_check(&mut self, proof_check: bool) -> &mut Self {
+        self.connection_params.proof_check = proof_check;
+        self
+    }
+
     pub async fn build(&self) -> Result<TonClient, error::TonClientError> {
-        TonClient::new(
+        TonClient::new_with_pools(
             self.pool_size,
             &self.connection_params,
-            self.retry_strategy.clone(),
+            self.retry_policy.clone(),
             self.callback.clone(),
             self.connection_check.clone(),
+            self.pool_options.clone(),
         )
         .await
     }
@@ -27,9 +27,10 @@ pub trait TonConnectionCallback: Send + Sync {
     ) {
     }
 
-    /// Method `on_cancelled_invoke` gets called when attempt to send an invoke result is failed  
+    /// Method `on_cancelled_invoke` gets called when an invoke is dropped before receiving its result  
     ///
-    /// Typically this happens when the corresponding future (async fn invoke_on_connection) is cancelled  
+    /// Typically this happens when the corresponding future (async fn invoke_on_connection) is cancelled,  
+    /// e.g. on timeout or by `CancellationToken`  
     fn on_cancelled_invoke(&self, tag: &str, request_id: u32, method: &str, duration: &Duration) {}
 
     /// Method `on_notification` gets called upon receiving valid notification from tonlib.
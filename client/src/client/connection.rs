@@ -1,39 +1,84 @@
+#[cfg(feature = "tonlib")]
 use std::collections::HashMap;
+use std::future::Future;
 use std::sync::atomic::{AtomicU32, Ordering};
-use std::sync::{Arc, Weak};
+use std::sync::Arc;
+#[cfg(feature = "tonlib")]
+use std::sync::Weak;
+#[cfg(feature = "tonlib")]
 use std::thread;
+#[cfg(feature = "tonlib")]
+use std::time::Duration;
 use std::time::Instant;
 
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
-use tokio::sync::{broadcast, oneshot, Mutex, Semaphore, SemaphorePermit};
+use tokio::sync::broadcast;
+#[cfg(feature = "tonlib")]
+use tokio::sync::oneshot;
+
+#[cfg(feature = "liteapi")]
+use ton_liteapi::tl::request::Request;
+#[cfg(feature = "liteapi")]
+use ton_liteapi::tl::response::Response;
 
 use crate::client::{
-    TonClientError, TonClientInterface, TonConnectionCallback, TonConnectionParams,
-    TonNotificationReceiver,
+    LimiterPermit, PriorityLimiter, RequestPriority, TonBackend, TonClientError,
+    TonClientInterface, TonConnectionCallback, TonConnectionParams, TonNotificationReceiver,
 };
+#[cfg(feature = "liteapi")]
+use crate::client::{LiteClient, LiteRequest};
 use crate::tl::{
-    BlockId, Config, KeyStoreType, Options, OptionsInfo, SmcRunResult, TlTonClient, TonFunction,
-    TonNotification, TonResult, TonResultDiscriminants, TvmStackEntry,
+    BlockId, SmcRunResult, TonFunction, TonNotification, TonResult, TonResultDiscriminants,
+    TvmStackEntry,
 };
+#[cfg(feature = "tonlib")]
+use crate::tl::{Config, KeyStoreType, Options, OptionsInfo, TlTonClient};
 use crate::types::TonMethodId;
 
 pub const DEFAULT_NOTIFICATION_QUEUE_LENGTH: usize = 10000;
 pub const DEFAULT_CONNECTION_CONCURRENCY_LIMIT: usize = 100;
 pub const DEFAULT_UPDATE_INIT_BLOCK: bool = true;
 
+#[cfg(feature = "tonlib")]
 struct RequestData {
     method: &'static str,
     send_time: Instant,
     sender: oneshot::Sender<Result<TonResult, TonClientError>>,
 }
 
-type RequestMap = Mutex<HashMap<u32, RequestData>>;
+/// Locked only for short non-async sections, so `PendingRequest` can always lock it on drop
+#[cfg(feature = "tonlib")]
+type RequestMap = std::sync::Mutex<HashMap<u32, RequestData>>;
+
+/// How long the run loop silently drops late responses to cancelled requests
+#[cfg(feature = "tonlib")]
+const CANCELLED_REQUEST_TTL: Duration = Duration::from_secs(300);
+
+/// Ids of cancelled tonlib requests, kept to tell their late responses from unknown results
+#[cfg(feature = "tonlib")]
+#[derive(Default)]
+struct CancelledRequests(std::sync::Mutex<HashMap<u32, Instant>>);
+
+#[cfg(feature = "tonlib")]
+impl CancelledRequests {
+    fn insert(&self, request_id: u32) {
+        let now = Instant::now();
+        let mut cancelled = self.0.lock().unwrap();
+        cancelled.retain(|_, time| now.duration_since(*time) < CANCELLED_REQUEST_TTL);
+        cancelled.insert(request_id, now);
+    }
+
+    fn remove(&self, request_id: u32) -> bool {
+        self.0.lock().unwrap().remove(&request_id).is_some()
+    }
+}
 type TonNotificationSender = broadcast::Sender<Arc<TonNotification>>;
 
 #[derive(Clone)]
 pub struct TonConnection {
     inner: Arc<Inner>,
+    priority: RequestPriority,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
@@ -45,12 +90,42 @@ pub enum ConnectionCheck {
 }
 
 struct Inner {
-    tl_client: TlTonClient,
+    tag: String,
+    backend: Backend,
     counter: AtomicU32,
-    request_map: RequestMap,
     notification_sender: TonNotificationSender,
     callback: Arc<dyn TonConnectionCallback>,
-    semaphore: Option<Semaphore>,
+    limiter: Option<Arc<PriorityLimiter>>,
+}
+
+enum Backend {
+    #[cfg(feature = "tonlib")]
+    Tonlib {
+        tl_client: TlTonClient,
+        request_map: RequestMap,
+        cancelled: CancelledRequests,
+        /// Direct connection to a lite server of the config, tonlib doesn't relay raw queries
+        #[cfg(feature = "liteapi")]
+        lite_peer: Option<LiteClient>,
+    },
+    #[cfg(feature = "liteapi")]
+    Lite(LiteClient),
+    Handler(Arc<dyn TonFunctionHandler>),
+}
+
+/// Answers `TonFunction`s in process, without tonlib or network access.
+#[async_trait]
+pub trait TonFunctionHandler: Send + Sync {
+    async fn handle(&self, function: &TonFunction) -> Result<TonResult, TonClientError>;
+
+    /// Answers raw lite server queries, unsupported by default
+    #[cfg(feature = "liteapi")]
+    async fn handle_lite_query(&self, request: &Request) -> Result<Response, TonClientError> {
+        Err(TonClientError::InternalError(format!(
+            "Lite server query {:?} is not supported by handler",
+            request
+        )))
+    }
 }
 
 static CONNECTION_COUNTER: AtomicU32 = AtomicU32::new(0);
@@ -68,6 +143,7 @@ impl TonConnection {
         }
     }
 
+    #[cfg(feature = "tonlib")]
     async fn init(&self, params: &TonConnectionParams) -> Result<OptionsInfo, TonClientError> {
         let keystore_type = match &params.keystore_dir {
             Some(keystore) => KeyStoreType::Directory {
@@ -96,6 +172,50 @@ impl TonConnection {
         }
     }
 
+    #[cfg(feature = "liteapi")]
+    pub(crate) fn from_lite_client(
+        lite_client: LiteClient,
+        callback: Arc<dyn TonConnectionCallback>,
+        notification_sender: TonNotificationSender,
+        limiter: Option<Arc<PriorityLimiter>>,
+    ) -> TonConnection {
+        let conn_id = CONNECTION_COUNTER.fetch_add(1, Ordering::Relaxed);
+        let inner = Inner {
+            tag: format!("ton-lite-conn-{conn_id}"),
+            backend: Backend::Lite(lite_client),
+            counter: AtomicU32::new(0),
+            notification_sender,
+            callback,
+            limiter,
+        };
+        TonConnection {
+            inner: Arc::new(inner),
+            priority: RequestPriority::default(),
+        }
+    }
+
+    /// Creates a connection serving all invocations with `handler`
+    pub fn from_handler(
+        handler: Arc<dyn TonFunctionHandler>,
+        callback: Arc<dyn TonConnectionCallback>,
+    ) -> TonConnection {
+        let conn_id = CONNECTION_COUNTER.fetch_add(1, Ordering::Relaxed);
+        let (notification_sender, _rcv) =
+            broadcast::channel::<Arc<TonNotification>>(DEFAULT_NOTIFICATION_QUEUE_LENGTH);
+        let inner = Inner {
+            tag: format!("ton-handler-conn-{conn_id}"),
+            backend: Backend::Handler(handler),
+            counter: AtomicU32::new(0),
+            notification_sender,
+            callback,
+            limiter: None,
+        };
+        TonConnection {
+            inner: Arc::new(inner),
+            priority: RequestPriority::default(),
+        }
+    }
+
     pub fn subscribe(&self) -> TonNotificationReceiver {
         self.inner.notification_sender.subscribe()
     }
@@ -121,42 +241,300 @@ impl TonConnection {
         }
     }
 
-    async fn limit_rate(&self) -> Result<Option<SemaphorePermit<'_>>, TonClientError> {
-        match &self.inner.semaphore {
-            Some(semaphore) => {
-                let permit = semaphore.acquire().await.map_err(|_| {
-                    TonClientError::InternalError("Failed to acquire semaphore permit".to_string())
-                })?;
-                Ok(Some(permit))
+    /// Returns a handle to this connection queuing its requests with `priority`
+    pub fn with_priority(&self, priority: RequestPriority) -> TonConnection {
+        TonConnection {
+            inner: self.inner.clone(),
+            priority,
+        }
+    }
+
+    pub fn priority(&self) -> RequestPriority {
+        self.priority
+    }
+
+    /// Sends a raw request to the lite server and returns its response.
+    ///
+    /// Tonlib connections send queries over a separate connection to a lite server
+    /// of the config, which is not necessarily the one tonlib is connected to.
+    /// Lite server errors are reported as `TonClientError::TonlibError`.
+    #[cfg(feature = "liteapi")]
+    pub async fn lite_query(&self, request: Request) -> Result<Response, TonClientError> {
+        self.lite_query_method("liteServer.query", request).await
+    }
+
+    /// Sends a typed request to the lite server and returns its typed response.
+    #[cfg(feature = "liteapi")]
+    pub async fn lite_request<R: LiteRequest>(
+        &self,
+        request: R,
+    ) -> Result<R::Response, TonClientError> {
+        let response = self
+            .lite_query_method(R::METHOD, request.into_request())
+            .await?;
+        R::from_response(response)
+    }
+
+    #[cfg(feature = "liteapi")]
+    async fn lite_query_method(
+        &self,
+        method: &'static str,
+        request: Request,
+    ) -> Result<Response, TonClientError> {
+        let _permit = self.limit_rate().await?;
+        let response = match &self.inner.backend {
+            #[cfg(feature = "tonlib")]
+            Backend::Tonlib {
+                lite_peer: Some(lite_client),
+                ..
+            } => {
+                return lite_client.execute(method, request, None).await;
+            }
+            Backend::Lite(lite_client) => {
+                return lite_client.execute(method, request, None).await;
+            }
+            #[cfg(feature = "tonlib")]
+            Backend::Tonlib {
+                lite_peer: None, ..
+            } => {
+                return Err(TonClientError::InternalError(format!(
+                    "No lite server available for {} on {}",
+                    method, self.inner.tag
+                )));
             }
+            Backend::Handler(handler) => handler.handle_lite_query(&request).await?,
+        };
+        match response {
+            Response::Error(e) => Err(TonClientError::TonlibError {
+                method,
+                code: e.code,
+                message: e.message.to_string(),
+            }),
+            r => Ok(r),
+        }
+    }
+
+    async fn limit_rate(&self) -> Result<Option<LimiterPermit>, TonClientError> {
+        match &self.inner.limiter {
+            Some(limiter) => Ok(Some(limiter.acquire(self.priority).await?)),
             None => Ok(None),
         }
     }
+
+    /// Invokes a backend that answers in the calling task instead of the run loop
+    async fn invoke_in_process<F>(
+        &self,
+        cnt: u32,
+        function: &TonFunction,
+        invoke: F,
+    ) -> Result<(TonConnection, TonResult), TonClientError>
+    where
+        F: Future<Output = Result<TonResult, TonClientError>>,
+    {
+        let tag = self.inner.tag.as_str();
+        let method: &'static str = function.into();
+        let send_time = Instant::now();
+        self.inner.callback.on_invoke(tag, cnt, function);
+        let result = invoke.await;
+        let duration = send_time.elapsed();
+        self.inner
+            .callback
+            .on_invoke_result(tag, cnt, method, &duration, &result);
+        result.map(|r| (self.clone(), r))
+    }
+
+    async fn invoke_request(
+        &self,
+        cnt: u32,
+        function: &TonFunction,
+    ) -> Result<(TonConnection, TonResult), TonClientError> {
+        match &self.inner.backend {
+            #[cfg(feature = "tonlib")]
+            Backend::Tonlib {
+                tl_client,
+                request_map,
+                cancelled,
+                ..
+            } => {
+                self.invoke_tonlib(cnt, function, tl_client, request_map, cancelled)
+                    .await
+            }
+            #[cfg(feature = "liteapi")]
+            Backend::Lite(lite_client) => {
+                self.invoke_in_process(cnt, function, lite_client.invoke(function))
+                    .await
+            }
+            Backend::Handler(handler) => {
+                self.invoke_in_process(cnt, function, handler.handle(function))
+                    .await
+            }
+        }
+    }
+
+    /// Sends the request to tonlib and waits for the run loop to deliver the response
+    #[cfg(feature = "tonlib")]
+    async fn invoke_tonlib(
+        &self,
+        cnt: u32,
+        function: &TonFunction,
+        tl_client: &TlTonClient,
+        request_map: &RequestMap,
+        cancelled: &CancelledRequests,
+    ) -> Result<(TonConnection, TonResult), TonClientError> {
+        let tag = self.inner.tag.as_str();
+        let extra = cnt.to_string();
+        let (tx, rx) = oneshot::channel::<Result<TonResult, TonClientError>>();
+        let data = RequestData {
+            method: function.into(),
+            send_time: Instant::now(),
+            sender: tx,
+        };
+        request_map.lock().unwrap().insert(cnt, data);
+        let _pending = PendingRequest {
+            inner: &self.inner,
+            request_map,
+            cancelled,
+            request_id: cnt,
+        };
+        self.inner.callback.on_invoke(tag, cnt, function);
+
+        let res = tl_client.send(function, extra.as_str());
+        if let Err(e) = res {
+            let data = request_map.lock().unwrap().remove(&cnt).unwrap();
+            let duration = data.send_time.elapsed();
+            let res = Err(TonClientError::TlError(e));
+            self.inner
+                .callback
+                .on_invoke_result(tag, cnt, data.method, &duration, &res);
+            data.sender.send(res).unwrap(); // Send should always succeed, so something went terribly wrong
+        }
+        let maybe_result = rx.await;
+        let result = match maybe_result {
+            Ok(result) => result,
+            Err(_) => {
+                return Err(TonClientError::InternalError(
+                    "Sender dropped without sending".to_string(),
+                ));
+            }
+        };
+        result.map(|r| (self.clone(), r))
+    }
 }
 
 async fn new_connection(
     params: &TonConnectionParams,
     callback: Arc<dyn TonConnectionCallback>,
 ) -> Result<TonConnection, TonClientError> {
-    let conn_id = CONNECTION_COUNTER.fetch_add(1, Ordering::Relaxed);
-    let tag = format!("ton-conn-{conn_id}");
+    match params.backend {
+        #[cfg(feature = "tonlib")]
+        TonBackend::Tonlib => new_tonlib_connection(params, callback).await,
+        #[cfg(not(feature = "tonlib"))]
+        TonBackend::Tonlib => Err(TonClientError::InvalidArgument(
+            "Feature 'tonlib' is disabled, tonlib backend is not available".to_string(),
+        )),
+        TonBackend::LiteApi => new_lite_connection(params, callback),
+    }
+}
 
+#[cfg(feature = "liteapi")]
+fn new_lite_connection(
+    params: &TonConnectionParams,
+    callback: Arc<dyn TonConnectionCallback>,
+) -> Result<TonConnection, TonClientError> {
+    use rand::seq::IndexedRandom;
+
+    use crate::config::TonConfig;
+
+    let config = TonConfig::from_json(&params.config)
+        .map_err(|e| TonClientError::InvalidArgument(format!("Fail to parse config: {}", e)))?;
+    let endpoint = config
+        .liteservers
+        .choose(&mut rand::rng())
+        .ok_or_else(|| TonClientError::InvalidArgument("No liteservers in config".to_string()))?;
+    let trusted_key_block = if params.proof_check {
+        let block = config.get_init_block().map_err(|e| {
+            TonClientError::InvalidArgument(format!("Fail to parse init_block: {}", e))
+        })?;
+        Some(block)
+    } else {
+        None
+    };
+    let lite_client = LiteClient::from_endpoint(endpoint, trusted_key_block.as_ref())?
+        .with_testnet(params.testnet);
     let (sender, _rcv) =
         broadcast::channel::<Arc<TonNotification>>(params.notification_queue_length);
+    Ok(TonConnection::from_lite_client(
+        lite_client,
+        callback,
+        sender,
+        new_limiter(params),
+    ))
+}
+
+#[cfg(not(feature = "liteapi"))]
+fn new_lite_connection(
+    _params: &TonConnectionParams,
+    _callback: Arc<dyn TonConnectionCallback>,
+) -> Result<TonConnection, TonClientError> {
+    Err(TonClientError::InvalidArgument(
+        "Feature 'liteapi' is disabled, lite backend is not available".to_string(),
+    ))
+}
+
+/// Picks a lite server for raw queries of a tonlib connection, connecting lazily
+#[cfg(all(feature = "liteapi", feature = "tonlib"))]
+fn new_lite_peer(params: &TonConnectionParams) -> Option<LiteClient> {
+    use rand::seq::IndexedRandom;
 
-    let semaphore = if params.concurrency_limit != 0 {
-        Some(Semaphore::new(params.concurrency_limit))
+    use crate::config::TonConfig;
+
+    let config = TonConfig::from_json(&params.config).ok()?;
+    let endpoint = config.liteservers.choose(&mut rand::rng())?;
+    match LiteClient::from_endpoint(endpoint, None) {
+        Ok(lite_client) => Some(lite_client),
+        Err(e) => {
+            log::warn!("Raw lite server queries are not available: {}", e);
+            None
+        }
+    }
+}
+
+#[cfg_attr(not(any(feature = "tonlib", feature = "liteapi")), allow(dead_code))]
+fn new_limiter(params: &TonConnectionParams) -> Option<Arc<PriorityLimiter>> {
+    if params.concurrency_limit != 0 {
+        Some(Arc::new(PriorityLimiter::new(
+            params.concurrency_limit,
+            &params.priority_weights,
+        )))
     } else {
         None
-    };
+    }
+}
+
+#[cfg(feature = "tonlib")]
+async fn new_tonlib_connection(
+    params: &TonConnectionParams,
+    callback: Arc<dyn TonConnectionCallback>,
+) -> Result<TonConnection, TonClientError> {
+    let conn_id = CONNECTION_COUNTER.fetch_add(1, Ordering::Relaxed);
+    let tag = format!("ton-conn-{conn_id}");
+
+    let (sender, _rcv) =
+        broadcast::channel::<Arc<TonNotification>>(params.notification_queue_length);
 
     let inner = Inner {
-        tl_client: TlTonClient::new(tag.clone()),
+        tag: tag.clone(),
+        backend: Backend::Tonlib {
+            tl_client: TlTonClient::new(tag.clone()),
+            request_map: std::sync::Mutex::new(HashMap::new()),
+            cancelled: CancelledRequests::default(),
+            #[cfg(feature = "liteapi")]
+            lite_peer: new_lite_peer(params),
+        },
         counter: AtomicU32::new(0),
-        request_map: Mutex::new(HashMap::new()),
         notification_sender: sender,
         callback,
-        semaphore,
+        limiter: new_limiter(params),
     };
     let inner_arc = Arc::new(inner);
     let inner_weak: Weak<Inner> = Arc::downgrade(&inner_arc);
@@ -164,7 +542,10 @@ async fn new_connection(
     let callback = inner_arc.callback.clone();
     let _join_handle = thread_builder.spawn(|| run_loop(tag, inner_weak, callback))?;
 
-    let conn = TonConnection { inner: inner_arc };
+    let conn = TonConnection {
+        inner: inner_arc,
+        priority: RequestPriority::default(),
+    };
     let _info = conn.init(params).await?;
 
     Ok(conn)
@@ -224,57 +605,82 @@ impl TonClientInterface for TonConnection {
         &self,
         function: &TonFunction,
     ) -> Result<(TonConnection, TonResult), TonClientError> {
-        // TODO 2025.04.25 Sild it doesn't work because permit is dropped right after the call,
-        // But it requires more investigation to understand if fix won't break anything else
-        self.limit_rate().await?; // take the semaphore to limit number of simultaneous invokes being processed
+        // hold the permit to limit number of simultaneous invokes being processed
+        let _permit = self.limit_rate().await?;
 
         let cnt = self.inner.counter.fetch_add(1, Ordering::Relaxed);
+        let invoke = self.invoke_request(cnt, function);
+        #[cfg(feature = "tracing")]
+        let invoke = tracing::Instrument::instrument(
+            invoke,
+            tracing::debug_span!(
+                "ton_invoke",
+                tag = self.inner.tag.as_str(),
+                request_id = cnt,
+                method = <&'static str>::from(function),
+            ),
+        );
+        invoke.await
+    }
+}
 
-        let extra = cnt.to_string();
-        let (tx, rx) = oneshot::channel::<Result<TonResult, TonClientError>>();
-        let data = RequestData {
-            method: function.into(),
-            send_time: Instant::now(),
-            sender: tx,
-        };
-        self.inner.request_map.lock().await.insert(cnt, data);
-        self.inner
-            .callback
-            .on_invoke(self.inner.tl_client.get_tag(), cnt, function);
+/// Releases a pending tonlib request if the invoke future is dropped before the response.
+///
+/// Tonlib API has no function to abort a query: it keeps running and its late response
+/// is discarded by the run loop.
+#[cfg(feature = "tonlib")]
+struct PendingRequest<'a> {
+    inner: &'a Inner,
+    request_map: &'a RequestMap,
+    cancelled: &'a CancelledRequests,
+    request_id: u32,
+}
 
-        let res = self.inner.tl_client.send(function, extra.as_str());
-        if let Err(e) = res {
-            let data = self.inner.request_map.lock().await.remove(&cnt).unwrap();
-            let tag = self.inner.tl_client.get_tag();
+#[cfg(feature = "tonlib")]
+impl Drop for PendingRequest<'_> {
+    fn drop(&mut self) {
+        // the run loop has already taken the data if it's replying to the request
+        let maybe_data = self
+            .request_map
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&self.request_id);
+        if let Some(data) = maybe_data {
+            self.cancelled.insert(self.request_id);
+            let tag = self.inner.tag.as_str();
             let duration = data.send_time.elapsed();
-            let res = Err(TonClientError::TlError(e));
-            self.inner
-                .callback
-                .on_invoke_result(tag, cnt, data.method, &duration, &res);
-            data.sender.send(res).unwrap(); // Send should always succeed, so something went terribly wrong
+            let result = Err(TonClientError::Cancelled {
+                method: data.method,
+            });
+            let callback = &self.inner.callback;
+            callback.on_invoke_result(tag, self.request_id, data.method, &duration, &result);
+            callback.on_cancelled_invoke(tag, self.request_id, data.method, &duration);
         }
-        let maybe_result = rx.await;
-        let result = match maybe_result {
-            Ok(result) => result,
-            Err(_) => {
-                return Err(TonClientError::InternalError(
-                    "Sender dropped without sending".to_string(),
-                ));
-            }
-        };
-        result.map(|r| (self.clone(), r))
     }
 }
 
+#[cfg(feature = "tonlib")]
 static NOT_AVAILABLE: &str = "N/A";
 
 /// Client run loop
+#[cfg(feature = "tonlib")]
 fn run_loop(tag: String, weak_inner: Weak<Inner>, callback: Arc<dyn TonConnectionCallback>) {
     callback.on_connection_loop_start(&tag);
 
     loop {
         if let Some(inner) = weak_inner.upgrade() {
-            let recv = inner.tl_client.receive(1.0);
+            let (tl_client, request_map, cancelled) = match &inner.backend {
+                Backend::Tonlib {
+                    tl_client,
+                    request_map,
+                    cancelled,
+                    ..
+                } => (tl_client, request_map, cancelled),
+                #[cfg(feature = "liteapi")]
+                Backend::Lite(_) => break,
+                Backend::Handler(_) => break,
+            };
+            let recv = tl_client.receive(1.0);
             if let Some((ton_result, maybe_extra)) = recv {
                 let maybe_request_id = if let Some(s) = &maybe_extra {
                     s.parse::<u32>().ok()
@@ -282,7 +688,7 @@ fn run_loop(tag: String, weak_inner: Weak<Inner>, callback: Arc<dyn TonConnectio
                     None
                 };
                 let maybe_data =
-                    maybe_request_id.and_then(|i| inner.request_map.blocking_lock().remove(&i));
+                    maybe_request_id.and_then(|i| request_map.lock().unwrap().remove(&i));
                 let result: Result<TonResult, TonClientError> = match ton_result {
                     Ok(TonResult::Error { code, message }) => {
                         let method = maybe_data
@@ -309,6 +715,8 @@ fn run_loop(tag: String, weak_inner: Weak<Inner>, callback: Arc<dyn TonConnectio
                     if data.sender.send(result).is_err() {
                         callback.on_cancelled_invoke(&tag, request_id, data.method, &duration);
                     }
+                } else if maybe_request_id.is_some_and(|id| cancelled.remove(id)) {
+                    // Late response to a cancelled request, already reported on cancellation
                 } else {
                     // No request data, attempt to parse notification. Errors are ignored here.
                     if let Ok(r) = result {
@@ -332,3 +740,55 @@ fn run_loop(tag: String, weak_inner: Weak<Inner>, callback: Arc<dyn TonConnectio
         }
     }
 }
+
+#[cfg(all(test, feature = "tonlib"))]
+mod tests {
+    use std::sync::atomic::AtomicUsize;
+
+    use super::*;
+    use crate::client::mock::MockResponses;
+
+    #[derive(Default)]
+    struct CancelCounter(AtomicUsize);
+
+    impl TonConnectionCallback for CancelCounter {
+        fn on_cancelled_invoke(&self, _: &str, _: u32, _: &str, _: &Duration) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn test_pending_request_dropped_while_map_is_locked() {
+        let callback = Arc::new(CancelCounter::default());
+        let conn =
+            TonConnection::from_handler(Arc::new(MockResponses::default()), callback.clone());
+        let request_map = RequestMap::default();
+        let cancelled = CancelledRequests::default();
+        let (sender, _receiver) = oneshot::channel();
+        let data = RequestData {
+            method: "test",
+            send_time: Instant::now(),
+            sender,
+        };
+        request_map.lock().unwrap().insert(7, data);
+        let pending = PendingRequest {
+            inner: &conn.inner,
+            request_map: &request_map,
+            cancelled: &cancelled,
+            request_id: 7,
+        };
+
+        // another invoker holds the lock while the pending request is dropped
+        let guard = request_map.lock().unwrap();
+        thread::scope(|scope| {
+            let dropping = scope.spawn(move || drop(pending));
+            thread::sleep(Duration::from_millis(50));
+            assert!(!dropping.is_finished());
+            drop(guard);
+        });
+
+        assert!(request_map.lock().unwrap().is_empty());
+        assert!(cancelled.remove(7));
+        assert_eq!(callback.0.load(Ordering::Relaxed), 1);
+    }
+}
@@ -1,10 +1,12 @@
 use std::io;
+use std::time::Duration;
 
 use thiserror::Error;
+use tonlib_core::cell::TonCellError;
 use tonlib_core::types::TonHashParseError;
-use tonlib_core::TonAddressParseError;
+use tonlib_core::{TonAddressParseError, TonHash};
 
-use crate::tl::{TlError, TonResult, TonResultDiscriminants};
+use crate::tl::{BlockIdExt, TlError, TonResult, TonResultDiscriminants};
 
 #[derive(Error, Debug)]
 pub enum TonClientError {
@@ -38,9 +40,181 @@ pub enum TonClientError {
 
     #[error("TonHash parse error ({0})")]
     TonHashParseError(#[from] TonHashParseError),
+
+    #[error("TonCellError ({0})")]
+    TonCellError(#[from] TonCellError),
+
+    #[error("Timeout (Method: {method}, timeout: {timeout:?})")]
+    Timeout {
+        method: &'static str,
+        timeout: Duration,
+    },
+
+    #[error("Cancelled (Method: {method})")]
+    Cancelled { method: &'static str },
+
+    #[error("Proof check failed ({0})")]
+    ProofError(#[from] TonProofError),
+}
+
+/// Failure of lite server response verification.
+#[derive(Error, Debug)]
+pub enum TonProofError {
+    #[error("Invalid proof ({0})")]
+    InvalidProof(String),
+
+    #[error("Hash mismatch in {what} (expected: {expected}, actual: {actual})")]
+    HashMismatch {
+        what: &'static str,
+        expected: TonHash,
+        actual: TonHash,
+    },
+
+    #[error("Block mismatch (expected: {expected:?}, actual: {actual:?})")]
+    BlockMismatch {
+        expected: Box<BlockIdExt>,
+        actual: Box<BlockIdExt>,
+    },
+
+    #[error("Unknown validator {0}")]
+    UnknownValidator(TonHash),
+
+    #[error("Invalid signature of validator {0}")]
+    InvalidSignature(TonHash),
+
+    #[error("Insufficient signatures (signed weight: {signed}, total weight: {total})")]
+    InsufficientSignatures { signed: u64, total: u64 },
+
+    #[error("Transaction mismatch at lt {lt} (expected: {expected}, actual: {actual})")]
+    TransactionMismatch {
+        lt: i64,
+        expected: TonHash,
+        actual: TonHash,
+    },
+}
+
+impl From<TonCellError> for TonProofError {
+    fn from(e: TonCellError) -> Self {
+        TonProofError::InvalidProof(e.to_string())
+    }
+}
+
+/// Known kinds of tonlib and lite server errors, parsed from `TonClientError::TonlibError`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TonlibErrorKind {
+    /// Lite server isn't synced yet (`LITE_SERVER_NOTREADY`)
+    NotReady,
+    Timeout,
+    Cancelled,
+    RateLimited,
+    /// Lite server failed to load block from its database
+    CannotLoadBlock,
+    /// Block is not yet applied by the lite server
+    BlockNotApplied,
+    /// Block is absent in the lite server database, e.g. it's too old
+    BlockNotInDb,
+    /// State is already garbage collected by the lite server
+    StateGarbageCollected,
+    AccountNotFound,
+    TransactionNotFound,
+    TransactionHashMismatch,
+    NotFound,
+    InvalidArgument,
+    Other,
+}
+
+// Lite server error codes, see `ton/common/errorcode.h`
+const LITE_SERVER_NOT_READY: i32 = 651;
+const LITE_SERVER_TIMEOUT: i32 = 652;
+const LITE_SERVER_CANCELLED: i32 = 653;
+
+impl TonlibErrorKind {
+    pub fn parse(code: i32, message: &str) -> TonlibErrorKind {
+        let message = message.to_lowercase();
+        let contains = |patterns: &[&str]| patterns.iter().any(|p| message.contains(p));
+        if code == LITE_SERVER_NOT_READY || contains(&["lite_server_notready"]) {
+            TonlibErrorKind::NotReady
+        } else if code == LITE_SERVER_TIMEOUT || contains(&["timeout", "timed out"]) {
+            TonlibErrorKind::Timeout
+        } else if code == LITE_SERVER_CANCELLED || contains(&["cancelled", "canceled"]) {
+            TonlibErrorKind::Cancelled
+        } else if code == 429 || contains(&["rate limit", "ratelimit", "too many requests"]) {
+            TonlibErrorKind::RateLimited
+        } else if contains(&["state already gc"]) {
+            TonlibErrorKind::StateGarbageCollected
+        } else if contains(&["not in db"]) {
+            TonlibErrorKind::BlockNotInDb
+        } else if contains(&["cannot load block"]) {
+            TonlibErrorKind::CannotLoadBlock
+        } else if contains(&["block is not applied", "block not applied"]) {
+            TonlibErrorKind::BlockNotApplied
+        } else if contains(&["transaction hash mismatch"]) {
+            TonlibErrorKind::TransactionHashMismatch
+        } else if contains(&["account not found", "account state not found"]) {
+            TonlibErrorKind::AccountNotFound
+        } else if contains(&["transaction not found", "cannot locate transaction"]) {
+            TonlibErrorKind::TransactionNotFound
+        } else if contains(&["not found"]) {
+            TonlibErrorKind::NotFound
+        } else if code == 400 || contains(&["invalid"]) {
+            TonlibErrorKind::InvalidArgument
+        } else {
+            TonlibErrorKind::Other
+        }
+    }
 }
 
 impl TonClientError {
+    /// Returns the kind of `TonlibError`, `None` for other errors
+    pub fn tonlib_error_kind(&self) -> Option<TonlibErrorKind> {
+        match self {
+            TonClientError::TonlibError { code, message, .. } => {
+                Some(TonlibErrorKind::parse(*code, message))
+            }
+            _ => None,
+        }
+    }
+
+    /// Checks if the error is transient and the request may succeed on retry
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            TonClientError::TonlibError { code, .. } => match self.tonlib_error_kind() {
+                Some(
+                    TonlibErrorKind::NotReady
+                    | TonlibErrorKind::Timeout
+                    | TonlibErrorKind::Cancelled
+                    | TonlibErrorKind::RateLimited
+                    | TonlibErrorKind::CannotLoadBlock
+                    | TonlibErrorKind::BlockNotApplied,
+                ) => true,
+                Some(TonlibErrorKind::Other) => *code == 500,
+                _ => false,
+            },
+            TonClientError::Io(_) | TonClientError::Timeout { .. } => true,
+            _ => false,
+        }
+    }
+
+    pub fn is_not_found(&self) -> bool {
+        matches!(
+            self.tonlib_error_kind(),
+            Some(
+                TonlibErrorKind::AccountNotFound
+                    | TonlibErrorKind::TransactionNotFound
+                    | TonlibErrorKind::BlockNotInDb
+                    | TonlibErrorKind::NotFound
+            )
+        )
+    }
+
+    /// Checks if the request may succeed on an archive node
+    pub fn is_archive_required(&self) -> bool {
+        matches!(
+            self.tonlib_error_kind(),
+            Some(TonlibErrorKind::StateGarbageCollected | TonlibErrorKind::BlockNotInDb)
+        )
+    }
+
     pub fn unexpected_ton_result(
         expected: TonResultDiscriminants,
         actual: TonResult,
@@ -51,3 +225,86 @@ impl TonClientError {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tonlib_error(code: i32, message: &str) -> TonClientError {
+        TonClientError::TonlibError {
+            method: "RawGetAccountState",
+            code,
+            message: message.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_tonlib_error_kind() {
+        let cases = [
+            (500, "LITE_SERVER_NOTREADY", TonlibErrorKind::NotReady),
+            (651, "block is not ready", TonlibErrorKind::NotReady),
+            (500, "adnl query timeout", TonlibErrorKind::Timeout),
+            (500, "Ratelimit exceeded", TonlibErrorKind::RateLimited),
+            (
+                500,
+                "LITE_SERVER_UNKNOWN: state already gc'd",
+                TonlibErrorKind::StateGarbageCollected,
+            ),
+            (
+                500,
+                "LITE_SERVER_UNKNOWN: block is not in db",
+                TonlibErrorKind::BlockNotInDb,
+            ),
+            (
+                500,
+                "cannot load block (-1,8000000000000000,1)",
+                TonlibErrorKind::CannotLoadBlock,
+            ),
+            (
+                500,
+                "transaction hash mismatch",
+                TonlibErrorKind::TransactionHashMismatch,
+            ),
+            (500, "account not found", TonlibErrorKind::AccountNotFound),
+            (
+                400,
+                "INVALID_ACCOUNT_ADDRESS",
+                TonlibErrorKind::InvalidArgument,
+            ),
+            (500, "something went wrong", TonlibErrorKind::Other),
+        ];
+        for (code, message, kind) in cases {
+            assert_eq!(TonlibErrorKind::parse(code, message), kind, "{}", message);
+        }
+        assert_eq!(
+            TonClientError::InternalError("".to_string()).tonlib_error_kind(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_error_helpers() {
+        assert!(tonlib_error(500, "LITE_SERVER_NOTREADY").is_retryable());
+        assert!(tonlib_error(0, "cannot load block (-1,8000000000000000,1)").is_retryable());
+        assert!(tonlib_error(500, "something went wrong").is_retryable());
+        assert!(!tonlib_error(400, "INVALID_ACCOUNT_ADDRESS").is_retryable());
+        assert!(!tonlib_error(500, "account not found").is_retryable());
+        assert!(!TonClientError::InvalidArgument("".to_string()).is_retryable());
+        let timeout = TonClientError::Timeout {
+            method: "RawGetAccountState",
+            timeout: Duration::from_secs(1),
+        };
+        assert!(timeout.is_retryable());
+        assert!(!TonClientError::Cancelled {
+            method: "RawGetAccountState"
+        }
+        .is_retryable());
+
+        assert!(tonlib_error(500, "account not found").is_not_found());
+        assert!(!tonlib_error(500, "LITE_SERVER_NOTREADY").is_not_found());
+
+        assert!(tonlib_error(500, "state already gc'd").is_archive_required());
+        assert!(tonlib_error(500, "block is not in db").is_archive_required());
+        assert!(!tonlib_error(500, "account not found").is_archive_required());
+    }
+}
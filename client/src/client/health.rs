@@ -0,0 +1,270 @@
+use std::sync::Mutex;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::client::{TonClientError, TonlibErrorKind};
+use crate::tl::TonResult;
+
+const EWMA_ALPHA: f64 = 0.1;
+/// Score penalty for each masterchain block a connection lags behind the pool
+const SEQNO_LAG_PENALTY_MS: f64 = 1000.0;
+/// Score penalty for error rate of 1.0
+const ERROR_RATE_PENALTY_MS: f64 = 10000.0;
+/// Error rate isn't taken into account until a connection served that many requests
+const MIN_REQUESTS_FOR_ERROR_RATE: u64 = 10;
+
+/// Parameters of the background health check of `TonClient` connections.
+///
+/// Every `probe_interval_ms` each connection is probed with `get_masterchain_info`.
+/// Connections exceeding any of the limits are ejected and reconnected.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct HealthCheckParams {
+    pub probe_interval_ms: u64,
+    pub probe_timeout_ms: u64,
+    pub max_seqno_lag: i32,
+    pub max_error_rate: f64,
+    pub max_consecutive_failures: u32,
+}
+
+impl Default for HealthCheckParams {
+    fn default() -> Self {
+        HealthCheckParams {
+            probe_interval_ms: 10000,
+            probe_timeout_ms: 5000,
+            max_seqno_lag: 10,
+            max_error_rate: 0.5,
+            max_consecutive_failures: 3,
+        }
+    }
+}
+
+/// Health snapshot of a single connection of `TonClient`.
+///
+/// `score` combines latency, error rate and seqno lag, lower is better.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConnectionScore {
+    pub index: usize,
+    pub latency_ms: f64,
+    pub error_rate: f64,
+    pub last_seqno: Option<i32>,
+    pub seqno_lag: i32,
+    pub not_ready_count: u64,
+    pub consecutive_failures: u32,
+    pub requests: u64,
+    pub score: f64,
+}
+
+impl ConnectionScore {
+    pub fn is_healthy(&self, params: &HealthCheckParams) -> bool {
+        let error_rate_exceeded =
+            self.requests >= MIN_REQUESTS_FOR_ERROR_RATE && self.error_rate > params.max_error_rate;
+        self.consecutive_failures < params.max_consecutive_failures
+            && self.seqno_lag <= params.max_seqno_lag
+            && !error_rate_exceeded
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct ConnectionHealth {
+    state: Mutex<HealthState>,
+}
+
+#[derive(Default, Clone)]
+struct HealthState {
+    latency_ms: Option<f64>,
+    error_rate: f64,
+    last_seqno: Option<i32>,
+    not_ready_count: u64,
+    consecutive_failures: u32,
+    requests: u64,
+}
+
+impl ConnectionHealth {
+    pub(crate) fn record(&self, duration: &Duration, result: &Result<TonResult, TonClientError>) {
+        let mut state = self.state.lock().unwrap();
+        let latency_ms = duration.as_secs_f64() * 1000.0;
+        state.latency_ms = Some(match state.latency_ms {
+            Some(avg) => ewma(avg, latency_ms),
+            None => latency_ms,
+        });
+        state.requests += 1;
+        let failed = match result {
+            Ok(TonResult::BlocksMasterchainInfo(info)) => {
+                state.last_seqno = state.last_seqno.max(Some(info.last.seqno));
+                false
+            }
+            Ok(_) => false,
+            Err(e) => {
+                if e.tonlib_error_kind() == Some(TonlibErrorKind::NotReady) {
+                    state.not_ready_count += 1;
+                }
+                is_connection_failure(e)
+            }
+        };
+        state.error_rate = ewma(state.error_rate, if failed { 1.0 } else { 0.0 });
+        if failed {
+            state.consecutive_failures += 1;
+        } else {
+            state.consecutive_failures = 0;
+        }
+    }
+
+    pub(crate) fn last_seqno(&self) -> Option<i32> {
+        self.state.lock().unwrap().last_seqno
+    }
+
+    pub(crate) fn reset(&self) {
+        *self.state.lock().unwrap() = HealthState::default();
+    }
+
+    pub(crate) fn score(&self, index: usize, max_seqno: Option<i32>) -> ConnectionScore {
+        let state = self.state.lock().unwrap().clone();
+        let seqno_lag = match (max_seqno, state.last_seqno) {
+            (Some(max), Some(last)) => max - last,
+            _ => 0,
+        };
+        let latency_ms = state.latency_ms.unwrap_or(0.0);
+        let error_rate = if state.requests >= MIN_REQUESTS_FOR_ERROR_RATE {
+            state.error_rate
+        } else {
+            0.0
+        };
+        let score = latency_ms
+            + error_rate * ERROR_RATE_PENALTY_MS
+            + seqno_lag as f64 * SEQNO_LAG_PENALTY_MS
+            + state.consecutive_failures as f64 * ERROR_RATE_PENALTY_MS;
+        ConnectionScore {
+            index,
+            latency_ms,
+            error_rate: state.error_rate,
+            last_seqno: state.last_seqno,
+            seqno_lag,
+            not_ready_count: state.not_ready_count,
+            consecutive_failures: state.consecutive_failures,
+            requests: state.requests,
+            score,
+        }
+    }
+}
+
+fn ewma(avg: f64, value: f64) -> f64 {
+    avg + EWMA_ALPHA * (value - avg)
+}
+
+/// Errors caused by the lite server or transport rather than by the request itself.
+///
+/// Tonlib reports most errors with code 500, so `TonlibError` is classified by its kind:
+/// only timeouts and unrecognized errors count, while e.g. garbage collected states or
+/// missing blocks are answers about the requested data.
+fn is_connection_failure(error: &TonClientError) -> bool {
+    match error {
+        TonClientError::TonlibError { .. } => matches!(
+            error.tonlib_error_kind(),
+            Some(TonlibErrorKind::Timeout | TonlibErrorKind::Other)
+        ),
+        TonClientError::InvalidArgument(_)
+        | TonClientError::Cancelled { .. }
+        | TonClientError::UnexpectedTonResult { .. }
+        | TonClientError::TonAddressParseError(_)
+        | TonClientError::TonHashParseError(_) => false,
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tl::{BlockIdExt, BlocksMasterchainInfo};
+
+    fn masterchain_info(seqno: i32) -> Result<TonResult, TonClientError> {
+        let block = BlockIdExt {
+            workchain: -1,
+            shard: i64::MIN,
+            seqno,
+            root_hash: vec![0; 32],
+            file_hash: vec![0; 32],
+        };
+        Ok(TonResult::BlocksMasterchainInfo(BlocksMasterchainInfo {
+            last: block.clone(),
+            state_root_hash: vec![0; 32],
+            init: block,
+        }))
+    }
+
+    fn timeout() -> Result<TonResult, TonClientError> {
+        Err(TonClientError::TonlibError {
+            method: "BlocksGetMasterchainInfo",
+            code: 652,
+            message: "LITE_SERVER_TIMEOUT: timeout".to_string(),
+        })
+    }
+
+    fn not_ready() -> Result<TonResult, TonClientError> {
+        Err(TonClientError::TonlibError {
+            method: "BlocksGetMasterchainInfo",
+            code: 500,
+            message: "LITE_SERVER_NOTREADY: block is not applied".to_string(),
+        })
+    }
+
+    #[test]
+    fn test_score_prefers_fast_and_fresh_connections() {
+        let fast = ConnectionHealth::default();
+        let slow = ConnectionHealth::default();
+        let lagging = ConnectionHealth::default();
+        fast.record(&Duration::from_millis(10), &masterchain_info(100));
+        slow.record(&Duration::from_millis(500), &masterchain_info(100));
+        lagging.record(&Duration::from_millis(10), &masterchain_info(95));
+
+        let fast = fast.score(0, Some(100));
+        let slow = slow.score(1, Some(100));
+        let lagging = lagging.score(2, Some(100));
+        assert_eq!(lagging.seqno_lag, 5);
+        assert!(fast.score < slow.score);
+        assert!(slow.score < lagging.score);
+    }
+
+    #[test]
+    fn test_failures_make_connection_unhealthy() {
+        let params = HealthCheckParams::default();
+        let health = ConnectionHealth::default();
+        health.record(&Duration::from_millis(10), &masterchain_info(100));
+        assert!(health.score(0, Some(100)).is_healthy(&params));
+
+        for _ in 0..params.max_consecutive_failures {
+            health.record(&Duration::from_millis(10), &timeout());
+        }
+        assert!(!health.score(0, Some(100)).is_healthy(&params));
+
+        health.record(&Duration::from_millis(10), &masterchain_info(100));
+        assert!(health.score(0, Some(100)).is_healthy(&params));
+        assert!(!health.score(0, Some(111)).is_healthy(&params));
+
+        health.reset();
+        assert_eq!(health.score(0, None).requests, 0);
+    }
+
+    #[test]
+    fn test_request_errors_are_not_failures() {
+        let health = ConnectionHealth::default();
+        let result = Err(TonClientError::TonlibError {
+            method: "RawGetAccountState",
+            code: 400,
+            message: "INVALID_ACCOUNT_ADDRESS".to_string(),
+        });
+        health.record(&Duration::from_millis(10), &result);
+        assert_eq!(health.score(0, None).consecutive_failures, 0);
+
+        let gc_error = Err(TonClientError::TonlibError {
+            method: "RawGetAccountState",
+            code: 500,
+            message: "LITE_SERVER_UNKNOWN: state already gc'd".to_string(),
+        });
+        health.record(&Duration::from_millis(10), &gc_error);
+        health.record(&Duration::from_millis(10), &not_ready());
+        let score = health.score(0, None);
+        assert_eq!(score.consecutive_failures, 0);
+        assert_eq!(score.not_ready_count, 1);
+    }
+}
@@ -1,14 +1,18 @@
+use std::time::Duration;
+
 use async_trait::async_trait;
+use tokio_util::sync::CancellationToken;
 use tonlib_core::TonAddress;
 
 use super::{SmcLibraryQueryExt, SmcLibraryResult, SmcLibraryResultExt, TonLibraryId};
 use crate::client::{TonClientError, TonConnection};
 use crate::contract::LoadedSmcState;
 use crate::tl::{
-    AccountAddress, BlockId, BlockIdExt, BlocksAccountTransactionId, BlocksHeader,
-    BlocksMasterchainInfo, BlocksShards, BlocksTransactions, BlocksTransactionsExt, ConfigInfo,
-    FullAccountState, InternalTransactionId, LiteServerInfo, RawFullAccountState, RawTransactions,
-    TonFunction, TonResult, TonResultDiscriminants, TvmCell,
+    AccountAddress, BlockId, BlockIdExt, BlocksAccountTransactionId, BlocksBlockSignatures,
+    BlocksHeader, BlocksMasterchainInfo, BlocksOutMsgQueueSizes, BlocksShardBlockProof,
+    BlocksShards, BlocksTransactions, BlocksTransactionsExt, ConfigInfo, DnsResolved,
+    FullAccountState, InternalTransactionId, LiteServerInfo, QueryFees, QueryInfo,
+    RawFullAccountState, RawTransactions, TonFunction, TonResult, TonResultDiscriminants, TvmCell,
 };
 
 #[async_trait]
@@ -24,6 +28,38 @@ pub trait TonClientInterface: Send + Sync {
         self.invoke_on_connection(function).await.map(|(_, r)| r)
     }
 
+    /// Invokes `function`, failing with `TonClientError::Timeout` if it takes longer than `timeout`
+    async fn invoke_with_timeout(
+        &self,
+        function: &TonFunction,
+        timeout: Duration,
+    ) -> Result<TonResult, TonClientError> {
+        match tokio::time::timeout(timeout, self.invoke(function)).await {
+            Ok(result) => result,
+            Err(_) => Err(TonClientError::Timeout {
+                method: function.into(),
+                timeout,
+            }),
+        }
+    }
+
+    /// Invokes `function`, failing with `TonClientError::Cancelled` once `token` is cancelled.
+    ///
+    /// Cancellation only stops waiting for the result. Tonlib API has no function to abort
+    /// a query, so tonlib keeps processing it and its late response is discarded.
+    async fn invoke_with_cancellation(
+        &self,
+        function: &TonFunction,
+        token: &CancellationToken,
+    ) -> Result<TonResult, TonClientError> {
+        tokio::select! {
+            result = self.invoke(function) => result,
+            _ = token.cancelled() => Err(TonClientError::Cancelled {
+                method: function.into(),
+            }),
+        }
+    }
+
     async fn get_raw_account_state(
         &self,
         account_address: &TonAddress,
@@ -64,6 +100,92 @@ pub trait TonClientInterface: Send + Sync {
         }
     }
 
+    /// Returns state of the account as of block `block_id`, usually a masterchain one.
+    async fn get_raw_account_state_at_block(
+        &self,
+        account_address: &TonAddress,
+        block_id: &BlockIdExt,
+    ) -> Result<RawFullAccountState, TonClientError> {
+        let func = TonFunction::WithBlock {
+            id: block_id.clone(),
+            function: Box::new(TonFunction::RawGetAccountState {
+                account_address: AccountAddress {
+                    account_address: account_address.to_hex(),
+                },
+            }),
+        };
+        let result = self.invoke(&func).await?;
+        match result {
+            TonResult::RawFullAccountState(state) => Ok(state),
+            r => Err(TonClientError::unexpected_ton_result(
+                TonResultDiscriminants::RawFullAccountState,
+                r,
+            )),
+        }
+    }
+
+    /// Returns state of the account after transaction `transaction_id`,
+    /// looking the transaction up in shards referenced by masterchain block `block_id`.
+    ///
+    /// Unlike `get_raw_account_state_by_transaction`, works for shard blocks
+    /// which are not referenced by the latest masterchain block anymore.
+    async fn get_raw_account_state_by_transaction_at_block(
+        &self,
+        account_address: &TonAddress,
+        transaction_id: &InternalTransactionId,
+        block_id: &BlockIdExt,
+    ) -> Result<RawFullAccountState, TonClientError> {
+        let func = TonFunction::WithBlock {
+            id: block_id.clone(),
+            function: Box::new(TonFunction::RawGetAccountStateByTransaction {
+                account_address: AccountAddress {
+                    account_address: account_address.to_hex(),
+                },
+                transaction_id: transaction_id.clone(),
+            }),
+        };
+        let result = self.invoke(&func).await?;
+        match result {
+            TonResult::RawFullAccountState(state) => Ok(state),
+            r => Err(TonClientError::unexpected_ton_result(
+                TonResultDiscriminants::RawFullAccountState,
+                r,
+            )),
+        }
+    }
+
+    /// Returns state of the account as of masterchain block `seqno`.
+    async fn get_raw_account_state_at_seqno(
+        &self,
+        account_address: &TonAddress,
+        seqno: i32,
+    ) -> Result<RawFullAccountState, TonClientError> {
+        let block_id = BlockId {
+            workchain: -1,
+            shard: i64::MIN,
+            seqno,
+        };
+        let block = self.lookup_block(1, &block_id, 0, 0).await?;
+        self.get_raw_account_state_at_block(account_address, &block)
+            .await
+    }
+
+    /// Returns state of the account as of the last masterchain block generated before `utime`.
+    async fn get_raw_account_state_at_utime(
+        &self,
+        account_address: &TonAddress,
+        utime: i32,
+    ) -> Result<RawFullAccountState, TonClientError> {
+        let block_id = BlockId {
+            workchain: -1,
+            shard: i64::MIN,
+            seqno: 0,
+        };
+        let block = self.lookup_block(4, &block_id, 0, utime).await?;
+        self.get_raw_account_state_at_block(account_address, &block)
+            .await
+    }
+
     async fn get_raw_transactions(
         &self,
         account_address: &TonAddress,
@@ -426,6 +548,179 @@ pub trait TonClientInterface: Send + Sync {
         }
     }
 
+    async fn get_masterchain_block_signatures(
+        &self,
+        seqno: i32,
+    ) -> Result<BlocksBlockSignatures, TonClientError> {
+        let func = TonFunction::BlocksGetMasterchainBlockSignatures { seqno };
+        let result = self.invoke(&func).await?;
+        match result {
+            TonResult::BlocksBlockSignatures(result) => Ok(result),
+            r => Err(TonClientError::unexpected_ton_result(
+                TonResultDiscriminants::BlocksBlockSignatures,
+                r,
+            )),
+        }
+    }
+
+    /// Returns proof of shard block `block_id` linking it to masterchain block `from`,
+    /// or to the latest masterchain block if `from` is `None`.
+    async fn get_shard_block_proof(
+        &self,
+        block_id: &BlockIdExt,
+        from: Option<&BlockIdExt>,
+    ) -> Result<BlocksShardBlockProof, TonClientError> {
+        let func = TonFunction::BlocksGetShardBlockProof {
+            id: block_id.clone(),
+            mode: from.is_some() as u32,
+            from: from.cloned(),
+        };
+        let result = self.invoke(&func).await?;
+        match result {
+            TonResult::BlocksShardBlockProof(result) => Ok(result),
+            r => Err(TonClientError::unexpected_ton_result(
+                TonResultDiscriminants::BlocksShardBlockProof,
+                r,
+            )),
+        }
+    }
+
+    /// Returns outbound message queue sizes of shard `(workchain, shard)`,
+    /// or of all shards if `shard` is `None`.
+    async fn get_out_msg_queue_sizes(
+        &self,
+        shard: Option<(i32, i64)>,
+    ) -> Result<BlocksOutMsgQueueSizes, TonClientError> {
+        let func = TonFunction::BlocksGetOutMsgQueueSizes {
+            mode: shard.is_some() as u32,
+            wc: shard.map(|(wc, _)| wc),
+            shard: shard.map(|(_, shard)| shard),
+        };
+        let result = self.invoke(&func).await?;
+        match result {
+            TonResult::BlocksOutMsgQueueSizes(result) => Ok(result),
+            r => Err(TonClientError::unexpected_ton_result(
+                TonResultDiscriminants::BlocksOutMsgQueueSizes,
+                r,
+            )),
+        }
+    }
+
+    /// Resolves DNS `name` starting from resolver `account_address`.
+    ///
+    /// * `category`: sha256 of the category name, or zeros to get all entries.
+    /// * `ttl`: Maximum number of resolvers to follow.
+    async fn dns_resolve(
+        &self,
+        account_address: &TonAddress,
+        name: &str,
+        category: &[u8],
+        ttl: i32,
+    ) -> Result<DnsResolved, TonClientError> {
+        let func = TonFunction::DnsResolve {
+            account_address: AccountAddress {
+                account_address: account_address.to_hex(),
+            },
+            name: name.to_string(),
+            category: category.to_vec(),
+            ttl,
+        };
+        let result = self.invoke(&func).await?;
+        match result {
+            TonResult::DnsResolved(result) => Ok(result),
+            r => Err(TonClientError::unexpected_ton_result(
+                TonResultDiscriminants::DnsResolved,
+                r,
+            )),
+        }
+    }
+
+    /// Creates query for external message with `body` to `destination`.
+    ///
+    /// Query is kept by the connection it was created on,
+    /// use `invoke_on_connection` based methods to operate it.
+    async fn create_query(
+        &self,
+        destination: &TonAddress,
+        init_code: &[u8],
+        init_data: &[u8],
+        body: &[u8],
+    ) -> Result<(TonConnection, QueryInfo), TonClientError> {
+        let func = TonFunction::RawCreateQuery {
+            destination: AccountAddress {
+                account_address: destination.to_hex(),
+            },
+            init_code: init_code.to_vec(),
+            init_data: init_data.to_vec(),
+            body: body.to_vec(),
+        };
+        let (conn, result) = self.invoke_on_connection(&func).await?;
+        match result {
+            TonResult::QueryInfo(result) => Ok((conn, result)),
+            r => Err(TonClientError::unexpected_ton_result(
+                TonResultDiscriminants::QueryInfo,
+                r,
+            )),
+        }
+    }
+
+    async fn query_estimate_fees(
+        &self,
+        id: i64,
+        ignore_chksig: bool,
+    ) -> Result<QueryFees, TonClientError> {
+        let func = TonFunction::QueryEstimateFees { id, ignore_chksig };
+        let result = self.invoke(&func).await?;
+        match result {
+            TonResult::QueryFees(result) => Ok(result),
+            r => Err(TonClientError::unexpected_ton_result(
+                TonResultDiscriminants::QueryFees,
+                r,
+            )),
+        }
+    }
+
+    async fn query_get_info(&self, id: i64) -> Result<QueryInfo, TonClientError> {
+        let func = TonFunction::QueryGetInfo { id };
+        let result = self.invoke(&func).await?;
+        match result {
+            TonResult::QueryInfo(result) => Ok(result),
+            r => Err(TonClientError::unexpected_ton_result(
+                TonResultDiscriminants::QueryInfo,
+                r,
+            )),
+        }
+    }
+
+    async fn query_send(&self, id: i64) -> Result<(), TonClientError> {
+        let func = TonFunction::QuerySend { id };
+        self.invoke(&func).await?.expect_ok()
+    }
+
+    async fn query_forget(&self, id: i64) -> Result<(), TonClientError> {
+        let func = TonFunction::QueryForget { id };
+        self.invoke(&func).await?.expect_ok()
+    }
+
+    /// Estimates fees of external message with `body` to `destination`.
+    ///
+    /// Creates a query, estimates its fees and forgets it on the same connection.
+    async fn estimate_fees(
+        &self,
+        destination: &TonAddress,
+        init_code: &[u8],
+        init_data: &[u8],
+        body: &[u8],
+        ignore_chksig: bool,
+    ) -> Result<QueryFees, TonClientError> {
+        let (conn, query) = self
+            .create_query(destination, init_code, init_data, body)
+            .await?;
+        let fees = conn.query_estimate_fees(query.id, ignore_chksig).await;
+        conn.query_forget(query.id).await?;
+        fees
+    }
+
     async fn get_config_param(&self, mode: u32, param: u32) -> Result<ConfigInfo, TonClientError> {
         let func = TonFunction::GetConfigParam { mode, param };
         let result = self.invoke(&func).await?;
@@ -0,0 +1,296 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::oneshot;
+
+use crate::client::TonClientError;
+
+/// Class of a request, connection capacity is shared between classes by `PriorityWeights`
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum RequestPriority {
+    /// Latency-sensitive requests, e.g. serving API calls
+    #[default]
+    Interactive,
+    Background,
+    /// Throughput-oriented requests, e.g. indexer backfills
+    Bulk,
+}
+
+impl RequestPriority {
+    fn index(self) -> usize {
+        match self {
+            RequestPriority::Interactive => 0,
+            RequestPriority::Background => 1,
+            RequestPriority::Bulk => 2,
+        }
+    }
+}
+
+/// Relative shares of connection capacity granted to priorities while requests are queued
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PriorityWeights {
+    pub interactive: u32,
+    pub background: u32,
+    pub bulk: u32,
+}
+
+impl Default for PriorityWeights {
+    fn default() -> Self {
+        PriorityWeights {
+            interactive: 16,
+            background: 4,
+            bulk: 1,
+        }
+    }
+}
+
+/// Token bucket limiting the request rate of a caller
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CallerBudget {
+    pub requests_per_sec: f64,
+    /// Number of requests which may be sent at once after idle period
+    pub burst: u32,
+}
+
+/// Concurrency limiter serving queued requests by weighted fair queuing between priorities
+pub(crate) struct PriorityLimiter {
+    strides: [f64; 3],
+    state: Mutex<LimiterState>,
+}
+
+struct LimiterState {
+    available: usize,
+    queues: [VecDeque<oneshot::Sender<LimiterPermit>>; 3],
+    passes: [f64; 3],
+    virtual_time: f64,
+}
+
+/// Slot of `PriorityLimiter`, released on drop
+pub(crate) struct LimiterPermit {
+    limiter: Option<Arc<PriorityLimiter>>,
+}
+
+impl PriorityLimiter {
+    pub(crate) fn new(limit: usize, weights: &PriorityWeights) -> PriorityLimiter {
+        let stride = |weight: u32| 1.0 / weight.max(1) as f64;
+        PriorityLimiter {
+            strides: [
+                stride(weights.interactive),
+                stride(weights.background),
+                stride(weights.bulk),
+            ],
+            state: Mutex::new(LimiterState {
+                available: limit,
+                queues: Default::default(),
+                passes: [0.0; 3],
+                virtual_time: 0.0,
+            }),
+        }
+    }
+
+    pub(crate) async fn acquire(
+        self: &Arc<Self>,
+        priority: RequestPriority,
+    ) -> Result<LimiterPermit, TonClientError> {
+        let receiver = {
+            let mut state = self.state.lock().unwrap();
+            if state.available > 0 && state.queues.iter().all(|q| q.is_empty()) {
+                state.available -= 1;
+                return Ok(self.permit());
+            }
+            let index = priority.index();
+            if state.queues[index].is_empty() {
+                state.passes[index] = state.passes[index].max(state.virtual_time);
+            }
+            let (sender, receiver) = oneshot::channel();
+            state.queues[index].push_back(sender);
+            receiver
+        };
+        receiver.await.map_err(|_| {
+            TonClientError::InternalError("Rate limiter dropped queued request".to_string())
+        })
+    }
+
+    fn permit(self: &Arc<Self>) -> LimiterPermit {
+        LimiterPermit {
+            limiter: Some(self.clone()),
+        }
+    }
+
+    /// Hands the slot over to the next queued request or returns it to the pool
+    fn release(self: &Arc<Self>) {
+        let mut state = self.state.lock().unwrap();
+        loop {
+            let next = (0..state.queues.len())
+                .filter(|i| !state.queues[*i].is_empty())
+                .min_by(|a, b| state.passes[*a].total_cmp(&state.passes[*b]));
+            let Some(index) = next else {
+                state.available += 1;
+                return;
+            };
+            let sender = state.queues[index].pop_front().unwrap();
+            match sender.send(self.permit()) {
+                Ok(()) => {
+                    state.virtual_time = state.passes[index];
+                    state.passes[index] += self.strides[index];
+                    return;
+                }
+                // the request was cancelled while queued
+                Err(mut permit) => permit.limiter = None,
+            }
+        }
+    }
+}
+
+impl Drop for LimiterPermit {
+    fn drop(&mut self) {
+        if let Some(limiter) = self.limiter.take() {
+            limiter.release();
+        }
+    }
+}
+
+/// Token buckets of callers, requests of callers without budget are not limited
+#[derive(Default)]
+pub(crate) struct CallerBudgets {
+    buckets: HashMap<String, Mutex<TokenBucket>>,
+}
+
+struct TokenBucket {
+    budget: CallerBudget,
+    tokens: f64,
+    updated: Instant,
+}
+
+impl CallerBudgets {
+    pub(crate) fn new(budgets: &HashMap<String, CallerBudget>) -> CallerBudgets {
+        let buckets = budgets
+            .iter()
+            .map(|(caller, budget)| {
+                let bucket = TokenBucket {
+                    budget: budget.clone(),
+                    tokens: budget.burst as f64,
+                    updated: Instant::now(),
+                };
+                (caller.clone(), Mutex::new(bucket))
+            })
+            .collect();
+        CallerBudgets { buckets }
+    }
+
+    /// Waits until `caller` has a token available and takes it
+    pub(crate) async fn acquire(&self, caller: &str) -> Result<(), TonClientError> {
+        let Some(bucket) = self.buckets.get(caller) else {
+            return Ok(());
+        };
+        loop {
+            let delay = bucket.lock().unwrap().take()?;
+            match delay {
+                Some(delay) => tokio::time::sleep(delay).await,
+                None => return Ok(()),
+            }
+        }
+    }
+}
+
+impl TokenBucket {
+    /// Takes a token or returns the time until the next one is available
+    fn take(&mut self) -> Result<Option<Duration>, TonClientError> {
+        let now = Instant::now();
+        let rate = self.budget.requests_per_sec;
+        let elapsed = now.duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * rate.max(0.0)).min(self.budget.burst.max(1) as f64);
+        self.updated = now;
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            return Ok(None);
+        }
+        if rate <= 0.0 {
+            return Err(TonClientError::InvalidArgument(
+                "Caller budget is exhausted and has no refill rate".to_string(),
+            ));
+        }
+        Ok(Some(Duration::from_secs_f64((1.0 - self.tokens) / rate)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_weighted_fair_queuing() {
+        let limiter = Arc::new(PriorityLimiter::new(1, &PriorityWeights::default()));
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let held = limiter.acquire(RequestPriority::Bulk).await.unwrap();
+
+        let priorities = [RequestPriority::Bulk; 4]
+            .into_iter()
+            .chain([RequestPriority::Interactive; 4]);
+        let mut handles = vec![];
+        for priority in priorities {
+            let limiter = limiter.clone();
+            let order = order.clone();
+            handles.push(tokio::spawn(async move {
+                let _permit = limiter.acquire(priority).await.unwrap();
+                order.lock().unwrap().push(priority);
+            }));
+            tokio::task::yield_now().await;
+        }
+        drop(held);
+        for handle in handles {
+            handle.await.unwrap();
+        }
+
+        use RequestPriority::*;
+        let expected = [
+            Interactive,
+            Bulk,
+            Interactive,
+            Interactive,
+            Interactive,
+            Bulk,
+            Bulk,
+            Bulk,
+        ];
+        assert_eq!(*order.lock().unwrap(), expected);
+        assert_eq!(limiter.state.lock().unwrap().available, 1);
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_request_releases_slot() {
+        let limiter = Arc::new(PriorityLimiter::new(1, &PriorityWeights::default()));
+        let held = limiter.acquire(RequestPriority::Interactive).await.unwrap();
+        let queued = tokio::time::timeout(
+            Duration::from_millis(10),
+            limiter.acquire(RequestPriority::Interactive),
+        )
+        .await;
+        assert!(queued.is_err());
+        drop(held);
+        assert_eq!(limiter.state.lock().unwrap().available, 1);
+    }
+
+    #[tokio::test]
+    async fn test_caller_budget() {
+        let budget = CallerBudget {
+            requests_per_sec: 50.0,
+            burst: 2,
+        };
+        let budgets = CallerBudgets::new(&HashMap::from([("indexer".to_string(), budget)]));
+        let start = Instant::now();
+        for _ in 0..3 {
+            budgets.acquire("indexer").await.unwrap();
+        }
+        assert!(start.elapsed() >= Duration::from_millis(15));
+
+        let start = Instant::now();
+        for _ in 0..10 {
+            budgets.acquire("api").await.unwrap();
+        }
+        assert!(start.elapsed() < Duration::from_millis(15));
+    }
+}
@@ -0,0 +1,1000 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::net::SocketAddrV4;
+use std::ops::Deref;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use adnl::AdnlPeer;
+use async_trait::async_trait;
+use base64::prelude::BASE64_STANDARD;
+use base64::Engine;
+use tokio::net::TcpStream;
+use tokio::sync::{broadcast, Mutex, OnceCell};
+use tokio::time::timeout;
+use tokio_tower::multiplex::Client;
+use ton_liteapi::layers::{WrapMessagesLayer, WrapService};
+use ton_liteapi::peer::LitePeer;
+use ton_liteapi::tl::adnl::Message;
+use ton_liteapi::tl::common::{
+    AccountId, BlockId as LiteBlockId, BlockIdExt as LiteBlockIdExt, Int256,
+};
+use ton_liteapi::tl::request::{
+    GetAccountState, GetAllShardsInfo, GetBlock, GetBlockHeader, GetBlockProof, GetConfigAll,
+    GetConfigParams, GetLibraries, GetTransactions, LookupBlock, Request, SendMessage,
+    WaitMasterchainSeqno, WrappedRequest,
+};
+use ton_liteapi::tl::response::{MasterchainInfo, Response};
+use ton_liteapi::types::LiteError;
+use tonlib_core::cell::{ArcCell, BagOfCells, Cell};
+use tonlib_core::constants::SHARD_FULL;
+use tonlib_core::tlb_types::block::state_init::StateInit;
+use tonlib_core::tlb_types::tlb::TLB;
+use tonlib_core::{TonAddress, TonHash};
+use tower::{Service, ServiceBuilder, ServiceExt};
+
+use crate::client::{
+    TonClientError, TonClientInterface, TonConnection, TonConnectionCallback, TonProofError,
+    NOOP_CONNECTION_CALLBACK,
+};
+use crate::config::LiteEndpoint;
+use crate::tl::{
+    AccountAddress, BlockIdExt, BlocksAccountTransactionId, BlocksHeader, BlocksMasterchainInfo,
+    BlocksShards, BlocksShortTxId, BlocksTransactions, BlocksTransactionsExt, ConfigInfo,
+    InternalTransactionId, LiteServerInfo, RawExtMessageInfo, RawFullAccountState, RawTransaction,
+    RawTransactions, SmcInfo, SmcLibraryEntry, SmcLibraryResult, TonFunction, TonLibraryId,
+    TonResult, TvmCell,
+};
+#[cfg(not(feature = "tonlib"))]
+use crate::tl::{SmcRunResult, TvmStackEntry as TlTvmStackEntry};
+#[cfg(not(feature = "tonlib"))]
+use crate::types::TonMethodId;
+
+#[cfg(feature = "tonlib")]
+mod emulation;
+mod parse;
+mod proof;
+
+const CONNECTION_TIMEOUT: Duration = Duration::from_secs(10);
+const REQ_TIMEOUT: Duration = Duration::from_secs(10);
+const RAW_TRANSACTIONS_COUNT: u32 = 10;
+
+type ConnService =
+    WrapService<Client<LitePeer<AdnlPeer<TcpStream>>, Box<dyn Error + Sync + Send>, Message>>;
+
+/// Lite server client built on top of `ton_liteapi`, doesn't require tonlib.
+///
+/// Smart contract get methods are executed locally by `TvmEmulator`, which requires
+/// `tonlib` feature.
+///
+/// Client created by [`LiteClient::new_with_proof_check`] doesn't trust the lite server:
+/// account states are checked against Merkle proofs of masterchain blocks proved
+/// by validator signatures starting from the trusted key block, and transactions are checked
+/// to form the chain starting at the requested transaction id.
+#[derive(Clone)]
+pub struct LiteClient {
+    inner: Arc<Inner>,
+    testnet: bool,
+}
+
+struct LoadedSmc {
+    #[cfg_attr(not(feature = "tonlib"), allow(dead_code))]
+    address: TonAddress,
+    state: RawFullAccountState,
+}
+
+struct Inner {
+    public: Vec<u8>,
+    addr: SocketAddrV4,
+    service: Mutex<Option<ConnService>>,
+    smc_counter: AtomicI64,
+    smc_states: Mutex<HashMap<i64, Arc<LoadedSmc>>>,
+    #[cfg_attr(not(feature = "tonlib"), allow(dead_code))]
+    config: OnceCell<Vec<u8>>,
+    trusted: Option<Mutex<TrustedBlocks>>,
+}
+
+/// Masterchain blocks proved so far.
+struct TrustedBlocks {
+    key_block: LiteBlockIdExt,
+    last: LiteBlockIdExt,
+}
+
+impl LiteClient {
+    /// Creates a new LiteClient. Connection is established lazily on the first request.
+    pub fn new(addr: SocketAddrV4, public_key: &[u8]) -> LiteClient {
+        LiteClient::with_trusted_block(addr, public_key, None)
+    }
+
+    /// Creates a new LiteClient which checks proofs of account states and transactions.
+    ///
+    /// `trusted_key_block` is a masterchain key block to start block proofs from,
+    /// usually `init_block` of the network config.
+    pub fn new_with_proof_check(
+        addr: SocketAddrV4,
+        public_key: &[u8],
+        trusted_key_block: &BlockIdExt,
+    ) -> Result<LiteClient, TonClientError> {
+        let trusted_key_block = to_lite_block_id(trusted_key_block)?;
+        Ok(LiteClient::with_trusted_block(
+            addr,
+            public_key,
+            Some(trusted_key_block),
+        ))
+    }
+
+    fn with_trusted_block(
+        addr: SocketAddrV4,
+        public_key: &[u8],
+        trusted_key_block: Option<LiteBlockIdExt>,
+    ) -> LiteClient {
+        let trusted = trusted_key_block.map(|key_block| {
+            Mutex::new(TrustedBlocks {
+                last: key_block.clone(),
+                key_block,
+            })
+        });
+        let inner = Inner {
+            public: public_key.to_vec(),
+            addr,
+            service: Mutex::new(None),
+            smc_counter: AtomicI64::new(1),
+            smc_states: Mutex::new(HashMap::new()),
+            config: OnceCell::new(),
+            trusted,
+        };
+        LiteClient {
+            inner: Arc::new(inner),
+            testnet: false,
+        }
+    }
+
+    /// Formats user-friendly addresses of responses with testnet flag
+    pub fn with_testnet(mut self, testnet: bool) -> LiteClient {
+        self.testnet = testnet;
+        self
+    }
+
+    pub(crate) fn from_endpoint(
+        endpoint: &LiteEndpoint,
+        trusted_key_block: Option<&BlockIdExt>,
+    ) -> Result<LiteClient, TonClientError> {
+        let public = BASE64_STANDARD.decode(&endpoint.id.key).map_err(|e| {
+            TonClientError::InvalidArgument(format!("Invalid liteserver key: {}", e))
+        })?;
+        let addr = endpoint.socket_addr();
+        match trusted_key_block {
+            Some(block) => LiteClient::new_with_proof_check(addr, public.as_slice(), block),
+            None => Ok(LiteClient::new(addr, public.as_slice())),
+        }
+    }
+
+    pub fn addr(&self) -> &SocketAddrV4 {
+        &self.inner.addr
+    }
+
+    pub fn is_proof_check_enabled(&self) -> bool {
+        self.inner.trusted.is_some()
+    }
+
+    /// Executes `function` against the lite server.
+    ///
+    /// Returns `TonClientError::InternalError` for functions not supported by lite backend.
+    pub async fn invoke(&self, function: &TonFunction) -> Result<TonResult, TonClientError> {
+        let method: &'static str = function.into();
+        match function {
+            TonFunction::Sync {} => {
+                let info = self.masterchain_info(method).await?;
+                Ok(TonResult::BlockIdExt(from_lite_block_id(&info.last)))
+            }
+            TonFunction::BlocksGetMasterchainInfo {} => {
+                let info = self.masterchain_info(method).await?;
+                let init = BlockIdExt {
+                    workchain: info.init.workchain,
+                    shard: SHARD_FULL as i64,
+                    seqno: 0,
+                    root_hash: info.init.root_hash.0.to_vec(),
+                    file_hash: info.init.file_hash.0.to_vec(),
+                };
+                Ok(TonResult::BlocksMasterchainInfo(BlocksMasterchainInfo {
+                    last: from_lite_block_id(&info.last),
+                    state_root_hash: info.state_root_hash.0.to_vec(),
+                    init,
+                }))
+            }
+            TonFunction::LiteServerGetInfo {} => {
+                match self.execute(method, Request::GetVersion, None).await? {
+                    Response::Version(version) => Ok(TonResult::LiteServerInfo(LiteServerInfo {
+                        now: version.now as i64,
+                        version: version.version as i32,
+                        capabilities: version.capabilities as i64,
+                    })),
+                    r => Err(unexpected_response(method, r)),
+                }
+            }
+            TonFunction::RawGetAccountState { account_address } => {
+                let address = parse_address(account_address)?;
+                let state = self.raw_account_state(method, &address, None).await?;
+                Ok(TonResult::RawFullAccountState(state))
+            }
+            TonFunction::RawGetTransactions {
+                account_address,
+                from_transaction_id,
+            } => {
+                let address = parse_address(account_address)?;
+                let txs = self
+                    .raw_transactions(
+                        method,
+                        &address,
+                        from_transaction_id,
+                        RAW_TRANSACTIONS_COUNT,
+                    )
+                    .await?;
+                Ok(TonResult::RawTransactions(txs))
+            }
+            TonFunction::RawGetTransactionsV2 {
+                account_address,
+                from_transaction_id,
+                count,
+                ..
+            } => {
+                let address = parse_address(account_address)?;
+                let txs = self
+                    .raw_transactions(method, &address, from_transaction_id, *count)
+                    .await?;
+                Ok(TonResult::RawTransactions(txs))
+            }
+            TonFunction::RawSendMessage { body } => {
+                self.send_message(method, body).await?;
+                Ok(TonResult::Ok {})
+            }
+            TonFunction::RawSendMessageReturnHash { body } => {
+                let root = BagOfCells::parse(body)?.single_root()?;
+                let hash = Cell::cell_hash(&root);
+                self.send_message(method, body).await?;
+                Ok(TonResult::RawExtMessageInfo(RawExtMessageInfo {
+                    hash: hash.to_vec(),
+                }))
+            }
+            TonFunction::BlocksLookupBlock {
+                mode,
+                id,
+                lt,
+                utime,
+            } => {
+                let request = Request::LookupBlock(LookupBlock {
+                    mode: (),
+                    id: LiteBlockId {
+                        workchain: id.workchain,
+                        shard: id.shard as u64,
+                        seqno: id.seqno as u32,
+                    },
+                    seqno: (mode & 1 != 0).then_some(()),
+                    lt: (mode & 2 != 0).then_some(*lt as u64),
+                    utime: (mode & 4 != 0).then_some(*utime as u32),
+                    with_state_update: None,
+                    with_value_flow: None,
+                    with_extra: None,
+                    with_shard_hashes: None,
+                    with_prev_blk_signatures: None,
+                });
+                match self.execute(method, request, None).await? {
+                    Response::BlockHeader(header) => {
+                        Ok(TonResult::BlockIdExt(from_lite_block_id(&header.id)))
+                    }
+                    r => Err(unexpected_response(method, r)),
+                }
+            }
+            TonFunction::GetBlockHeader { id } => {
+                let request = Request::GetBlockHeader(GetBlockHeader {
+                    id: to_lite_block_id(id)?,
+                    mode: (),
+                    with_state_update: None,
+                    with_value_flow: None,
+                    with_extra: None,
+                    with_shard_hashes: None,
+                    with_prev_blk_signatures: None,
+                });
+                match self.execute(method, request, None).await? {
+                    Response::BlockHeader(header) => {
+                        let header: BlocksHeader = parse::parse_block_header(
+                            &from_lite_block_id(&header.id),
+                            &header.header_proof,
+                        )?;
+                        Ok(TonResult::BlocksHeader(header))
+                    }
+                    r => Err(unexpected_response(method, r)),
+                }
+            }
+            TonFunction::BlocksGetShards { id } => {
+                let request = Request::GetAllShardsInfo(GetAllShardsInfo {
+                    id: to_lite_block_id(id)?,
+                });
+                match self.execute(method, request, None).await? {
+                    Response::AllShardsInfo(info) => {
+                        let shards = parse::parse_shard_hashes(&info.data)?;
+                        Ok(TonResult::BlocksShards(BlocksShards { shards }))
+                    }
+                    r => Err(unexpected_response(method, r)),
+                }
+            }
+            TonFunction::BlocksGetTransactions {
+                id,
+                mode,
+                count,
+                after,
+            } => {
+                let (result, txs) = self
+                    .block_transactions(method, id, *mode, *count, after)
+                    .await?;
+                let transactions = txs
+                    .iter()
+                    .map(|(tx, cell)| BlocksShortTxId {
+                        mode: 7,
+                        account: tx.account.to_vec(),
+                        lt: tx.lt as i64,
+                        hash: Cell::cell_hash(cell).to_vec(),
+                    })
+                    .collect();
+                Ok(TonResult::BlocksTransactions(BlocksTransactions {
+                    id: result.id,
+                    req_count: result.req_count,
+                    incomplete: result.incomplete,
+                    transactions,
+                }))
+            }
+            TonFunction::BlocksGetTransactionsExt {
+                id,
+                mode,
+                count,
+                after,
+            } => {
+                let (mut result, txs) = self
+                    .block_transactions(method, id, *mode, *count, after)
+                    .await?;
+                result.transactions = txs
+                    .into_iter()
+                    .map(|(tx, cell)| raw_transaction(id.workchain, tx, &cell, self.testnet))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(TonResult::BlocksTransactionsExt(result))
+            }
+            TonFunction::GetConfigAll { .. } => {
+                let config = self.fetch_config(method).await?;
+                Ok(TonResult::ConfigInfo(ConfigInfo {
+                    config: TvmCell { bytes: config },
+                }))
+            }
+            TonFunction::GetConfigParam { param, .. } => {
+                let info = self.masterchain_info(method).await?;
+                let request = Request::GetConfigParams(GetConfigParams {
+                    mode: (),
+                    id: info.last,
+                    param_list: vec![*param as i32],
+                    with_state_root: None,
+                    with_libraries: None,
+                    with_state_extra_root: None,
+                    with_shard_hashes: None,
+                    with_validator_set: None,
+                    with_special_smc: None,
+                    with_accounts_root: None,
+                    with_prev_blocks: None,
+                    with_workchain_info: None,
+                    with_capabilities: None,
+                    extract_from_key_block: None,
+                });
+                let config = match self.execute(method, request, None).await? {
+                    Response::ConfigInfo(config) => parse::parse_config_dict(&config.config_proof)?,
+                    r => return Err(unexpected_response(method, r)),
+                };
+                let value = parse::find_config_param(&config, *param)?.ok_or_else(|| {
+                    TonClientError::InternalError(format!("Config param {} not found", param))
+                })?;
+                Ok(TonResult::ConfigInfo(ConfigInfo {
+                    config: TvmCell {
+                        bytes: BagOfCells::from_root(value.deref().clone()).serialize(false)?,
+                    },
+                }))
+            }
+            TonFunction::SmcGetLibraries { library_list } => {
+                let result = self.libraries(method, library_list).await?;
+                Ok(TonResult::SmcLibraryResult(result))
+            }
+            TonFunction::SmcLoad { account_address } => {
+                let address = parse_address(account_address)?;
+                self.load_smc(method, address, None).await
+            }
+            TonFunction::SmcForget { id } => {
+                self.inner.smc_states.lock().await.remove(id);
+                Ok(TonResult::Ok {})
+            }
+            TonFunction::SmcGetCode { id } => {
+                let smc = self.loaded_smc(method, *id).await?;
+                Ok(TonResult::TvmCell(TvmCell {
+                    bytes: smc.state.code.clone(),
+                }))
+            }
+            TonFunction::SmcGetData { id } => {
+                let smc = self.loaded_smc(method, *id).await?;
+                Ok(TonResult::TvmCell(TvmCell {
+                    bytes: smc.state.data.clone(),
+                }))
+            }
+            TonFunction::SmcGetState { id } => {
+                let smc = self.loaded_smc(method, *id).await?;
+                let code = BagOfCells::parse(&smc.state.code)?.single_root()?;
+                let data = BagOfCells::parse(&smc.state.data)?.single_root()?;
+                Ok(TonResult::TvmCell(TvmCell {
+                    bytes: StateInit::new(code, data).to_boc(false)?,
+                }))
+            }
+            TonFunction::SmcRunGetMethod {
+                id,
+                method: smc_method,
+                stack,
+            } => {
+                let smc = self.loaded_smc(method, *id).await?;
+                let result = self
+                    .run_get_method(method, smc, smc_method.into(), stack)
+                    .await?;
+                Ok(TonResult::SmcRunResult(result))
+            }
+            TonFunction::WithBlock { id, function } => match function.as_ref() {
+                TonFunction::RawGetAccountState { account_address } => {
+                    let address = parse_address(account_address)?;
+                    let state = self.raw_account_state(method, &address, Some(id)).await?;
+                    Ok(TonResult::RawFullAccountState(state))
+                }
+                TonFunction::SmcLoad { account_address } => {
+                    let address = parse_address(account_address)?;
+                    self.load_smc(method, address, Some(id)).await
+                }
+                f => Err(TonClientError::InternalError(format!(
+                    "Method {} with block is not supported by lite backend",
+                    <&'static str>::from(f)
+                ))),
+            },
+            _ => Err(TonClientError::InternalError(format!(
+                "Method {} is not supported by lite backend",
+                method
+            ))),
+        }
+    }
+
+    /// Sends a raw request to the lite server and returns its response.
+    ///
+    /// Lite server errors are reported as `TonClientError::TonlibError`.
+    pub async fn execute(
+        &self,
+        method: &'static str,
+        request: Request,
+        wait_masterchain_seqno: Option<u32>,
+    ) -> Result<Response, TonClientError> {
+        let request = WrappedRequest {
+            wait_masterchain_seqno: wait_masterchain_seqno.map(|seqno| WaitMasterchainSeqno {
+                seqno,
+                timeout_ms: REQ_TIMEOUT.as_millis() as u32,
+            }),
+            request,
+        };
+        let response_future = {
+            let mut service = self.inner.service.lock().await;
+            if service.is_none() {
+                *service = Some(self.connect().await?);
+            }
+            // unwrap is safe: we initialized it above
+            match service.as_mut().unwrap().ready().await {
+                Ok(ready) => ready.call(request),
+                Err(e) => {
+                    *service = None;
+                    return Err(lite_error(method, e));
+                }
+            }
+        };
+        let response = match timeout(REQ_TIMEOUT, response_future).await {
+            Ok(response) => response,
+            Err(_) => {
+                return Err(TonClientError::Timeout {
+                    method,
+                    timeout: REQ_TIMEOUT,
+                })
+            }
+        };
+        match response {
+            Ok(Response::Error(e)) => Err(TonClientError::TonlibError {
+                method,
+                code: e.code,
+                message: e.message.to_string(),
+            }),
+            Ok(response) => Ok(response),
+            Err(LiteError::ServerError(e)) => Err(TonClientError::TonlibError {
+                method,
+                code: e.code,
+                message: e.message.to_string(),
+            }),
+            Err(e) => {
+                *self.inner.service.lock().await = None;
+                Err(lite_error(method, e))
+            }
+        }
+    }
+
+    async fn connect(&self) -> Result<ConnService, TonClientError> {
+        let adnl = timeout(
+            CONNECTION_TIMEOUT,
+            AdnlPeer::connect(&self.inner.public, self.inner.addr),
+        )
+        .await
+        .map_err(|_| {
+            TonClientError::InternalError(format!("Connection to {} timed out", self.inner.addr))
+        })?
+        .map_err(|e| {
+            TonClientError::InternalError(format!(
+                "Failed to connect to {}: {}",
+                self.inner.addr, e
+            ))
+        })?;
+        let lite = LitePeer::new(adnl);
+        let service = ServiceBuilder::new()
+            .layer(WrapMessagesLayer)
+            .service(Client::<_, Box<dyn Error + Send + Sync + 'static>, _>::new(
+                lite,
+            ));
+        Ok(service)
+    }
+
+    async fn masterchain_info(
+        &self,
+        method: &'static str,
+    ) -> Result<MasterchainInfo, TonClientError> {
+        match self
+            .execute(method, Request::GetMasterchainInfo, None)
+            .await?
+        {
+            Response::MasterchainInfo(info) => Ok(info),
+            r => Err(unexpected_response(method, r)),
+        }
+    }
+
+    /// Proves masterchain block `id` by the chain of block links from the trusted key block.
+    async fn check_mc_block(
+        &self,
+        method: &'static str,
+        id: &LiteBlockIdExt,
+    ) -> Result<(), TonClientError> {
+        let trusted = match &self.inner.trusted {
+            Some(trusted) => trusted,
+            None => return Ok(()),
+        };
+        let mut trusted = trusted.lock().await;
+        if trusted.last == *id || trusted.key_block == *id {
+            return Ok(());
+        }
+        let mut current = trusted.key_block.clone();
+        loop {
+            let request = Request::GetBlockProof(GetBlockProof {
+                mode: (),
+                known_block: current.clone(),
+                target_block: Some(id.clone()),
+                allow_weak_target: None,
+                base_block_from_request: None,
+            });
+            let proof = match self.execute(method, request, None).await? {
+                Response::PartialBlockProof(proof) => proof,
+                r => return Err(unexpected_response(method, r)),
+            };
+            if proof.from != current {
+                return Err(proof::block_mismatch(&current, &proof.from).into());
+            }
+            for step in &proof.steps {
+                let (from, to, to_key_block) = proof::check_block_link(step)?;
+                if *from != current {
+                    return Err(proof::block_mismatch(&current, from).into());
+                }
+                current = to.clone();
+                if to_key_block && to.seqno > trusted.key_block.seqno {
+                    trusted.key_block = to.clone();
+                }
+            }
+            if proof.complete {
+                break;
+            }
+            if proof.steps.is_empty() {
+                return Err(TonProofError::InvalidProof(format!(
+                    "Incomplete block proof {} -> {} without steps",
+                    proof.from, proof.to
+                ))
+                .into());
+            }
+        }
+        if current != *id {
+            return Err(proof::block_mismatch(id, &current).into());
+        }
+        trusted.last = id.clone();
+        Ok(())
+    }
+
+    async fn send_message(&self, method: &'static str, body: &[u8]) -> Result<(), TonClientError> {
+        let request = Request::SendMessage(SendMessage {
+            body: body.to_vec(),
+        });
+        match self.execute(method, request, None).await? {
+            Response::SendMsgStatus(_) => Ok(()),
+            r => Err(unexpected_response(method, r)),
+        }
+    }
+
+    /// Loads account state as of `block`, or the last masterchain block if it's `None`
+    async fn raw_account_state(
+        &self,
+        method: &'static str,
+        address: &TonAddress,
+        block: Option<&BlockIdExt>,
+    ) -> Result<RawFullAccountState, TonClientError> {
+        let (id, wait_seqno) = match block {
+            Some(block) => (to_lite_block_id(block)?, None),
+            None => {
+                let info = self.masterchain_info(method).await?;
+                let seqno = info.last.seqno;
+                (info.last, Some(seqno))
+            }
+        };
+        let request = Request::GetAccountState(GetAccountState {
+            id,
+            account: account_id(address),
+        });
+        let state = match self.execute(method, request, wait_seqno).await? {
+            Response::AccountState(state) => state,
+            r => return Err(unexpected_response(method, r)),
+        };
+
+        let shard_state = if self.is_proof_check_enabled() {
+            self.check_mc_block(method, &state.id).await?;
+            proof::check_account_state(&state, address.workchain, &address.hash_part)?
+        } else {
+            parse::merkle_proof_root(&state.proof, 1)?
+        };
+        let sync_utime = parse::parse_state_gen_utime(&shard_state)?;
+        let last_transaction_id =
+            match parse::find_last_transaction(&shard_state, &address.hash_part)? {
+                Some((hash, lt)) => InternalTransactionId {
+                    lt: lt as i64,
+                    hash: hash.to_vec(),
+                },
+                None => InternalTransactionId {
+                    lt: 0,
+                    hash: vec![0u8; 32],
+                },
+            };
+        let account = if state.state.is_empty() {
+            parse::AccountInfo::default()
+        } else {
+            let account = BagOfCells::parse(&state.state)?.single_root()?;
+            parse::parse_account(&account)?
+        };
+        Ok(RawFullAccountState {
+            balance: account.balance,
+            code: serialize_optional_cell(account.code)?,
+            data: serialize_optional_cell(account.data)?,
+            last_transaction_id,
+            block_id: from_lite_block_id(&state.id),
+            frozen_hash: account.frozen_hash.map(|h| h.to_vec()).unwrap_or_default(),
+            sync_utime: sync_utime as i64,
+        })
+    }
+
+    async fn load_smc(
+        &self,
+        method: &'static str,
+        address: TonAddress,
+        block: Option<&BlockIdExt>,
+    ) -> Result<TonResult, TonClientError> {
+        let state = self.raw_account_state(method, &address, block).await?;
+        let id = self.inner.smc_counter.fetch_add(1, Ordering::Relaxed);
+        let smc = LoadedSmc { address, state };
+        self.inner.smc_states.lock().await.insert(id, Arc::new(smc));
+        Ok(TonResult::SmcInfo(SmcInfo { id }))
+    }
+
+    async fn raw_transactions(
+        &self,
+        method: &'static str,
+        address: &TonAddress,
+        from_transaction_id: &InternalTransactionId,
+        count: u32,
+    ) -> Result<RawTransactions, TonClientError> {
+        let request = Request::GetTransactions(GetTransactions {
+            count,
+            account: account_id(address),
+            lt: from_transaction_id.lt as u64,
+            hash: Int256(to_hash_bytes(&from_transaction_id.hash)?),
+        });
+        let list = match self.execute(method, request, None).await? {
+            Response::TransactionList(list) => list,
+            r => return Err(unexpected_response(method, r)),
+        };
+        let roots = if list.transactions.is_empty() {
+            vec![]
+        } else {
+            BagOfCells::parse(&list.transactions)?.roots
+        };
+        let parsed = roots
+            .iter()
+            .map(|cell| Ok((parse::parse_transaction(cell)?, cell)))
+            .collect::<Result<Vec<_>, TonClientError>>()?;
+        if self.is_proof_check_enabled() {
+            proof::check_transactions(
+                &address.hash_part,
+                from_transaction_id,
+                parsed.iter().map(|(tx, cell)| (tx, cell.as_ref())),
+            )?;
+        }
+        let previous_transaction_id = match parsed.last() {
+            Some((tx, _)) => InternalTransactionId {
+                lt: tx.prev_trans_lt as i64,
+                hash: tx.prev_trans_hash.to_vec(),
+            },
+            None => InternalTransactionId {
+                lt: 0,
+                hash: vec![0u8; 32],
+            },
+        };
+        let transactions = parsed
+            .into_iter()
+            .map(|(tx, cell)| raw_transaction(address.workchain, tx, cell, self.testnet))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(RawTransactions {
+            transactions,
+            previous_transaction_id,
+        })
+    }
+
+    /// Emulates `liteServer.listBlockTransactionsExt` using the whole block,
+    /// because `ton_liteapi` can't request transaction ids with account, lt and hash.
+    async fn block_transactions(
+        &self,
+        method: &'static str,
+        id: &BlockIdExt,
+        mode: u32,
+        count: u32,
+        after: &BlocksAccountTransactionId,
+    ) -> Result<
+        (
+            BlocksTransactionsExt,
+            Vec<(parse::TransactionInfo, ArcCell)>,
+        ),
+        TonClientError,
+    > {
+        let request = Request::GetBlock(GetBlock {
+            id: to_lite_block_id(id)?,
+        });
+        let block = match self.execute(method, request, None).await? {
+            Response::BlockData(block) => block,
+            r => return Err(unexpected_response(method, r)),
+        };
+        let mut all = parse::parse_block_transactions(&block.data)?;
+        let reverse = mode & 64 != 0;
+        if reverse {
+            all.reverse();
+        }
+        if mode & 128 != 0 {
+            let after_key = (
+                TonHash::try_from(after.account.as_slice())?,
+                after.lt as u64,
+            );
+            all.retain(|(account, lt, _)| {
+                let key = (account.clone(), *lt);
+                if reverse {
+                    key < after_key
+                } else {
+                    key > after_key
+                }
+            });
+        }
+        let incomplete = all.len() > count as usize;
+        let transactions = all
+            .into_iter()
+            .take(count as usize)
+            .map(|(_, _, cell)| Ok((parse::parse_transaction(&cell)?, cell)))
+            .collect::<Result<Vec<_>, TonClientError>>()?;
+        let header = BlocksTransactionsExt {
+            id: from_lite_block_id(&block.id),
+            req_count: count as i32,
+            incomplete,
+            transactions: vec![],
+        };
+        Ok((header, transactions))
+    }
+
+    async fn fetch_config(&self, method: &'static str) -> Result<Vec<u8>, TonClientError> {
+        let info = self.masterchain_info(method).await?;
+        let request = Request::GetConfigAll(GetConfigAll {
+            mode: (),
+            id: info.last,
+            with_state_root: None,
+            with_libraries: None,
+            with_state_extra_root: None,
+            with_shard_hashes: None,
+            with_validator_set: None,
+            with_special_smc: None,
+            with_accounts_root: None,
+            with_prev_blocks: None,
+            with_workchain_info: None,
+            with_capabilities: None,
+            extract_from_key_block: None,
+        });
+        match self.execute(method, request, None).await? {
+            Response::ConfigInfo(config) => {
+                let config = parse::parse_config_dict(&config.config_proof)?;
+                Ok(BagOfCells::from_root(config.deref().clone()).serialize(false)?)
+            }
+            r => Err(unexpected_response(method, r)),
+        }
+    }
+
+    async fn libraries(
+        &self,
+        method: &'static str,
+        library_list: &[TonLibraryId],
+    ) -> Result<SmcLibraryResult, TonClientError> {
+        let library_list = library_list
+            .iter()
+            .map(|id| Ok(Int256(to_hash_bytes(&id.id)?)))
+            .collect::<Result<Vec<_>, TonClientError>>()?;
+        let request = Request::GetLibraries(GetLibraries { library_list });
+        match self.execute(method, request, None).await? {
+            Response::LibraryResult(libs) => Ok(SmcLibraryResult {
+                result: libs
+                    .result
+                    .into_iter()
+                    .map(|entry| SmcLibraryEntry {
+                        hash: entry.hash.0.to_vec(),
+                        data: entry.data,
+                    })
+                    .collect(),
+            }),
+            r => Err(unexpected_response(method, r)),
+        }
+    }
+
+    async fn loaded_smc(&self, method: &str, id: i64) -> Result<Arc<LoadedSmc>, TonClientError> {
+        self.inner
+            .smc_states
+            .lock()
+            .await
+            .get(&id)
+            .cloned()
+            .ok_or_else(|| {
+                TonClientError::InvalidArgument(format!(
+                    "{}: smc with id {} is not loaded",
+                    method, id
+                ))
+            })
+    }
+
+    #[cfg(not(feature = "tonlib"))]
+    async fn run_get_method(
+        &self,
+        method: &'static str,
+        _smc: Arc<LoadedSmc>,
+        _method_id: TonMethodId,
+        _stack: &[TlTvmStackEntry],
+    ) -> Result<SmcRunResult, TonClientError> {
+        Err(TonClientError::InternalError(format!(
+            "{}: get methods require TVM emulator, enable 'tonlib' feature",
+            method
+        )))
+    }
+}
+
+#[async_trait]
+impl TonClientInterface for LiteClient {
+    async fn get_connection(&self) -> Result<TonConnection, TonClientError> {
+        let callback: Arc<dyn TonConnectionCallback> = NOOP_CONNECTION_CALLBACK.clone();
+        let (notification_sender, _) = broadcast::channel(1);
+        Ok(TonConnection::from_lite_client(
+            self.clone(),
+            callback,
+            notification_sender,
+            None,
+        ))
+    }
+
+    async fn invoke_on_connection(
+        &self,
+        function: &TonFunction,
+    ) -> Result<(TonConnection, TonResult), TonClientError> {
+        let conn = self.get_connection().await?;
+        let result = self.invoke(function).await?;
+        Ok((conn, result))
+    }
+}
+
+fn raw_transaction(
+    workchain: i32,
+    tx: parse::TransactionInfo,
+    cell: &ArcCell,
+    testnet: bool,
+) -> Result<RawTransaction, TonClientError> {
+    let in_msg = tx
+        .in_msg
+        .as_deref()
+        .map(|msg| parse::parse_raw_message(msg, testnet))
+        .transpose()?;
+    let out_msgs = tx
+        .out_msgs
+        .iter()
+        .map(|msg| parse::parse_raw_message(msg, testnet))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(RawTransaction {
+        address: AccountAddress {
+            account_address: TonAddress::new(workchain, tx.account)
+                .to_base64_url_flags(false, testnet),
+        },
+        utime: tx.now as i64,
+        data: BagOfCells::from_root(cell.deref().clone()).serialize(false)?,
+        transaction_id: InternalTransactionId {
+            lt: tx.lt as i64,
+            hash: Cell::cell_hash(cell).to_vec(),
+        },
+        fee: tx.total_fees,
+        storage_fee: tx.storage_fee,
+        other_fee: tx.total_fees - tx.storage_fee,
+        in_msg,
+        out_msgs,
+    })
+}
+
+fn serialize_optional_cell(cell: Option<ArcCell>) -> Result<Vec<u8>, TonClientError> {
+    match cell {
+        Some(cell) => Ok(BagOfCells::from_root(cell.deref().clone()).serialize(false)?),
+        None => Ok(vec![]),
+    }
+}
+
+fn parse_address(address: &AccountAddress) -> Result<TonAddress, TonClientError> {
+    Ok(TonAddress::from_str(&address.account_address)?)
+}
+
+fn account_id(address: &TonAddress) -> AccountId {
+    AccountId {
+        workchain: address.workchain,
+        id: Int256(address.hash_part.clone().into()),
+    }
+}
+
+fn to_hash_bytes(bytes: &[u8]) -> Result<[u8; 32], TonClientError> {
+    Ok(TonHash::try_from(bytes)?.into())
+}
+
+fn to_lite_block_id(id: &BlockIdExt) -> Result<LiteBlockIdExt, TonClientError> {
+    Ok(LiteBlockIdExt {
+        workchain: id.workchain,
+        shard: id.shard as u64,
+        seqno: id.seqno as u32,
+        root_hash: Int256(to_hash_bytes(&id.root_hash)?),
+        file_hash: Int256(to_hash_bytes(&id.file_hash)?),
+    })
+}
+
+fn from_lite_block_id(id: &LiteBlockIdExt) -> BlockIdExt {
+    BlockIdExt {
+        workchain: id.workchain,
+        shard: id.shard as i64,
+        seqno: id.seqno as i32,
+        root_hash: id.root_hash.0.to_vec(),
+        file_hash: id.file_hash.0.to_vec(),
+    }
+}
+
+pub(crate) fn unexpected_response(method: &str, response: Response) -> TonClientError {
+    TonClientError::InternalError(format!(
+        "Unexpected lite server response to {}: {:?}",
+        method, response
+    ))
+}
+
+fn lite_error(method: &str, error: LiteError) -> TonClientError {
+    TonClientError::InternalError(format!("Lite server error in {}: {:?}", method, error))
+}
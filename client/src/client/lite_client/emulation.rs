@@ -0,0 +1,90 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use tonlib_core::cell::{ArcCell, BagOfCells};
+use tonlib_core::library_helper::LibraryHelper;
+use tonlib_core::TonHash;
+
+use super::{LiteClient, LoadedSmc};
+use crate::client::TonClientError;
+use crate::emulator::c7_register::TvmEmulatorC7;
+use crate::emulator::tvm_emulator::TvmEmulator;
+use crate::tl::{SmcRunResult, TonLibraryId, TvmStack, TvmStackEntry as TlTvmStackEntry};
+use crate::types::{TonMethodId, TvmStackEntry};
+
+const MAX_EMULATION_LIBRARIES: usize = 16;
+
+impl LiteClient {
+    pub(super) async fn run_get_method(
+        &self,
+        method: &'static str,
+        smc: Arc<LoadedSmc>,
+        method_id: TonMethodId,
+        stack: &[TlTvmStackEntry],
+    ) -> Result<SmcRunResult, TonClientError> {
+        let stack = stack
+            .iter()
+            .map(TvmStackEntry::try_from)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| TonClientError::InvalidArgument(e.to_string()))?;
+        let config = self
+            .inner
+            .config
+            .get_or_try_init(|| self.fetch_config(method))
+            .await?
+            .clone();
+        let c7 = TvmEmulatorC7::new(smc.address.clone(), config)
+            .map_err(|e| TonClientError::InternalError(e.to_string()))?;
+
+        let mut libs: HashMap<TonHash, ArcCell> = HashMap::new();
+        loop {
+            let libs_dict = LibraryHelper::store_to_dict(libs.clone())
+                .map_err(|e| TonClientError::InternalError(e.to_string()))?;
+            let loaded = smc.clone();
+            let c7 = c7.clone();
+            let method_id = method_id.clone();
+            let stack = stack.clone();
+            let run_result = tokio::task::spawn_blocking(move || {
+                let mut emulator = TvmEmulator::new(&loaded.state.code, &loaded.state.data)?;
+                emulator
+                    .with_c7(&c7)?
+                    .with_libraries(libs_dict.0.as_slice())?;
+                emulator.run_get_method(&method_id, &stack)
+            })
+            .await
+            .map_err(|e| TonClientError::InternalError(e.to_string()))?
+            .map_err(|e| TonClientError::InternalError(e.to_string()))?;
+
+            let missing_library = match &run_result.missing_library {
+                Some(lib) if libs.len() < MAX_EMULATION_LIBRARIES => TonHash::from_hex(lib)?,
+                Some(lib) => {
+                    return Err(TonClientError::InternalError(format!(
+                        "Too many libraries required by {}, missing: {}",
+                        smc.address, lib
+                    )))
+                }
+                None => {
+                    let elements = run_result
+                        .stack
+                        .iter()
+                        .map(TlTvmStackEntry::try_from)
+                        .collect::<Result<Vec<_>, _>>()
+                        .map_err(|e| TonClientError::InternalError(e.to_string()))?;
+                    return Ok(SmcRunResult {
+                        gas_used: run_result.gas_used as i64,
+                        stack: TvmStack { elements },
+                        exit_code: run_result.vm_exit_code,
+                    });
+                }
+            };
+            let found = self
+                .libraries(method, &[TonLibraryId::from(&missing_library)])
+                .await?;
+            let entry = found.result.first().ok_or_else(|| {
+                TonClientError::InternalError(format!("Library {} not found", missing_library))
+            })?;
+            let lib = BagOfCells::parse(&entry.data)?.single_root()?;
+            libs.insert(missing_library, lib);
+        }
+    }
+}
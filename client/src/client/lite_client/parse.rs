@@ -0,0 +1,658 @@
+use std::ops::Deref;
+
+use num_traits::ToPrimitive;
+use tonlib_core::cell::dict::predefined_readers::{
+    key_reader_u16, key_reader_u32, val_reader_ref_cell,
+};
+use tonlib_core::cell::{ArcCell, BagOfCells, Cell, CellParser, TonCellError};
+use tonlib_core::tlb_types::block::coins::{CurrencyCollection, Grams};
+use tonlib_core::tlb_types::block::message::{CommonMsgInfo, Message};
+use tonlib_core::tlb_types::block::msg_address::MsgAddress;
+use tonlib_core::tlb_types::block::state_init::StateInit;
+use tonlib_core::tlb_types::tlb::TLB;
+use tonlib_core::{TonAddress, TonHash};
+
+use crate::tl::{AccountAddress, BlockIdExt, BlocksHeader, MsgData, RawMessage};
+
+pub(crate) const SHARD_STATE_TAG: u32 = 0x9023afe2;
+pub(crate) const MC_STATE_EXTRA_TAG: u16 = 0xcc26;
+pub(crate) const BLOCK_TAG: u32 = 0x11ef55aa;
+pub(crate) const BLOCK_INFO_TAG: u32 = 0x9bc7a987;
+pub(crate) const BLOCK_EXTRA_TAG: u32 = 0x4a33f6fd;
+const ACCOUNT_BLOCK_TAG: u8 = 0x5;
+const TRANSACTION_TAG: u8 = 0b0111;
+
+/// Parsed `Account` as returned by `liteServer.getAccountState`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct AccountInfo {
+    pub balance: i64,
+    pub code: Option<ArcCell>,
+    pub data: Option<ArcCell>,
+    pub frozen_hash: Option<TonHash>,
+}
+
+/// Fields of `Transaction` required to build `RawTransaction`.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct TransactionInfo {
+    pub account: TonHash,
+    pub lt: u64,
+    pub prev_trans_hash: TonHash,
+    pub prev_trans_lt: u64,
+    pub now: u32,
+    pub total_fees: i64,
+    pub storage_fee: i64,
+    pub in_msg: Option<ArcCell>,
+    pub out_msgs: Vec<ArcCell>,
+}
+
+/// Returns the cell proved by the Merkle proof at `index` of the BoC.
+pub(crate) fn merkle_proof_root(boc: &[u8], index: usize) -> Result<ArcCell, TonCellError> {
+    let boc = BagOfCells::parse(boc)?;
+    let proof = boc.roots.get(index).ok_or(TonCellError::InvalidIndex {
+        idx: index,
+        ref_count: boc.roots.len(),
+    })?;
+    if !proof.is_exotic() {
+        return Err(TonCellError::InvalidCellData(
+            "Merkle proof cell expected".to_string(),
+        ));
+    }
+    Ok(proof.reference(0)?.clone())
+}
+
+/// Extracts `gen_utime` from `ShardStateUnsplit`.
+pub(crate) fn parse_state_gen_utime(state: &Cell) -> Result<u32, TonCellError> {
+    let mut parser = ordinary_parser(state)?;
+    expect_tag(&mut parser, 32, SHARD_STATE_TAG as u64)?;
+    // global_id(32), shard_id(104), seq_no(32), vert_seq_no(32)
+    parser.skip_bits(32 + 104 + 32 + 32)?;
+    parser.load_u32(32)
+}
+
+/// `ShardAccount` of the account stored in `ShardStateUnsplit.accounts`.
+pub(crate) struct ShardAccount {
+    pub account: ArcCell,
+    pub last_trans_hash: TonHash,
+    pub last_trans_lt: u64,
+}
+
+/// Looks up `account` in `ShardStateUnsplit.accounts`.
+pub(crate) fn find_shard_account(
+    state: &Cell,
+    account: &TonHash,
+) -> Result<Option<ShardAccount>, TonCellError> {
+    ordinary_parser(state)?;
+    let accounts = state.reference(1)?;
+    let mut parser = ordinary_parser(accounts)?;
+    if !parser.load_bit()? {
+        return Ok(None);
+    }
+    let root = parser.next_reference()?;
+    dict_lookup(&root, account.as_slice(), 256, |parser| {
+        // extra:DepthBalanceInfo, split_depth:(#<= 30) balance:CurrencyCollection
+        parser.skip_bits(5)?;
+        CurrencyCollection::read(parser)?;
+        // account:^Account last_trans_hash:bits256 last_trans_lt:uint64
+        Ok(ShardAccount {
+            account: parser.next_reference()?,
+            last_trans_hash: parser.load_tonhash()?,
+            last_trans_lt: parser.load_u64(64)?,
+        })
+    })
+}
+
+/// Looks up `last_trans_hash` and `last_trans_lt` of `account` in `ShardStateUnsplit.accounts`.
+pub(crate) fn find_last_transaction(
+    state: &Cell,
+    account: &TonHash,
+) -> Result<Option<(TonHash, u64)>, TonCellError> {
+    let shard_account = find_shard_account(state, account)?;
+    Ok(shard_account.map(|a| (a.last_trans_hash, a.last_trans_lt)))
+}
+
+pub(crate) fn parse_account(account: &Cell) -> Result<AccountInfo, TonCellError> {
+    let mut info = AccountInfo::default();
+    let mut parser = ordinary_parser(account)?;
+    if !parser.load_bit()? {
+        // account_none$0
+        return Ok(info);
+    }
+    MsgAddress::read(&mut parser)?;
+    // storage_used$_ cells:(VarUInteger 7) bits:(VarUInteger 7)
+    skip_var_uint(&mut parser, 3)?;
+    skip_var_uint(&mut parser, 3)?;
+    // storage_extra_none$000 or storage_extra_info$001 dict_hash:uint256
+    if parser.load_u8(3)? == 1 {
+        parser.skip_bits(256)?;
+    }
+    // last_paid:uint32 due_payment:(Maybe Grams)
+    parser.skip_bits(32)?;
+    if parser.load_bit()? {
+        Grams::read(&mut parser)?;
+    }
+    // last_trans_lt:uint64 balance:CurrencyCollection state:AccountState
+    parser.skip_bits(64)?;
+    info.balance = grams_to_i64(&CurrencyCollection::read(&mut parser)?.grams)?;
+    if parser.load_bit()? {
+        // account_active$1 _:StateInit
+        let init = StateInit::read(&mut parser)?;
+        info.code = init.code.map(|c| c.0);
+        info.data = init.data.map(|d| d.0);
+    } else if parser.load_bit()? {
+        // account_frozen$01 state_hash:bits256
+        info.frozen_hash = Some(parser.load_tonhash()?);
+    }
+    Ok(info)
+}
+
+pub(crate) fn parse_transaction(tx: &Cell) -> Result<TransactionInfo, TonCellError> {
+    let mut parser = ordinary_parser(tx)?;
+    expect_tag(&mut parser, 4, TRANSACTION_TAG as u64)?;
+    let account = parser.load_tonhash()?;
+    let lt = parser.load_u64(64)?;
+    let prev_trans_hash = parser.load_tonhash()?;
+    let prev_trans_lt = parser.load_u64(64)?;
+    let now = parser.load_u32(32)?;
+    // outmsg_cnt:uint15 orig_status:AccountStatus end_status:AccountStatus
+    parser.skip_bits(15 + 2 + 2)?;
+
+    let msgs = parser.next_reference()?;
+    let mut msgs_parser = msgs.parser();
+    let in_msg = msgs_parser.load_maybe_cell_ref()?;
+    let mut out_msgs: Vec<_> = msgs_parser
+        .load_dict(15, key_reader_u16, val_reader_ref_cell)?
+        .into_iter()
+        .collect();
+    out_msgs.sort_by_key(|(k, _)| *k);
+
+    let total_fees = grams_to_i64(&CurrencyCollection::read(&mut parser)?.grams)?;
+    let _state_update = parser.next_reference()?;
+    let description = parser.next_reference()?;
+    let storage_fee = parse_storage_fee(&description)?;
+
+    Ok(TransactionInfo {
+        account,
+        lt,
+        prev_trans_hash,
+        prev_trans_lt,
+        now,
+        total_fees,
+        storage_fee,
+        in_msg,
+        out_msgs: out_msgs.into_iter().map(|(_, m)| m).collect(),
+    })
+}
+
+fn parse_storage_fee(description: &Cell) -> Result<i64, TonCellError> {
+    let mut parser = ordinary_parser(description)?;
+    let has_storage_phase = match parser.load_u8(3)? {
+        0b000 => {
+            if parser.load_bit()? {
+                // trans_storage$0001 storage_ph:TrStoragePhase
+                true
+            } else {
+                // trans_ord$0000 credit_first:Bool storage_ph:(Maybe TrStoragePhase)
+                parser.skip_bits(1)?;
+                parser.load_bit()?
+            }
+        }
+        // trans_tick_tock$001 is_tock:Bool storage_ph:TrStoragePhase
+        0b001 => {
+            parser.skip_bits(1)?;
+            true
+        }
+        _ => false,
+    };
+    if has_storage_phase {
+        grams_to_i64(&Grams::read(&mut parser)?)
+    } else {
+        Ok(0)
+    }
+}
+
+pub(crate) fn parse_raw_message(cell: &Cell, testnet: bool) -> Result<RawMessage, TonCellError> {
+    let message = Message::from_cell(cell)?;
+    let (source, destination, value, fwd_fee, ihr_fee, created_lt) = match &message.info {
+        CommonMsgInfo::Int(info) => (
+            account_address(&info.src, testnet)?,
+            account_address(&info.dest, testnet)?,
+            grams_to_i64(&info.value.grams)?,
+            grams_to_i64(&info.fwd_fee)?,
+            grams_to_i64(&info.ihr_fee)?,
+            info.created_lt as i64,
+        ),
+        CommonMsgInfo::ExtIn(info) => (
+            account_address(&MsgAddress::Ext(info.src.clone()), testnet)?,
+            account_address(&MsgAddress::Int(info.dest.clone()), testnet)?,
+            0,
+            0,
+            0,
+            0,
+        ),
+        CommonMsgInfo::ExtOut(info) => (
+            account_address(&MsgAddress::Int(info.src.clone()), testnet)?,
+            account_address(&MsgAddress::Ext(info.dest.clone()), testnet)?,
+            0,
+            0,
+            0,
+            info.created_lt as i64,
+        ),
+    };
+    let body = message.body.value;
+    let init_state = match &message.init {
+        Some(init) => init.value.to_boc(false)?,
+        None => vec![],
+    };
+    Ok(RawMessage {
+        source,
+        destination,
+        value,
+        fwd_fee,
+        ihr_fee,
+        created_lt,
+        body_hash: Cell::cell_hash(&body).to_vec(),
+        msg_data: MsgData::Raw {
+            body: BagOfCells::from_root(body.deref().clone()).serialize(false)?,
+            init_state,
+        },
+    })
+}
+
+fn account_address(address: &MsgAddress, testnet: bool) -> Result<AccountAddress, TonCellError> {
+    let account_address = match address {
+        MsgAddress::Int(_) => TonAddress::from_msg_address(address.clone())
+            .map_err(|e| TonCellError::InvalidCellData(e.to_string()))?
+            .to_base64_url_flags(false, testnet),
+        MsgAddress::Ext(_) => String::new(),
+    };
+    Ok(AccountAddress { account_address })
+}
+
+/// Parses `BlockInfo` from the Merkle proof returned by `liteServer.getBlockHeader`.
+pub(crate) fn parse_block_header(
+    id: &BlockIdExt,
+    header_proof: &[u8],
+) -> Result<BlocksHeader, TonCellError> {
+    let block = merkle_proof_root(header_proof, 0)?;
+    let mut parser = ordinary_parser(&block)?;
+    expect_tag(&mut parser, 32, BLOCK_TAG as u64)?;
+    let global_id = parser.load_i32(32)?;
+
+    let info = block.reference(0)?;
+    let mut parser = ordinary_parser(info)?;
+    expect_tag(&mut parser, 32, BLOCK_INFO_TAG as u64)?;
+    let version = parser.load_i32(32)?;
+    let not_master = parser.load_bit()?;
+    let after_merge = parser.load_bit()?;
+    let before_split = parser.load_bit()?;
+    let after_split = parser.load_bit()?;
+    let want_split = parser.load_bit()?;
+    let want_merge = parser.load_bit()?;
+    let is_key_block = parser.load_bit()?;
+    let vert_seqno_incr = parser.load_bit()?;
+    let flags = parser.load_u8(8)?;
+    let _seqno = parser.load_u32(32)?;
+    let vert_seqno = parser.load_i32(32)?;
+    // shard_ident$00 shard_pfx_bits:(#<= 60) workchain_id:int32 shard_prefix:uint64
+    parser.skip_bits(2 + 6 + 32 + 64)?;
+    let gen_utime = parser.load_u32(32)?;
+    let start_lt = parser.load_u64(64)?;
+    let end_lt = parser.load_u64(64)?;
+    let validator_list_hash_short = parser.load_i32(32)?;
+    let catchain_seqno = parser.load_i32(32)?;
+    let min_ref_mc_seqno = parser.load_i32(32)?;
+    let prev_key_block_seqno = parser.load_i32(32)?;
+
+    if not_master {
+        let _master_ref = parser.next_reference()?;
+    }
+    let prev_ref = parser.next_reference()?;
+    let prev_blocks = parse_prev_blocks(id, &prev_ref, after_merge, after_split).ok();
+
+    Ok(BlocksHeader {
+        id: id.clone(),
+        global_id,
+        version,
+        flags: flags as i32,
+        after_merge,
+        after_split,
+        before_split,
+        want_merge,
+        want_split,
+        validator_list_hash_short,
+        catchain_seqno,
+        min_ref_mc_seqno,
+        is_key_block,
+        prev_key_block_seqno,
+        start_lt: start_lt as i64,
+        end_lt: end_lt as i64,
+        gen_utime: gen_utime as i64,
+        vert_seqno: vert_seqno_incr.then_some(vert_seqno),
+        prev_blocks,
+    })
+}
+
+fn parse_prev_blocks(
+    id: &BlockIdExt,
+    prev_ref: &Cell,
+    after_merge: bool,
+    after_split: bool,
+) -> Result<Vec<BlockIdExt>, TonCellError> {
+    let shard = id.shard as u64;
+    if after_merge {
+        let mut parser = ordinary_parser(prev_ref)?;
+        let left = parser.next_reference()?;
+        let right = parser.next_reference()?;
+        let low_bit = shard_low_bit(shard) >> 1;
+        Ok(vec![
+            parse_ext_blk_ref(id.workchain, shard - low_bit, &mut ordinary_parser(&left)?)?,
+            parse_ext_blk_ref(id.workchain, shard + low_bit, &mut ordinary_parser(&right)?)?,
+        ])
+    } else {
+        let prev_shard = if after_split {
+            shard_parent(shard)
+        } else {
+            shard
+        };
+        let mut parser = ordinary_parser(prev_ref)?;
+        Ok(vec![parse_ext_blk_ref(
+            id.workchain,
+            prev_shard,
+            &mut parser,
+        )?])
+    }
+}
+
+pub(crate) fn parse_ext_blk_ref(
+    workchain: i32,
+    shard: u64,
+    parser: &mut CellParser,
+) -> Result<BlockIdExt, TonCellError> {
+    // ext_blk_ref$_ end_lt:uint64 seq_no:uint32 root_hash:bits256 file_hash:bits256
+    parser.skip_bits(64)?;
+    let seqno = parser.load_i32(32)?;
+    let root_hash = parser.load_bytes(32)?;
+    let file_hash = parser.load_bytes(32)?;
+    Ok(BlockIdExt {
+        workchain,
+        shard: shard as i64,
+        seqno,
+        root_hash,
+        file_hash,
+    })
+}
+
+/// Collects all transactions of the block as `(account, lt, transaction)` ordered by account and lt.
+pub(crate) fn parse_block_transactions(
+    block: &[u8],
+) -> Result<Vec<(TonHash, u64, ArcCell)>, TonCellError> {
+    let block = BagOfCells::parse(block)?.single_root()?;
+    let mut parser = ordinary_parser(&block)?;
+    expect_tag(&mut parser, 32, BLOCK_TAG as u64)?;
+    let extra = block.reference(3)?;
+    let mut parser = ordinary_parser(extra)?;
+    expect_tag(&mut parser, 32, BLOCK_EXTRA_TAG as u64)?;
+    let account_blocks = extra.reference(2)?;
+    let mut parser = ordinary_parser(account_blocks)?;
+    if !parser.load_bit()? {
+        return Ok(vec![]);
+    }
+    let root = parser.next_reference()?;
+
+    let mut transactions = vec![];
+    dict_for_each(
+        &mut ordinary_parser(&root)?,
+        256,
+        &mut vec![],
+        &mut |_, parser| {
+            // extra:CurrencyCollection, acc_trans#5 account_addr:bits256
+            // transactions:(HashmapAug 64 ^Transaction CurrencyCollection)
+            CurrencyCollection::read(parser)?;
+            expect_tag(parser, 4, ACCOUNT_BLOCK_TAG as u64)?;
+            let account = parser.load_tonhash()?;
+            dict_for_each(parser, 64, &mut vec![], &mut |lt, parser| {
+                let tx = parser.next_reference()?;
+                transactions.push((account.clone(), bits_to_u64(lt), tx));
+                Ok(())
+            })
+        },
+    )?;
+    Ok(transactions)
+}
+
+/// Parses `ShardHashes` returned by `liteServer.getAllShardsInfo`.
+pub(crate) fn parse_shard_hashes(data: &[u8]) -> Result<Vec<BlockIdExt>, TonCellError> {
+    let root = BagOfCells::parse(data)?.single_root()?;
+    let mut workchains: Vec<_> = root
+        .parser()
+        .load_dict(32, key_reader_u32, val_reader_ref_cell)?
+        .into_iter()
+        .map(|(wc, tree)| (wc as i32, tree))
+        .collect();
+    workchains.sort_by_key(|(wc, _)| *wc);
+
+    let mut shards = vec![];
+    for (workchain, tree) in workchains {
+        collect_shards(
+            workchain,
+            tonlib_core::constants::SHARD_FULL,
+            &tree,
+            &mut shards,
+        )?;
+    }
+    Ok(shards)
+}
+
+fn collect_shards(
+    workchain: i32,
+    shard: u64,
+    tree: &Cell,
+    shards: &mut Vec<BlockIdExt>,
+) -> Result<(), TonCellError> {
+    let mut parser = ordinary_parser(tree)?;
+    if parser.load_bit()? {
+        // bt_fork$1 left:^(BinTree X) right:^(BinTree X)
+        let low_bit = shard_low_bit(shard) >> 1;
+        let left = parser.next_reference()?;
+        let right = parser.next_reference()?;
+        collect_shards(workchain, shard - low_bit, &left, shards)?;
+        collect_shards(workchain, shard + low_bit, &right, shards)?;
+    } else {
+        // bt_leaf$0 leaf:ShardDescr
+        shards.push(parse_shard_descr(workchain, shard, &mut parser)?);
+    }
+    Ok(())
+}
+
+pub(crate) fn parse_shard_descr(
+    workchain: i32,
+    shard: u64,
+    parser: &mut CellParser,
+) -> Result<BlockIdExt, TonCellError> {
+    let _tag = parser.load_u8(4)?;
+    let seqno = parser.load_i32(32)?;
+    // reg_mc_seqno:uint32 start_lt:uint64 end_lt:uint64
+    parser.skip_bits(32 + 64 + 64)?;
+    let root_hash = parser.load_bytes(32)?;
+    let file_hash = parser.load_bytes(32)?;
+    Ok(BlockIdExt {
+        workchain,
+        shard: shard as i64,
+        seqno,
+        root_hash,
+        file_hash,
+    })
+}
+
+/// Extracts `ConfigParams.config` from the masterchain state proved by `config_proof`.
+pub(crate) fn parse_config_dict(config_proof: &[u8]) -> Result<ArcCell, TonCellError> {
+    let state = merkle_proof_root(config_proof, 0)?;
+    let mut parser = ordinary_parser(&state)?;
+    expect_tag(&mut parser, 32, SHARD_STATE_TAG as u64)?;
+    let custom = match state.references().get(3) {
+        Some(custom) => custom,
+        None => {
+            return Err(TonCellError::InvalidCellData(
+                "McStateExtra is absent in the state".to_string(),
+            ))
+        }
+    };
+    let mut parser = ordinary_parser(custom)?;
+    expect_tag(&mut parser, 16, MC_STATE_EXTRA_TAG as u64)?;
+    // shard_hashes:ShardHashes config_addr:bits256
+    if parser.load_bit()? {
+        let _shard_hashes = parser.next_reference()?;
+    }
+    parser.skip_bits(256)?;
+    parser.next_reference()
+}
+
+/// Looks up a single config parameter in the dictionary returned by [`parse_config_dict`].
+pub(crate) fn find_config_param(
+    config: &ArcCell,
+    param: u32,
+) -> Result<Option<ArcCell>, TonCellError> {
+    dict_lookup(config, &param.to_be_bytes(), 32, |parser| {
+        parser.next_reference()
+    })
+}
+
+/// Walks a `Hashmap` along `key` without touching other branches, so it works on pruned proofs.
+pub(crate) fn dict_lookup<T>(
+    root: &ArcCell,
+    key: &[u8],
+    key_len: usize,
+    read_value: impl FnOnce(&mut CellParser) -> Result<T, TonCellError>,
+) -> Result<Option<T>, TonCellError> {
+    let key_bit = |pos: usize| (key[pos / 8] >> (7 - pos % 8)) & 1 == 1;
+    let mut cell = root.clone();
+    let mut pos = 0;
+    loop {
+        let mut parser = ordinary_parser(&cell)?;
+        let remaining = key_len - pos;
+        let label = load_label(&mut parser, remaining)?;
+        if label.len() > remaining {
+            return Err(TonCellError::InvalidCellData(format!(
+                "Dictionary label is too long: {} > {}",
+                label.len(),
+                remaining
+            )));
+        }
+        if label
+            .iter()
+            .enumerate()
+            .any(|(i, bit)| key_bit(pos + i) != *bit)
+        {
+            return Ok(None);
+        }
+        pos += label.len();
+        if pos == key_len {
+            return read_value(&mut parser).map(Some);
+        }
+        let next = cell.reference(key_bit(pos) as usize)?.clone();
+        pos += 1;
+        cell = next;
+    }
+}
+
+/// Visits every leaf of a (possibly augmented) `Hashmap` node which starts at the parser position.
+///
+/// Visitor receives the key bits and the parser positioned right after the leaf label.
+pub(crate) fn dict_for_each<F>(
+    parser: &mut CellParser,
+    key_len: usize,
+    prefix: &mut Vec<bool>,
+    visit: &mut F,
+) -> Result<(), TonCellError>
+where
+    F: FnMut(&[bool], &mut CellParser) -> Result<(), TonCellError>,
+{
+    let depth = prefix.len();
+    let label = load_label(parser, key_len - depth)?;
+    prefix.extend(label);
+    if prefix.len() > key_len {
+        return Err(TonCellError::InvalidCellData(format!(
+            "Dictionary label is too long: {} > {}",
+            prefix.len(),
+            key_len
+        )));
+    }
+    if prefix.len() == key_len {
+        visit(prefix, parser)?;
+    } else {
+        let left = parser.next_reference()?;
+        let right = parser.next_reference()?;
+        for (bit, child) in [(false, left), (true, right)] {
+            prefix.push(bit);
+            dict_for_each(&mut ordinary_parser(&child)?, key_len, prefix, visit)?;
+            prefix.pop();
+        }
+    }
+    prefix.truncate(depth);
+    Ok(())
+}
+
+pub(crate) fn bits_to_u64(bits: &[bool]) -> u64 {
+    bits.iter().fold(0, |acc, bit| (acc << 1) | *bit as u64)
+}
+
+fn load_label(parser: &mut CellParser, max_len: usize) -> Result<Vec<bool>, TonCellError> {
+    let len_bits = (usize::BITS - max_len.leading_zeros()) as usize;
+    if !parser.load_bit()? {
+        // hml_short$0 len:(Unary ~n) s:(n * Bit)
+        let len = parser.load_unary_length()?;
+        (0..len).map(|_| parser.load_bit()).collect()
+    } else if !parser.load_bit()? {
+        // hml_long$10 n:(#<= m) s:(n * Bit)
+        let len = parser.load_u32(len_bits)? as usize;
+        (0..len).map(|_| parser.load_bit()).collect()
+    } else {
+        // hml_same$11 v:Bit n:(#<= m)
+        let bit = parser.load_bit()?;
+        let len = parser.load_u32(len_bits)? as usize;
+        Ok(vec![bit; len])
+    }
+}
+
+pub(crate) fn ordinary_parser(cell: &Cell) -> Result<CellParser<'_>, TonCellError> {
+    if cell.is_exotic() {
+        return Err(TonCellError::InvalidCellData(
+            "Required cell is pruned from the proof".to_string(),
+        ));
+    }
+    Ok(cell.parser())
+}
+
+pub(crate) fn expect_tag(
+    parser: &mut CellParser,
+    bits: usize,
+    expected: u64,
+) -> Result<(), TonCellError> {
+    let tag = parser.load_u64(bits)?;
+    if tag != expected {
+        return Err(TonCellError::InvalidCellData(format!(
+            "Invalid tag: {tag:x}, expected: {expected:x}"
+        )));
+    }
+    Ok(())
+}
+
+pub(crate) fn skip_var_uint(parser: &mut CellParser, len_bits: usize) -> Result<(), TonCellError> {
+    let len = parser.load_u8(len_bits)? as usize;
+    parser.skip_bits(len * 8)
+}
+
+fn grams_to_i64(grams: &Grams) -> Result<i64, TonCellError> {
+    grams.amount.to_i64().ok_or_else(|| {
+        TonCellError::InvalidCellData(format!("Amount {} doesn't fit into i64", grams.amount))
+    })
+}
+
+pub(crate) fn shard_low_bit(shard: u64) -> u64 {
+    shard & shard.wrapping_neg()
+}
+
+fn shard_parent(shard: u64) -> u64 {
+    let low_bit = shard_low_bit(shard);
+    (shard - low_bit) | (low_bit << 1)
+}
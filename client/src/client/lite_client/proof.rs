@@ -0,0 +1,862 @@
+//! Verification of lite server proofs, mirrors `crypto/block/check-proof.cpp` of the TON node.
+
+use std::collections::HashSet;
+
+use adnl::crypto::PublicKey;
+use sha2::{Digest, Sha256, Sha512};
+use ton_liteapi::tl::common::{BlockIdExt as LiteBlockIdExt, BlockLink, Signature};
+use ton_liteapi::tl::response::AccountState;
+use tonlib_core::cell::{ArcCell, BagOfCells, Cell, CellBuilder, CellParser};
+use tonlib_core::constants::{MASTERCHAIN_ID, SHARD_FULL};
+use tonlib_core::TonHash;
+
+use super::from_lite_block_id;
+use super::parse::{
+    dict_for_each, dict_lookup, expect_tag, find_config_param, find_shard_account, ordinary_parser,
+    parse_ext_blk_ref, parse_shard_descr, shard_low_bit, skip_var_uint, TransactionInfo,
+    BLOCK_EXTRA_TAG, BLOCK_INFO_TAG, BLOCK_TAG, MC_STATE_EXTRA_TAG, SHARD_STATE_TAG,
+};
+use crate::client::TonProofError;
+use crate::tl::{BlockIdExt, InternalTransactionId};
+
+const MERKLE_PROOF_TYPE: u8 = 3;
+const MERKLE_UPDATE_TYPE: u8 = 4;
+const MC_BLOCK_EXTRA_TAG: u16 = 0xcca5;
+const VALIDATORS_TAG: u8 = 0x11;
+const VALIDATORS_EXT_TAG: u8 = 0x12;
+const ED25519_PUBKEY_TAG: u32 = 0x8e81278a;
+const CATCHAIN_CONFIG_TAG: u8 = 0xc1;
+const CATCHAIN_CONFIG_NEW_TAG: u8 = 0xc2;
+const CATCHAIN_CONFIG_PARAM: u32 = 28;
+const CUR_VALIDATORS_PARAM: u32 = 34;
+/// TL id of `pub.ed25519 key:int256`.
+const PUB_ED25519_TL_ID: u32 = 0x4813b4c6;
+/// TL id of `ton.blockId root_cell_hash:int256 file_hash:int256`.
+const TON_BLOCK_ID_TL_ID: u32 = 0xc50b6e70;
+
+/// `BlockInfo` fields used by the checks.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct BlockInfo {
+    pub workchain: i32,
+    pub shard: u64,
+    pub seqno: u32,
+    pub key_block: bool,
+    pub gen_utime: u32,
+    pub catchain_seqno: u32,
+}
+
+/// Masterchain validator selected to sign blocks.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Validator {
+    pub node_id: TonHash,
+    pub public_key: [u8; 32],
+    pub weight: u64,
+}
+
+impl Validator {
+    pub fn new(public_key: [u8; 32], weight: u64) -> Validator {
+        let mut hasher = Sha256::new();
+        hasher.update(PUB_ED25519_TL_ID.to_le_bytes());
+        hasher.update(public_key);
+        let node_id: [u8; 32] = hasher.finalize().into();
+        Validator {
+            node_id: node_id.into(),
+            public_key,
+            weight,
+        }
+    }
+}
+
+/// Checks `liteServer.accountState` against `state.id` and returns the proved shard state.
+///
+/// `state.id` itself must be checked by the caller.
+pub(crate) fn check_account_state(
+    state: &AccountState,
+    workchain: i32,
+    account: &TonHash,
+) -> Result<ArcCell, TonProofError> {
+    let shard_block = &state.shardblk;
+    if shard_block.workchain != workchain || !shard_contains(shard_block.shard, account) {
+        return Err(TonProofError::InvalidProof(format!(
+            "Block {} doesn't contain account {}:{}",
+            shard_block, workchain, account
+        )));
+    }
+    if workchain == MASTERCHAIN_ID {
+        if *shard_block != state.id {
+            return Err(block_mismatch(&state.id, shard_block));
+        }
+    } else {
+        check_shard_proof(&state.id, shard_block, &state.shard_proof)?;
+    }
+
+    let roots = proof_roots(&state.proof, 2)?;
+    let (block, _) = check_block_header(&roots[0], shard_block)?;
+    let shard_state = check_state(&block, &roots[1])?;
+    let expected = match find_shard_account(&shard_state, account)? {
+        Some(shard_account) => shard_account.account.get_hash(0),
+        None => account_none_hash()?,
+    };
+    let actual = if state.state.is_empty() {
+        account_none_hash()?
+    } else {
+        let root = BagOfCells::parse(&state.state)?.single_root()?;
+        Cell::cell_hash(&root)
+    };
+    if expected != actual {
+        return Err(TonProofError::HashMismatch {
+            what: "account state",
+            expected,
+            actual,
+        });
+    }
+    Ok(shard_state)
+}
+
+/// Checks that `shard_block` is registered in the state of masterchain block `mc_block`.
+pub(crate) fn check_shard_proof(
+    mc_block: &LiteBlockIdExt,
+    shard_block: &LiteBlockIdExt,
+    shard_proof: &[u8],
+) -> Result<(), TonProofError> {
+    let roots = proof_roots(shard_proof, 2)?;
+    let (block, _) = check_block_header(&roots[0], mc_block)?;
+    let state = check_state(&block, &roots[1])?;
+    let descr = find_shard_descr(&state, shard_block.workchain, shard_block.shard)?;
+    let expected = from_lite_block_id(shard_block);
+    if descr != expected {
+        return Err(TonProofError::BlockMismatch {
+            expected: Box::new(expected),
+            actual: Box::new(descr),
+        });
+    }
+    Ok(())
+}
+
+/// Checks a step of `liteServer.partialBlockProof` and returns `(from, to, to_key_block)`.
+pub(crate) fn check_block_link(
+    link: &BlockLink,
+) -> Result<(&LiteBlockIdExt, &LiteBlockIdExt, bool), TonProofError> {
+    match link {
+        BlockLink::BlockLinkBack {
+            to_key_block,
+            from,
+            to,
+            dest_proof,
+            proof,
+            state_proof,
+        } => {
+            check_link_blocks(from, to, false)?;
+            let (block, _) = check_block_header(&proof_roots(proof, 1)?[0], from)?;
+            let state = check_state(&block, &proof_roots(state_proof, 1)?[0])?;
+            let (is_key, prev) = find_prev_block(&state, to.seqno)?.ok_or_else(|| {
+                TonProofError::InvalidProof(format!(
+                    "Block {} is absent in prev_blocks of {}",
+                    to.seqno, from
+                ))
+            })?;
+            let expected = from_lite_block_id(to);
+            if prev != expected {
+                return Err(TonProofError::BlockMismatch {
+                    expected: Box::new(expected),
+                    actual: Box::new(prev),
+                });
+            }
+            check_key_block_flag(to, is_key, *to_key_block)?;
+            if !dest_proof.is_empty() {
+                let (_, info) = check_block_header(&proof_roots(dest_proof, 1)?[0], to)?;
+                check_key_block_flag(to, info.key_block, *to_key_block)?;
+            }
+            Ok((from, to, *to_key_block))
+        }
+        BlockLink::BlockLinkForward {
+            to_key_block,
+            from,
+            to,
+            dest_proof,
+            config_proof,
+            signatures,
+        } => {
+            check_link_blocks(from, to, true)?;
+            let (_, info) = check_block_header(&proof_roots(dest_proof, 1)?[0], to)?;
+            check_key_block_flag(to, info.key_block, *to_key_block)?;
+            if info.catchain_seqno != signatures.catchain_seqno {
+                return Err(TonProofError::InvalidProof(format!(
+                    "Catchain seqno mismatch for block {}: {} != {}",
+                    to, signatures.catchain_seqno, info.catchain_seqno
+                )));
+            }
+            let (key_block, _) = check_block_header(&proof_roots(config_proof, 1)?[0], from)?;
+            let config = parse_block_config(&key_block)?;
+            let validators = mc_validators(&config, signatures.catchain_seqno)?;
+            check_block_signatures(&validators, &signatures.signatures, to)?;
+            Ok((from, to, *to_key_block))
+        }
+    }
+}
+
+/// Checks that `transactions` form the chain which starts at `from` and belong to `account`.
+pub(crate) fn check_transactions<'a>(
+    account: &TonHash,
+    from: &InternalTransactionId,
+    transactions: impl IntoIterator<Item = (&'a TransactionInfo, &'a Cell)>,
+) -> Result<(), TonProofError> {
+    let mut expected_hash = TonHash::try_from(from.hash.as_slice())
+        .map_err(|e| TonProofError::InvalidProof(e.to_string()))?;
+    let mut expected_lt = from.lt;
+    for (tx, cell) in transactions {
+        let hash = Cell::cell_hash(cell);
+        if tx.lt as i64 != expected_lt || hash != expected_hash {
+            return Err(TonProofError::TransactionMismatch {
+                lt: expected_lt,
+                expected: expected_hash,
+                actual: hash,
+            });
+        }
+        if tx.account != *account {
+            return Err(TonProofError::HashMismatch {
+                what: "transaction account",
+                expected: account.clone(),
+                actual: tx.account.clone(),
+            });
+        }
+        expected_hash = tx.prev_trans_hash.clone();
+        expected_lt = tx.prev_trans_lt as i64;
+    }
+    Ok(())
+}
+
+/// Checks that validators with more than 2/3 of the total weight signed block `id`.
+pub(crate) fn check_block_signatures(
+    validators: &[Validator],
+    signatures: &[Signature],
+    id: &LiteBlockIdExt,
+) -> Result<(), TonProofError> {
+    let total: u64 = validators.iter().map(|v| v.weight).sum();
+    let mut message = Vec::with_capacity(4 + 32 + 32);
+    message.extend_from_slice(&TON_BLOCK_ID_TL_ID.to_le_bytes());
+    message.extend_from_slice(&id.root_hash.0);
+    message.extend_from_slice(&id.file_hash.0);
+
+    let mut signed_by = HashSet::new();
+    let mut signed = 0u64;
+    for signature in signatures {
+        let node_id = TonHash::from(signature.node_id_short.0);
+        let validator = validators
+            .iter()
+            .find(|v| v.node_id == node_id)
+            .ok_or_else(|| TonProofError::UnknownValidator(node_id.clone()))?;
+        if !signed_by.insert(node_id.clone()) {
+            return Err(TonProofError::InvalidProof(format!(
+                "Duplicate signature of validator {}",
+                node_id
+            )));
+        }
+        let public_key = PublicKey::from_bytes(validator.public_key)
+            .ok_or_else(|| TonProofError::InvalidSignature(node_id.clone()))?;
+        let signature: &[u8; 64] = signature
+            .signature
+            .as_slice()
+            .try_into()
+            .map_err(|_| TonProofError::InvalidSignature(node_id.clone()))?;
+        if !public_key.verify_raw(&message, signature) {
+            return Err(TonProofError::InvalidSignature(node_id));
+        }
+        signed += validator.weight;
+    }
+    if signed as u128 * 3 <= total as u128 * 2 {
+        return Err(TonProofError::InsufficientSignatures { signed, total });
+    }
+    Ok(())
+}
+
+/// Selects masterchain validators from the key block config like `Config::do_compute_validator_set`.
+pub(crate) fn mc_validators(
+    config: &ArcCell,
+    catchain_seqno: u32,
+) -> Result<Vec<Validator>, TonProofError> {
+    let vset = find_config_param(config, CUR_VALIDATORS_PARAM)?.ok_or_else(|| {
+        TonProofError::InvalidProof("Validator set is absent in the config".to_string())
+    })?;
+    let (main, list) = parse_validator_set(&vset)?;
+    let count = list.len().min(main as usize);
+    let shuffle = match find_config_param(config, CATCHAIN_CONFIG_PARAM)? {
+        Some(catchain_config) => parse_shuffle_mc_validators(&catchain_config)?,
+        None => false,
+    };
+    if !shuffle {
+        return Ok(list.into_iter().take(count).collect());
+    }
+    let mut prng = ValidatorSetPrng::new(MASTERCHAIN_ID, SHARD_FULL, catchain_seqno);
+    let mut idx = vec![0; count];
+    for i in 0..count {
+        let j = prng.next_ranged(i as u64 + 1) as usize;
+        idx[i] = idx[j];
+        idx[j] = i;
+    }
+    Ok(idx.into_iter().map(|i| list[i].clone()).collect())
+}
+
+/// Verifies the Merkle proof cell and returns the proved cell.
+pub(crate) fn virtualize(
+    proof: &Cell,
+    expected: &TonHash,
+    what: &'static str,
+) -> Result<ArcCell, TonProofError> {
+    let data = proof.data();
+    if !proof.is_exotic() || data.first() != Some(&MERKLE_PROOF_TYPE) || data.len() < 33 {
+        return Err(TonProofError::InvalidProof(format!(
+            "Merkle proof of {} expected",
+            what
+        )));
+    }
+    let proved = hash_at(data, 1);
+    let root = proof.reference(0)?;
+    let actual = root.get_hash(0);
+    if actual != proved {
+        return Err(TonProofError::HashMismatch {
+            what: "Merkle proof",
+            expected: proved,
+            actual,
+        });
+    }
+    if proved != *expected {
+        return Err(TonProofError::HashMismatch {
+            what,
+            expected: expected.clone(),
+            actual: proved,
+        });
+    }
+    Ok(root.clone())
+}
+
+/// Checks the block header proof against `id` and returns the proved block.
+fn check_block_header(
+    proof: &Cell,
+    id: &LiteBlockIdExt,
+) -> Result<(ArcCell, BlockInfo), TonProofError> {
+    let block = virtualize(proof, &TonHash::from(id.root_hash.0), "block")?;
+    let info = parse_block_info(&block)?;
+    if info.workchain != id.workchain || info.shard != id.shard || info.seqno != id.seqno {
+        let actual = LiteBlockIdExt {
+            workchain: info.workchain,
+            shard: info.shard,
+            seqno: info.seqno,
+            ..id.clone()
+        };
+        return Err(block_mismatch(id, &actual));
+    }
+    Ok((block, info))
+}
+
+/// Checks the state proof against `state_update` of the block and returns the proved state.
+fn check_state(block: &Cell, proof: &Cell) -> Result<ArcCell, TonProofError> {
+    let update = block.reference(2)?;
+    let data = update.data();
+    if !update.is_exotic() || data.first() != Some(&MERKLE_UPDATE_TYPE) || data.len() < 65 {
+        return Err(TonProofError::InvalidProof(
+            "state_update is pruned from the block proof".to_string(),
+        ));
+    }
+    virtualize(proof, &hash_at(data, 33), "shard state")
+}
+
+fn check_link_blocks(
+    from: &LiteBlockIdExt,
+    to: &LiteBlockIdExt,
+    forward: bool,
+) -> Result<(), TonProofError> {
+    if from.workchain != MASTERCHAIN_ID || to.workchain != MASTERCHAIN_ID {
+        return Err(TonProofError::InvalidProof(format!(
+            "Block link {} -> {} is not in masterchain",
+            from, to
+        )));
+    }
+    if (to.seqno > from.seqno) != forward || to.seqno == from.seqno {
+        return Err(TonProofError::InvalidProof(format!(
+            "Invalid direction of block link {} -> {}",
+            from, to
+        )));
+    }
+    Ok(())
+}
+
+fn check_key_block_flag(
+    id: &LiteBlockIdExt,
+    is_key: bool,
+    expected: bool,
+) -> Result<(), TonProofError> {
+    if is_key != expected {
+        return Err(TonProofError::InvalidProof(format!(
+            "Block {} is_key_block = {}, expected {}",
+            id, is_key, expected
+        )));
+    }
+    Ok(())
+}
+
+fn parse_block_info(block: &Cell) -> Result<BlockInfo, TonProofError> {
+    let mut parser = ordinary_parser(block)?;
+    expect_tag(&mut parser, 32, BLOCK_TAG as u64)?;
+    let info = block.reference(0)?;
+    let mut parser = ordinary_parser(info)?;
+    expect_tag(&mut parser, 32, BLOCK_INFO_TAG as u64)?;
+    // version:uint32 not_master after_merge before_split after_split want_split want_merge
+    parser.skip_bits(32 + 6)?;
+    let key_block = parser.load_bit()?;
+    // vert_seqno_incr:(## 1) flags:(## 8)
+    parser.skip_bits(1 + 8)?;
+    let seqno = parser.load_u32(32)?;
+    // vert_seq_no:# shard_ident$00
+    parser.skip_bits(32 + 2)?;
+    let pfx_bits = parser.load_u8(6)?;
+    let workchain = parser.load_i32(32)?;
+    let prefix = parser.load_u64(64)?;
+    let gen_utime = parser.load_u32(32)?;
+    // start_lt:uint64 end_lt:uint64 gen_validator_list_hash_short:uint32
+    parser.skip_bits(64 + 64 + 32)?;
+    let catchain_seqno = parser.load_u32(32)?;
+    Ok(BlockInfo {
+        workchain,
+        shard: prefix | (1 << (63 - pfx_bits.min(63))),
+        seqno,
+        key_block,
+        gen_utime,
+        catchain_seqno,
+    })
+}
+
+/// Extracts `ConfigParams.config` from `McBlockExtra` of the key block.
+fn parse_block_config(block: &Cell) -> Result<ArcCell, TonProofError> {
+    let extra = block.reference(3)?;
+    let mut parser = ordinary_parser(extra)?;
+    expect_tag(&mut parser, 32, BLOCK_EXTRA_TAG as u64)?;
+    // rand_seed:bits256 created_by:bits256 custom:(Maybe ^McBlockExtra)
+    parser.skip_bits(256 + 256)?;
+    if !parser.load_bit()? {
+        return Err(TonProofError::InvalidProof(
+            "McBlockExtra is absent in the block".to_string(),
+        ));
+    }
+    let custom = extra.reference(3)?;
+    let mut parser = ordinary_parser(custom)?;
+    expect_tag(&mut parser, 16, MC_BLOCK_EXTRA_TAG as u64)?;
+    if !parser.load_bit()? {
+        return Err(TonProofError::InvalidProof(
+            "Config is absent in non-key block".to_string(),
+        ));
+    }
+    // shard_hashes:ShardHashes shard_fees:(HashmapAugE 96 ShardFeeCreated ShardFeeCreated)
+    for _ in 0..2 {
+        if parser.load_bit()? {
+            parser.next_reference()?;
+        }
+    }
+    // ShardFeeCreated fees:CurrencyCollection create:CurrencyCollection
+    for _ in 0..2 {
+        skip_var_uint(&mut parser, 4)?;
+        if parser.load_bit()? {
+            parser.next_reference()?;
+        }
+    }
+    // ^[ prev_blk_signatures recover_create_msg mint_msg ] config_addr:bits256 config:^Cell
+    parser.next_reference()?;
+    parser.skip_bits(256)?;
+    Ok(parser.next_reference()?)
+}
+
+/// Returns `shuffle_mc_validators` of `CatchainConfig`.
+fn parse_shuffle_mc_validators(config: &Cell) -> Result<bool, TonProofError> {
+    let mut parser = ordinary_parser(config)?;
+    match parser.load_u8(8)? {
+        CATCHAIN_CONFIG_TAG => Ok(false),
+        CATCHAIN_CONFIG_NEW_TAG => {
+            parser.skip_bits(7)?;
+            Ok(parser.load_bit()?)
+        }
+        tag => Err(TonProofError::InvalidProof(format!(
+            "Unknown CatchainConfig tag: {:x}",
+            tag
+        ))),
+    }
+}
+
+/// Parses `ValidatorSet`, returns `main` and the validators ordered by index.
+fn parse_validator_set(vset: &Cell) -> Result<(u16, Vec<Validator>), TonProofError> {
+    let mut parser = ordinary_parser(vset)?;
+    let tag = parser.load_u8(8)?;
+    // utime_since:uint32 utime_until:uint32 total:(## 16)
+    parser.skip_bits(32 + 32 + 16)?;
+    let main = parser.load_u16(16)?;
+    let mut list = vec![];
+    let mut visit = |_: &[bool], parser: &mut CellParser| {
+        list.push(parse_validator_descr(parser)?);
+        Ok(())
+    };
+    match tag {
+        VALIDATORS_TAG => dict_for_each(&mut parser, 16, &mut vec![], &mut visit)?,
+        VALIDATORS_EXT_TAG => {
+            // total_weight:uint64 list:(HashmapE 16 ValidatorDescr)
+            parser.skip_bits(64)?;
+            if parser.load_bit()? {
+                let root = parser.next_reference()?;
+                dict_for_each(&mut ordinary_parser(&root)?, 16, &mut vec![], &mut visit)?;
+            }
+        }
+        tag => {
+            return Err(TonProofError::InvalidProof(format!(
+                "Unknown ValidatorSet tag: {:x}",
+                tag
+            )))
+        }
+    }
+    Ok((main, list))
+}
+
+fn parse_validator_descr(
+    parser: &mut CellParser,
+) -> Result<Validator, tonlib_core::cell::TonCellError> {
+    // validator#53 or validator_addr#73, public_key:SigPubKey weight:uint64
+    parser.skip_bits(8)?;
+    expect_tag(parser, 32, ED25519_PUBKEY_TAG as u64)?;
+    let mut public_key = [0u8; 32];
+    parser.load_slice(&mut public_key)?;
+    let weight = parser.load_u64(64)?;
+    Ok(Validator::new(public_key, weight))
+}
+
+/// Finds `ShardDescr` of the shard containing `shard` in `McStateExtra.shard_hashes`.
+fn find_shard_descr(state: &Cell, workchain: i32, shard: u64) -> Result<BlockIdExt, TonProofError> {
+    let mut parser = mc_state_extra(state)?;
+    if !parser.load_bit()? {
+        return Err(TonProofError::InvalidProof(
+            "ShardHashes are empty".to_string(),
+        ));
+    }
+    let root = parser.next_reference()?;
+    let mut tree = dict_lookup(&root, &(workchain as u32).to_be_bytes(), 32, |parser| {
+        parser.next_reference()
+    })?
+    .ok_or_else(|| {
+        TonProofError::InvalidProof(format!("Workchain {} is absent in ShardHashes", workchain))
+    })?;
+    let mut current = SHARD_FULL;
+    loop {
+        let mut parser = ordinary_parser(&tree)?;
+        if !parser.load_bit()? {
+            // bt_leaf$0 leaf:ShardDescr
+            return Ok(parse_shard_descr(workchain, current, &mut parser)?);
+        }
+        // bt_fork$1 left:^(BinTree X) right:^(BinTree X)
+        let low_bit = shard_low_bit(current) >> 1;
+        if low_bit == 0 {
+            return Err(TonProofError::InvalidProof(
+                "ShardHashes tree is too deep".to_string(),
+            ));
+        }
+        let left = parser.next_reference()?;
+        let right = parser.next_reference()?;
+        (tree, current) = if shard < current {
+            (left, current - low_bit)
+        } else {
+            (right, current + low_bit)
+        };
+    }
+}
+
+/// Looks up masterchain block `seqno` in `McStateExtra.prev_blocks`, returns `(is_key, block)`.
+fn find_prev_block(state: &Cell, seqno: u32) -> Result<Option<(bool, BlockIdExt)>, TonProofError> {
+    let mut parser = mc_state_extra(state)?;
+    // shard_hashes:ShardHashes config:ConfigParams
+    if parser.load_bit()? {
+        parser.next_reference()?;
+    }
+    parser.skip_bits(256)?;
+    parser.next_reference()?;
+    let info = parser.next_reference()?;
+    let mut parser = ordinary_parser(&info)?;
+    // flags:(## 16) validator_info:ValidatorInfo prev_blocks:OldMcBlocksInfo
+    parser.skip_bits(16 + 65)?;
+    if !parser.load_bit()? {
+        return Ok(None);
+    }
+    let root = parser.next_reference()?;
+    let prev = dict_lookup(&root, &seqno.to_be_bytes(), 32, |parser| {
+        // extra:KeyMaxLt value:KeyExtBlkRef
+        parser.skip_bits(1 + 64)?;
+        let is_key = parser.load_bit()?;
+        let block = parse_ext_blk_ref(MASTERCHAIN_ID, SHARD_FULL, parser)?;
+        Ok((is_key, block))
+    })?;
+    Ok(prev)
+}
+
+fn mc_state_extra(state: &Cell) -> Result<CellParser<'_>, TonProofError> {
+    let mut parser = ordinary_parser(state)?;
+    expect_tag(&mut parser, 32, SHARD_STATE_TAG as u64)?;
+    let custom = state.references().get(3).ok_or_else(|| {
+        TonProofError::InvalidProof("McStateExtra is absent in the state".to_string())
+    })?;
+    let mut parser = ordinary_parser(custom)?;
+    expect_tag(&mut parser, 16, MC_STATE_EXTRA_TAG as u64)?;
+    Ok(parser)
+}
+
+fn proof_roots(boc: &[u8], count: usize) -> Result<Vec<ArcCell>, TonProofError> {
+    let roots = BagOfCells::parse(boc)?.roots;
+    if roots.len() < count {
+        return Err(TonProofError::InvalidProof(format!(
+            "Expected {} proof roots, got {}",
+            count,
+            roots.len()
+        )));
+    }
+    Ok(roots)
+}
+
+fn shard_contains(shard: u64, account: &TonHash) -> bool {
+    let mut prefix = [0u8; 8];
+    prefix.copy_from_slice(&account.as_slice()[..8]);
+    let mask = !((shard_low_bit(shard) << 1).wrapping_sub(1));
+    (u64::from_be_bytes(prefix) ^ shard) & mask == 0
+}
+
+fn account_none_hash() -> Result<TonHash, TonProofError> {
+    Ok(CellBuilder::new().store_bit(false)?.build()?.cell_hash())
+}
+
+fn hash_at(data: &[u8], offset: usize) -> TonHash {
+    let mut hash = [0u8; 32];
+    hash.copy_from_slice(&data[offset..offset + 32]);
+    hash.into()
+}
+
+pub(crate) fn block_mismatch(expected: &LiteBlockIdExt, actual: &LiteBlockIdExt) -> TonProofError {
+    TonProofError::BlockMismatch {
+        expected: Box::new(from_lite_block_id(expected)),
+        actual: Box::new(from_lite_block_id(actual)),
+    }
+}
+
+/// Port of `ValidatorSetPRNG` used to shuffle validators.
+struct ValidatorSetPrng {
+    // seed:bits256 shard:uint64 workchain:int32 cc_seqno:uint32
+    data: [u8; 48],
+    hash: [u8; 64],
+    pos: usize,
+}
+
+impl ValidatorSetPrng {
+    fn new(workchain: i32, shard: u64, catchain_seqno: u32) -> ValidatorSetPrng {
+        let mut data = [0u8; 48];
+        data[32..40].copy_from_slice(&shard.to_be_bytes());
+        data[40..44].copy_from_slice(&workchain.to_be_bytes());
+        data[44..48].copy_from_slice(&catchain_seqno.to_be_bytes());
+        ValidatorSetPrng {
+            data,
+            hash: [0u8; 64],
+            pos: 8,
+        }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        if self.pos == 8 {
+            self.hash = Sha512::digest(self.data).into();
+            for byte in self.data[..32].iter_mut().rev() {
+                *byte = byte.wrapping_add(1);
+                if *byte != 0 {
+                    break;
+                }
+            }
+            self.pos = 0;
+        }
+        let mut value = [0u8; 8];
+        value.copy_from_slice(&self.hash[self.pos * 8..self.pos * 8 + 8]);
+        self.pos += 1;
+        u64::from_be_bytes(value)
+    }
+
+    /// Returns a number in `0..range`.
+    fn next_ranged(&mut self, range: u64) -> u64 {
+        ((self.next_u64() as u128 * range as u128) >> 64) as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use adnl::crypto::{KeyPair, SecretKey};
+    use ton_liteapi::tl::common::Int256;
+    use tonlib_core::types::ZERO_HASH;
+
+    use super::*;
+
+    fn block_id() -> LiteBlockIdExt {
+        LiteBlockIdExt {
+            workchain: MASTERCHAIN_ID,
+            shard: SHARD_FULL,
+            seqno: 100,
+            root_hash: Int256([1; 32]),
+            file_hash: Int256([2; 32]),
+        }
+    }
+
+    fn sign(keypair: &KeyPair, id: &LiteBlockIdExt) -> Signature {
+        let mut message = TON_BLOCK_ID_TL_ID.to_le_bytes().to_vec();
+        message.extend_from_slice(&id.root_hash.0);
+        message.extend_from_slice(&id.file_hash.0);
+        let validator = Validator::new(keypair.public_key.to_bytes(), 0);
+        Signature {
+            node_id_short: Int256(validator.node_id.into()),
+            signature: keypair.sign_raw(&message).to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_check_block_signatures() -> anyhow::Result<()> {
+        let keys: Vec<_> = (1..=4u8)
+            .map(|i| KeyPair::from(&SecretKey::from_bytes([i; 32])))
+            .collect();
+        let validators: Vec<_> = keys
+            .iter()
+            .map(|k| Validator::new(k.public_key.to_bytes(), 10))
+            .collect();
+        let id = block_id();
+
+        let signatures: Vec<_> = keys.iter().take(3).map(|k| sign(k, &id)).collect();
+        check_block_signatures(&validators, &signatures, &id)?;
+
+        let result = check_block_signatures(&validators, &signatures[..2], &id);
+        assert!(matches!(
+            result,
+            Err(TonProofError::InsufficientSignatures {
+                signed: 20,
+                total: 40
+            })
+        ));
+
+        let mut other_id = id.clone();
+        other_id.seqno += 1;
+        other_id.root_hash = Int256([3; 32]);
+        let result = check_block_signatures(&validators, &signatures, &other_id);
+        assert!(matches!(result, Err(TonProofError::InvalidSignature(_))));
+
+        let stranger = KeyPair::from(&SecretKey::from_bytes([9; 32]));
+        let result = check_block_signatures(&validators, &[sign(&stranger, &id)], &id);
+        assert!(matches!(result, Err(TonProofError::UnknownValidator(_))));
+
+        let duplicated = vec![signatures[0].clone(), signatures[0].clone()];
+        let result = check_block_signatures(&validators, &duplicated, &id);
+        assert!(matches!(result, Err(TonProofError::InvalidProof(_))));
+        Ok(())
+    }
+
+    #[test]
+    fn test_virtualize() -> anyhow::Result<()> {
+        let pruned_child = CellBuilder::new().store_u32(32, 0xdeadbeef)?.build()?;
+        let child_hash = pruned_child.cell_hash();
+        let root = CellBuilder::new()
+            .store_u8(8, 1)?
+            .store_child(pruned_child)?
+            .build()?;
+        let root_hash = root.cell_hash();
+
+        let mut pruned = CellBuilder::new();
+        pruned.set_cell_is_exotic(true);
+        pruned
+            .store_u8(8, 1)?
+            .store_u8(8, 1)?
+            .store_tonhash(&child_hash)?
+            .store_u16(16, 0)?;
+        let virtual_root = CellBuilder::new()
+            .store_u8(8, 1)?
+            .store_child(pruned.build()?)?
+            .build()?;
+
+        let mut proof = CellBuilder::new();
+        proof.set_cell_is_exotic(true);
+        proof
+            .store_u8(8, MERKLE_PROOF_TYPE)?
+            .store_tonhash(&root_hash)?
+            .store_u16(16, 1)?
+            .store_reference(&Arc::new(virtual_root))?;
+        let proof = proof.build()?;
+
+        let proved = virtualize(&proof, &root_hash, "root")?;
+        assert_eq!(proved.get_hash(0), root_hash);
+
+        let result = virtualize(&proof, &child_hash, "root");
+        assert!(matches!(
+            result,
+            Err(TonProofError::HashMismatch { what: "root", .. })
+        ));
+        let result = virtualize(&root, &root_hash, "root");
+        assert!(matches!(result, Err(TonProofError::InvalidProof(_))));
+        Ok(())
+    }
+
+    #[test]
+    fn test_check_transactions() -> anyhow::Result<()> {
+        let account = TonHash::from([5u8; 32]);
+        let cells: Vec<_> = (0..3u32)
+            .map(|i| CellBuilder::new().store_u32(32, i)?.build())
+            .collect::<Result<_, _>>()?;
+        let txs: Vec<_> = cells
+            .iter()
+            .enumerate()
+            .map(|(i, _)| TransactionInfo {
+                account: account.clone(),
+                lt: 30 - i as u64 * 10,
+                prev_trans_hash: cells.get(i + 1).map(|c| c.cell_hash()).unwrap_or(ZERO_HASH),
+                prev_trans_lt: 20 - i as u64 * 10,
+                now: 0,
+                total_fees: 0,
+                storage_fee: 0,
+                in_msg: None,
+                out_msgs: vec![],
+            })
+            .collect();
+        let from = InternalTransactionId {
+            lt: 30,
+            hash: cells[0].cell_hash().to_vec(),
+        };
+        check_transactions(&account, &from, txs.iter().zip(cells.iter()))?;
+
+        let result = check_transactions(&account, &from, txs.iter().zip(cells.iter().rev()));
+        assert!(matches!(
+            result,
+            Err(TonProofError::TransactionMismatch { lt: 30, .. })
+        ));
+
+        let other = TonHash::from([6u8; 32]);
+        let result = check_transactions(&other, &from, txs.iter().zip(cells.iter()));
+        assert!(matches!(result, Err(TonProofError::HashMismatch { .. })));
+        Ok(())
+    }
+
+    #[test]
+    fn test_shard_contains() {
+        let account = TonHash::from([0x80u8; 32]);
+        assert!(shard_contains(SHARD_FULL, &account));
+        assert!(shard_contains(0xc000_0000_0000_0000, &account));
+        assert!(!shard_contains(0x4000_0000_0000_0000, &account));
+        assert!(shard_contains(0x8100_0000_0000_0000, &account));
+        assert!(!shard_contains(0x8300_0000_0000_0000, &account));
+    }
+
+    #[test]
+    fn test_validator_set_prng() {
+        let mut prng = ValidatorSetPrng::new(MASTERCHAIN_ID, SHARD_FULL, 42);
+        let mut other = ValidatorSetPrng::new(MASTERCHAIN_ID, SHARD_FULL, 42);
+        let values: Vec<_> = (0..20).map(|_| prng.next_u64()).collect();
+        let other_values: Vec<_> = (0..20).map(|_| other.next_u64()).collect();
+        assert_eq!(values, other_values);
+        // seed is incremented after every 8 values
+        assert_ne!(values[..8], values[8..16]);
+
+        let mut prng = ValidatorSetPrng::new(MASTERCHAIN_ID, SHARD_FULL, 43);
+        assert_ne!(prng.next_u64(), values[0]);
+        assert!((0..100).all(|i| prng.next_ranged(i + 1) <= i));
+    }
+}
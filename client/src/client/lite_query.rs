@@ -0,0 +1,261 @@
+use ton_liteapi::tl::request::{
+    GetAccountState, GetAllShardsInfo, GetBlock, GetBlockHeader, GetBlockOutMsgQueueSize,
+    GetBlockProof, GetConfigAll, GetConfigParams, GetDispatchQueueInfo, GetDispatchQueueMessages,
+    GetLibraries, GetLibrariesWithProof, GetMasterchainInfoExt, GetOneTransaction,
+    GetOutMsgQueueSizes, GetShardBlockProof, GetShardInfo, GetState, GetTransactions,
+    GetValidatorStats, ListBlockTransactions, LookupBlock, LookupBlockWithProof, Request,
+    RunSmcMethod, SendMessage,
+};
+use ton_liteapi::tl::response::{
+    AccountState, AllShardsInfo, BlockData, BlockHeader, BlockOutMsgQueueSize, BlockState,
+    BlockTransactions, ConfigInfo, DispatchQueueInfo, DispatchQueueMessages, LibraryResult,
+    LibraryResultWithProof, LookupBlockResult, MasterchainInfoExt, OutMsgQueueSizes,
+    PartialBlockProof, Response, RunMethodResult, SendMsgStatus, ShardBlockProof, ShardInfo,
+    TransactionInfo, TransactionList, ValidatorStats,
+};
+
+use crate::client::{unexpected_response, TonClientError};
+
+/// Typed lite server request, see `TonConnection::lite_request`
+pub trait LiteRequest: Send {
+    type Response: Send;
+
+    /// Name of the lite server method, used in errors
+    const METHOD: &'static str;
+
+    fn into_request(self) -> Request;
+
+    /// Extracts typed response, failing if it doesn't match the request
+    fn from_response(response: Response) -> Result<Self::Response, TonClientError>;
+}
+
+/// `liteServer.getAccountStatePrunned`, account state with pruned cells of the state tree
+#[derive(Debug, Clone, PartialEq)]
+pub struct GetAccountStatePrunned(pub GetAccountState);
+
+macro_rules! lite_request {
+    ($request:ident, $method:literal, $response:ident) => {
+        impl LiteRequest for $request {
+            type Response = $response;
+
+            const METHOD: &'static str = $method;
+
+            fn into_request(self) -> Request {
+                Request::$request(self)
+            }
+
+            fn from_response(response: Response) -> Result<$response, TonClientError> {
+                match response {
+                    Response::$response(response) => Ok(response),
+                    r => Err(unexpected_response($method, r)),
+                }
+            }
+        }
+    };
+}
+
+lite_request!(
+    GetMasterchainInfoExt,
+    "liteServer.getMasterchainInfoExt",
+    MasterchainInfoExt
+);
+lite_request!(GetBlock, "liteServer.getBlock", BlockData);
+lite_request!(GetState, "liteServer.getState", BlockState);
+lite_request!(GetBlockHeader, "liteServer.getBlockHeader", BlockHeader);
+lite_request!(SendMessage, "liteServer.sendMessage", SendMsgStatus);
+lite_request!(GetAccountState, "liteServer.getAccountState", AccountState);
+lite_request!(RunSmcMethod, "liteServer.runSmcMethod", RunMethodResult);
+lite_request!(GetShardInfo, "liteServer.getShardInfo", ShardInfo);
+lite_request!(
+    GetAllShardsInfo,
+    "liteServer.getAllShardsInfo",
+    AllShardsInfo
+);
+lite_request!(
+    GetOneTransaction,
+    "liteServer.getOneTransaction",
+    TransactionInfo
+);
+lite_request!(
+    GetTransactions,
+    "liteServer.getTransactions",
+    TransactionList
+);
+lite_request!(LookupBlock, "liteServer.lookupBlock", BlockHeader);
+lite_request!(
+    LookupBlockWithProof,
+    "liteServer.lookupBlockWithProof",
+    LookupBlockResult
+);
+lite_request!(
+    ListBlockTransactions,
+    "liteServer.listBlockTransactions",
+    BlockTransactions
+);
+lite_request!(GetBlockProof, "liteServer.getBlockProof", PartialBlockProof);
+lite_request!(GetConfigAll, "liteServer.getConfigAll", ConfigInfo);
+lite_request!(GetConfigParams, "liteServer.getConfigParams", ConfigInfo);
+lite_request!(
+    GetValidatorStats,
+    "liteServer.getValidatorStats",
+    ValidatorStats
+);
+lite_request!(GetLibraries, "liteServer.getLibraries", LibraryResult);
+lite_request!(
+    GetLibrariesWithProof,
+    "liteServer.getLibrariesWithProof",
+    LibraryResultWithProof
+);
+lite_request!(
+    GetShardBlockProof,
+    "liteServer.getShardBlockProof",
+    ShardBlockProof
+);
+lite_request!(
+    GetOutMsgQueueSizes,
+    "liteServer.getOutMsgQueueSizes",
+    OutMsgQueueSizes
+);
+lite_request!(
+    GetBlockOutMsgQueueSize,
+    "liteServer.getBlockOutMsgQueueSize",
+    BlockOutMsgQueueSize
+);
+lite_request!(
+    GetDispatchQueueInfo,
+    "liteServer.getDispatchQueueInfo",
+    DispatchQueueInfo
+);
+lite_request!(
+    GetDispatchQueueMessages,
+    "liteServer.getDispatchQueueMessages",
+    DispatchQueueMessages
+);
+
+impl LiteRequest for GetAccountStatePrunned {
+    type Response = AccountState;
+
+    const METHOD: &'static str = "liteServer.getAccountStatePrunned";
+
+    fn into_request(self) -> Request {
+        Request::GetAccountStatePrunned(self.0)
+    }
+
+    fn from_response(response: Response) -> Result<AccountState, TonClientError> {
+        match response {
+            Response::AccountState(state) => Ok(state),
+            r => Err(unexpected_response(Self::METHOD, r)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use async_trait::async_trait;
+    use ton_liteapi::tl::common::{AccountId, BlockIdExt, Int256};
+    use ton_liteapi::tl::response::{Error, Version};
+
+    use super::*;
+    use crate::client::{NoopConnectionCallback, TonConnection, TonFunctionHandler};
+    use crate::tl::{TonFunction, TonResult};
+
+    struct LiteHandler;
+
+    fn block() -> BlockIdExt {
+        BlockIdExt {
+            workchain: -1,
+            shard: 0x8000_0000_0000_0000,
+            seqno: 100,
+            root_hash: Int256([1; 32]),
+            file_hash: Int256([2; 32]),
+        }
+    }
+
+    #[async_trait]
+    impl TonFunctionHandler for LiteHandler {
+        async fn handle(&self, function: &TonFunction) -> Result<TonResult, TonClientError> {
+            Err(TonClientError::InternalError(format!(
+                "Unexpected {:?}",
+                function
+            )))
+        }
+
+        async fn handle_lite_query(&self, request: &Request) -> Result<Response, TonClientError> {
+            let response = match request {
+                Request::GetVersion => Response::Version(Version {
+                    mode: 0,
+                    version: 0x101,
+                    capabilities: 7,
+                    now: 1700000000,
+                }),
+                Request::GetDispatchQueueInfo(request) => {
+                    Response::DispatchQueueInfo(DispatchQueueInfo {
+                        mode: (),
+                        id: request.id.clone(),
+                        account_dispatch_queues: vec![],
+                        complete: true,
+                        proof: None,
+                    })
+                }
+                Request::GetAccountStatePrunned(_) => Response::Error(Error {
+                    code: 651,
+                    message: "block is not applied".into(),
+                }),
+                _ => Response::Version(Version {
+                    mode: 0,
+                    version: 0,
+                    capabilities: 0,
+                    now: 0,
+                }),
+            };
+            Ok(response)
+        }
+    }
+
+    fn connection() -> TonConnection {
+        TonConnection::from_handler(Arc::new(LiteHandler), Arc::new(NoopConnectionCallback {}))
+    }
+
+    #[tokio::test]
+    async fn test_lite_request() -> anyhow::Result<()> {
+        let conn = connection();
+        let info = conn
+            .lite_request(GetDispatchQueueInfo {
+                mode: (),
+                id: block(),
+                want_proof: None,
+                after_addr: None,
+                max_accounts: 10,
+            })
+            .await?;
+        assert_eq!(info.id, block());
+        assert!(info.complete);
+
+        let response = conn.lite_query(Request::GetVersion).await?;
+        assert!(matches!(response, Response::Version(v) if v.version == 0x101));
+
+        let state = GetAccountState {
+            id: block(),
+            account: AccountId {
+                workchain: 0,
+                id: Int256([0; 32]),
+            },
+        };
+        let error = conn
+            .lite_request(GetAccountStatePrunned(state.clone()))
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            TonClientError::TonlibError { code: 651, method, .. }
+                if method == "liteServer.getAccountStatePrunned"
+        ));
+        assert!(matches!(
+            conn.lite_request(state).await,
+            Err(TonClientError::InternalError(_))
+        ));
+        Ok(())
+    }
+}
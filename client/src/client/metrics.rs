@@ -0,0 +1,331 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::client::{TonClientError, TonConnectionCallback};
+use crate::tl::{SyncState, TonFunction, TonNotification, TonResult};
+
+/// Upper bounds of invoke duration histogram buckets, in seconds
+pub const DEFAULT_DURATION_BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// An implementation of TonConnectionCallback that collects invoke metrics
+///
+/// Collected metrics are rendered in Prometheus text format by `render`:
+///
+/// - `ton_invoke_duration_seconds` histogram by method and connection tag
+/// - `ton_invoke_errors_total` counter by method, connection tag and error code
+/// - `ton_invoke_in_flight` gauge by connection tag
+/// - `ton_sync_current_seqno`, `ton_sync_to_seqno` and `ton_synced` gauges by connection tag
+/// - `ton_connection_loop_active` gauge by connection tag
+pub struct MetricsConnectionCallback {
+    buckets: Vec<f64>,
+    state: Mutex<MetricsState>,
+}
+
+#[derive(Default)]
+struct MetricsState {
+    durations: BTreeMap<(String, String), Histogram>,
+    errors: BTreeMap<(String, String, String), u64>,
+    in_flight: BTreeMap<String, i64>,
+    sync_states: BTreeMap<String, SyncState>,
+    loops: BTreeMap<String, bool>,
+}
+
+#[derive(Default)]
+struct Histogram {
+    bucket_counts: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+impl MetricsConnectionCallback {
+    pub fn new() -> MetricsConnectionCallback {
+        Self::with_buckets(&DEFAULT_DURATION_BUCKETS)
+    }
+
+    pub fn with_buckets(buckets: &[f64]) -> MetricsConnectionCallback {
+        MetricsConnectionCallback {
+            buckets: buckets.to_vec(),
+            state: Mutex::new(MetricsState::default()),
+        }
+    }
+
+    /// Renders collected metrics in Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let state = self.state.lock().unwrap();
+        let mut out = String::new();
+
+        header(
+            &mut out,
+            "ton_invoke_duration_seconds",
+            "Duration of TonFunction invocations",
+            "histogram",
+        );
+        for ((method, tag), h) in state.durations.iter() {
+            let labels = format!("method=\"{}\",tag=\"{}\"", method, tag);
+            let mut cumulative = 0;
+            for (bound, count) in self.buckets.iter().zip(h.bucket_counts.iter()) {
+                cumulative += count;
+                let _ = writeln!(
+                    out,
+                    "ton_invoke_duration_seconds_bucket{{{},le=\"{}\"}} {}",
+                    labels, bound, cumulative
+                );
+            }
+            let _ = writeln!(
+                out,
+                "ton_invoke_duration_seconds_bucket{{{},le=\"+Inf\"}} {}",
+                labels, h.count
+            );
+            let _ = writeln!(
+                out,
+                "ton_invoke_duration_seconds_sum{{{}}} {}",
+                labels, h.sum
+            );
+            let _ = writeln!(
+                out,
+                "ton_invoke_duration_seconds_count{{{}}} {}",
+                labels, h.count
+            );
+        }
+
+        header(
+            &mut out,
+            "ton_invoke_errors_total",
+            "Number of failed TonFunction invocations",
+            "counter",
+        );
+        for ((method, tag, code), count) in state.errors.iter() {
+            let _ = writeln!(
+                out,
+                "ton_invoke_errors_total{{method=\"{}\",tag=\"{}\",code=\"{}\"}} {}",
+                method, tag, code, count
+            );
+        }
+
+        header(
+            &mut out,
+            "ton_invoke_in_flight",
+            "Number of invocations waiting for result",
+            "gauge",
+        );
+        for (tag, count) in state.in_flight.iter() {
+            let _ = writeln!(out, "ton_invoke_in_flight{{tag=\"{}\"}} {}", tag, count);
+        }
+
+        header(
+            &mut out,
+            "ton_sync_current_seqno",
+            "Masterchain seqno tonlib is synced to",
+            "gauge",
+        );
+        for (tag, sync_state) in state.sync_states.iter() {
+            if let SyncState::InProgress { current_seqno, .. } = sync_state {
+                let _ = writeln!(
+                    out,
+                    "ton_sync_current_seqno{{tag=\"{}\"}} {}",
+                    tag, current_seqno
+                );
+            }
+        }
+
+        header(
+            &mut out,
+            "ton_sync_to_seqno",
+            "Masterchain seqno tonlib is syncing to",
+            "gauge",
+        );
+        for (tag, sync_state) in state.sync_states.iter() {
+            if let SyncState::InProgress { to_seqno, .. } = sync_state {
+                let _ = writeln!(out, "ton_sync_to_seqno{{tag=\"{}\"}} {}", tag, to_seqno);
+            }
+        }
+
+        header(
+            &mut out,
+            "ton_synced",
+            "Whether tonlib sync is done",
+            "gauge",
+        );
+        for (tag, sync_state) in state.sync_states.iter() {
+            let synced = matches!(sync_state, SyncState::Done) as u8;
+            let _ = writeln!(out, "ton_synced{{tag=\"{}\"}} {}", tag, synced);
+        }
+
+        header(
+            &mut out,
+            "ton_connection_loop_active",
+            "Whether connection loop is running",
+            "gauge",
+        );
+        for (tag, active) in state.loops.iter() {
+            let _ = writeln!(
+                out,
+                "ton_connection_loop_active{{tag=\"{}\"}} {}",
+                tag, *active as u8
+            );
+        }
+        out
+    }
+}
+
+impl Default for MetricsConnectionCallback {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TonConnectionCallback for MetricsConnectionCallback {
+    fn on_invoke(&self, tag: &str, _request_id: u32, _function: &TonFunction) {
+        let mut state = self.state.lock().unwrap();
+        *state.in_flight.entry(tag.to_string()).or_default() += 1;
+    }
+
+    fn on_invoke_result(
+        &self,
+        tag: &str,
+        _request_id: u32,
+        method: &str,
+        duration: &Duration,
+        result: &Result<TonResult, TonClientError>,
+    ) {
+        let mut state = self.state.lock().unwrap();
+        *state.in_flight.entry(tag.to_string()).or_default() -= 1;
+
+        let secs = duration.as_secs_f64();
+        let h = state
+            .durations
+            .entry((method.to_string(), tag.to_string()))
+            .or_default();
+        if h.bucket_counts.is_empty() {
+            h.bucket_counts = vec![0; self.buckets.len()];
+        }
+        if let Some(i) = self.buckets.iter().position(|bound| secs <= *bound) {
+            h.bucket_counts[i] += 1;
+        }
+        h.sum += secs;
+        h.count += 1;
+
+        if let Err(e) = result {
+            let key = (method.to_string(), tag.to_string(), error_code(e));
+            *state.errors.entry(key).or_default() += 1;
+        }
+    }
+
+    fn on_notification(&self, tag: &str, notification: &TonNotification) {
+        let TonNotification::UpdateSyncState(update) = notification;
+        let mut state = self.state.lock().unwrap();
+        state
+            .sync_states
+            .insert(tag.to_string(), update.sync_state.clone());
+    }
+
+    fn on_connection_loop_start(&self, tag: &str) {
+        let mut state = self.state.lock().unwrap();
+        state.loops.insert(tag.to_string(), true);
+    }
+
+    fn on_connection_loop_exit(&self, tag: &str) {
+        let mut state = self.state.lock().unwrap();
+        state.loops.insert(tag.to_string(), false);
+    }
+}
+
+fn header(out: &mut String, name: &str, help: &str, metric_type: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, metric_type);
+}
+
+fn error_code(error: &TonClientError) -> String {
+    match error {
+        TonClientError::TonlibError { code, .. } => code.to_string(),
+        TonClientError::Timeout { .. } => "timeout".to_string(),
+        TonClientError::Cancelled { .. } => "cancelled".to_string(),
+        _ => "client".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tl::UpdateSyncState;
+
+    #[test]
+    fn test_metrics_render() {
+        let metrics = MetricsConnectionCallback::new();
+        let func = TonFunction::BlocksGetMasterchainInfo {};
+        let method = "BlocksGetMasterchainInfo";
+        metrics.on_connection_loop_start("conn-0");
+        metrics.on_invoke("conn-0", 0, &func);
+        metrics.on_invoke("conn-0", 1, &func);
+        metrics.on_invoke_result(
+            "conn-0",
+            0,
+            method,
+            &Duration::from_millis(30),
+            &Ok(TonResult::Ok {}),
+        );
+        let error = TonClientError::TonlibError {
+            method,
+            code: 500,
+            message: "LITE_SERVER_NOTREADY".to_string(),
+        };
+        metrics.on_invoke_result("conn-0", 1, method, &Duration::from_secs(20), &Err(error));
+        let sync_state = SyncState::InProgress {
+            from_seqno: 1,
+            to_seqno: 100,
+            current_seqno: 50,
+        };
+        metrics.on_notification(
+            "conn-0",
+            &TonNotification::UpdateSyncState(UpdateSyncState { sync_state }),
+        );
+
+        let text = metrics.render();
+        let labels = "method=\"BlocksGetMasterchainInfo\",tag=\"conn-0\"";
+        for line in [
+            format!(
+                "ton_invoke_duration_seconds_bucket{{{},le=\"0.025\"}} 0",
+                labels
+            ),
+            format!(
+                "ton_invoke_duration_seconds_bucket{{{},le=\"0.05\"}} 1",
+                labels
+            ),
+            format!(
+                "ton_invoke_duration_seconds_bucket{{{},le=\"10\"}} 1",
+                labels
+            ),
+            format!(
+                "ton_invoke_duration_seconds_bucket{{{},le=\"+Inf\"}} 2",
+                labels
+            ),
+            format!("ton_invoke_duration_seconds_count{{{}}} 2", labels),
+            format!("ton_invoke_errors_total{{{},code=\"500\"}} 1", labels),
+            "ton_invoke_in_flight{tag=\"conn-0\"} 0".to_string(),
+            "ton_sync_current_seqno{tag=\"conn-0\"} 50".to_string(),
+            "ton_synced{tag=\"conn-0\"} 0".to_string(),
+            "ton_connection_loop_active{tag=\"conn-0\"} 1".to_string(),
+        ] {
+            assert!(
+                text.lines().any(|l| l == line),
+                "{} not found in\n{}",
+                line,
+                text
+            );
+        }
+
+        // every sample belongs to the family of the preceding TYPE header
+        let mut family = "";
+        for line in text.lines() {
+            if let Some(header) = line.strip_prefix("# TYPE ") {
+                family = header.split(' ').next().unwrap();
+            } else if !line.starts_with('#') {
+                assert!(line.starts_with(family), "{} is not in {}", line, family);
+            }
+        }
+    }
+}
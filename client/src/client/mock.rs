@@ -0,0 +1,104 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+
+use crate::client::{
+    NoopConnectionCallback, TonClient, TonClientError, TonClientInterface, TonConnection,
+    TonFunctionHandler,
+};
+use crate::tl::{TonFunction, TonResult};
+
+/// In-memory client answering `TonFunction`s with preconfigured `TonResult`s.
+///
+/// Responses added for the same function are returned in order, the last one is repeated.
+/// `TonResult::Error` is returned as `TonClientError::TonlibError`.
+#[derive(Clone)]
+pub struct MockTonClient {
+    responses: Arc<MockResponses>,
+    connection: TonConnection,
+}
+
+impl MockTonClient {
+    pub fn new() -> MockTonClient {
+        Self::from_responses(Arc::new(MockResponses::default()))
+    }
+
+    pub(crate) fn from_responses(responses: Arc<MockResponses>) -> MockTonClient {
+        let connection =
+            TonConnection::from_handler(responses.clone(), Arc::new(NoopConnectionCallback {}));
+        MockTonClient {
+            responses,
+            connection,
+        }
+    }
+
+    pub fn add_response(&self, function: TonFunction, result: TonResult) {
+        self.responses.add(function, result);
+    }
+
+    /// Returns a `TonClient` backed by this mock, e.g. for `TonContractFactory`
+    pub fn ton_client(&self) -> TonClient {
+        TonClient::from_connection(self.connection.clone())
+    }
+}
+
+impl Default for MockTonClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl TonClientInterface for MockTonClient {
+    async fn get_connection(&self) -> Result<TonConnection, TonClientError> {
+        Ok(self.connection.clone())
+    }
+
+    async fn invoke_on_connection(
+        &self,
+        function: &TonFunction,
+    ) -> Result<(TonConnection, TonResult), TonClientError> {
+        self.connection.invoke_on_connection(function).await
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct MockResponses {
+    responses: Mutex<HashMap<TonFunction, VecDeque<TonResult>>>,
+}
+
+impl MockResponses {
+    pub(crate) fn add(&self, function: TonFunction, result: TonResult) {
+        let mut responses = self.responses.lock().unwrap();
+        responses.entry(function).or_default().push_back(result);
+    }
+
+    fn next(&self, function: &TonFunction) -> Option<TonResult> {
+        let mut responses = self.responses.lock().unwrap();
+        let queue = responses.get_mut(function)?;
+        if queue.len() > 1 {
+            queue.pop_front()
+        } else {
+            queue.front().cloned()
+        }
+    }
+}
+
+#[async_trait]
+impl TonFunctionHandler for MockResponses {
+    async fn handle(&self, function: &TonFunction) -> Result<TonResult, TonClientError> {
+        match self.next(function) {
+            Some(TonResult::Error { code, message }) => Err(TonClientError::TonlibError {
+                method: function.into(),
+                code,
+                message,
+            }),
+            Some(result) => Ok(result),
+            None => Err(TonClientError::InternalError(format!(
+                "No response for {:?}",
+                function
+            ))),
+        }
+    }
+}
@@ -0,0 +1,242 @@
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use rand::Rng;
+
+use crate::client::{
+    ConnectionCheck, ConnectionHealth, ConnectionScore, HealthCheckParams, RequestPriority,
+    TonClientError, TonClientInterface, TonConnection, TonConnectionCallback, TonConnectionParams,
+};
+use crate::tl::{TonFunction, TonResult};
+
+/// Set of connections of `TonClient` routed by health score.
+pub(crate) struct ConnectionPool {
+    entries: Vec<PoolEntry>,
+}
+
+pub(crate) struct PoolEntry {
+    connection: RwLock<TonConnection>,
+    health: ConnectionHealth,
+    reconnect: Option<ReconnectParams>,
+}
+
+struct ReconnectParams {
+    params: RwLock<TonConnectionParams>,
+    connection_check: ConnectionCheck,
+    callback: Arc<dyn TonConnectionCallback>,
+}
+
+impl ConnectionPool {
+    /// Opens `size` connections, keystore directories are numbered starting from `first_index`
+    pub(crate) async fn connect(
+        first_index: usize,
+        size: usize,
+        params: &TonConnectionParams,
+        connection_check: ConnectionCheck,
+        callback: Arc<dyn TonConnectionCallback>,
+    ) -> Result<ConnectionPool, TonClientError> {
+        let mut entries = Vec::with_capacity(size);
+        for i in first_index..first_index + size {
+            let mut conn_params = params.clone();
+            if let Some(dir) = &params.keystore_dir {
+                let keystore_prefix = Path::new(dir.as_str());
+                let keystore_dir = keystore_prefix.join(format!("{}", i));
+                fs::create_dir_all(&keystore_dir)?;
+                let path_str = keystore_dir.into_os_string().into_string().map_err(|_| {
+                    TonClientError::InternalError("Error constructing keystore path".to_string())
+                })?;
+                conn_params.keystore_dir = Some(path_str)
+            };
+            let conn = TonConnection::new(connection_check.clone(), &conn_params, callback.clone())
+                .await?;
+            let reconnect = ReconnectParams {
+                params: RwLock::new(conn_params),
+                connection_check: connection_check.clone(),
+                callback: callback.clone(),
+            };
+
+            entries.push(PoolEntry::new(conn, Some(reconnect)));
+        }
+        Ok(ConnectionPool { entries })
+    }
+
+    pub(crate) fn from_connections(connections: Vec<TonConnection>) -> ConnectionPool {
+        let entries = connections
+            .into_iter()
+            .map(|c| PoolEntry::new(c, None))
+            .collect();
+        ConnectionPool { entries }
+    }
+
+    /// Picks the better scored of two random connections, avoiding `exclude` if possible
+    pub(crate) fn select(
+        &self,
+        exclude: Option<usize>,
+    ) -> Result<(usize, &PoolEntry), TonClientError> {
+        let candidates: Vec<usize> = (0..self.entries.len())
+            .filter(|i| Some(*i) != exclude)
+            .collect();
+        let candidates = match (candidates.is_empty(), exclude) {
+            (true, Some(exclude)) => vec![exclude],
+            (true, None) => {
+                return Err(TonClientError::InternalError(
+                    "Connection pool is empty".to_string(),
+                ));
+            }
+            (false, _) => candidates,
+        };
+        let (first, second) = {
+            let mut rng = rand::rng();
+            (
+                candidates[rng.random_range(0..candidates.len())],
+                candidates[rng.random_range(0..candidates.len())],
+            )
+        };
+        if first == second {
+            return Ok((first, &self.entries[first]));
+        }
+        let max_seqno = self.max_seqno();
+        let first_score = self.entries[first].health.score(first, max_seqno);
+        let second_score = self.entries[second].health.score(second, max_seqno);
+        if first_score.score <= second_score.score {
+            Ok((first, &self.entries[first]))
+        } else {
+            Ok((second, &self.entries[second]))
+        }
+    }
+
+    pub(crate) fn max_seqno(&self) -> Option<i32> {
+        self.entries
+            .iter()
+            .filter_map(|c| c.health.last_seqno())
+            .max()
+    }
+
+    pub(crate) fn scores(&self) -> Vec<ConnectionScore> {
+        let max_seqno = self.max_seqno();
+        self.entries
+            .iter()
+            .enumerate()
+            .map(|(i, c)| c.health.score(i, max_seqno))
+            .collect()
+    }
+
+    /// Probes all connections and reconnects unhealthy ones
+    pub(crate) async fn check_health(&self, params: &HealthCheckParams) {
+        let timeout = Duration::from_millis(params.probe_timeout_ms);
+        let probes = self.entries.iter().map(|entry| async move {
+            let conn = entry.connection();
+            let send_time = Instant::now();
+            let func = TonFunction::BlocksGetMasterchainInfo {};
+            let res = conn.invoke_with_timeout(&func, timeout).await;
+            entry.health.record(&send_time.elapsed(), &res);
+        });
+        futures::future::join_all(probes).await;
+
+        for score in self.scores() {
+            if score.is_healthy(params) {
+                continue;
+            }
+            let entry = &self.entries[score.index];
+            if entry.reconnect.is_none() {
+                continue;
+            }
+            log::warn!("Reconnecting unhealthy connection: {:?}", score);
+            if let Err(e) = entry.reconnect().await {
+                log::warn!("Failed to reconnect: {:?}", e);
+            }
+        }
+    }
+
+    /// Returns connection params of the pool, `None` if connections can't be reconnected
+    pub(crate) fn params(&self) -> Option<TonConnectionParams> {
+        self.entries
+            .iter()
+            .find_map(|e| e.reconnect.as_ref())
+            .map(|r| r.params.read().unwrap().clone())
+    }
+
+    /// Sets `config` for all reconnectable connections, they use it on the next reconnect
+    pub(crate) fn set_config(&self, config: &str) {
+        for reconnect in self.entries.iter().filter_map(|e| e.reconnect.as_ref()) {
+            reconnect.params.write().unwrap().config = config.to_string();
+        }
+    }
+
+    /// Reconnects connections one by one, waiting `interval` between them.
+    ///
+    /// A failed reconnect doesn't stop the reload, failures are returned with connection index.
+    pub(crate) async fn reconnect_all(
+        &self,
+        interval: Duration,
+    ) -> (usize, Vec<(usize, TonClientError)>) {
+        let mut reconnected = 0;
+        let mut failures = vec![];
+        let mut entries = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(_, e)| e.reconnect.is_some())
+            .peekable();
+        while let Some((index, entry)) = entries.next() {
+            match entry.reconnect().await {
+                Ok(()) => reconnected += 1,
+                Err(e) => failures.push((index, e)),
+            }
+            if entries.peek().is_some() {
+                tokio::time::sleep(interval).await;
+            }
+        }
+        (reconnected, failures)
+    }
+}
+
+impl PoolEntry {
+    fn new(connection: TonConnection, reconnect: Option<ReconnectParams>) -> PoolEntry {
+        PoolEntry {
+            connection: RwLock::new(connection),
+            health: ConnectionHealth::default(),
+            reconnect,
+        }
+    }
+
+    pub(crate) fn connection(&self) -> TonConnection {
+        self.connection.read().unwrap().clone()
+    }
+
+    /// Replaces the connection with a new one, in-flight requests finish on the old one
+    async fn reconnect(&self) -> Result<(), TonClientError> {
+        let Some(reconnect) = &self.reconnect else {
+            return Ok(());
+        };
+        let params = reconnect.params.read().unwrap().clone();
+        let conn = TonConnection::new(
+            reconnect.connection_check.clone(),
+            &params,
+            reconnect.callback.clone(),
+        )
+        .await?;
+        *self.connection.write().unwrap() = conn;
+        self.health.reset();
+        Ok(())
+    }
+
+    /// Invokes `function` on the current connection, recording its health
+    pub(crate) async fn invoke(
+        &self,
+        function: &TonFunction,
+        timeout: Option<Duration>,
+        priority: RequestPriority,
+    ) -> Result<(TonConnection, TonResult), TonClientError> {
+        let conn = self.connection().with_priority(priority);
+        let send_time = Instant::now();
+        let res = match timeout {
+            Some(timeout) => conn.invoke_with_timeout(function, timeout).await,
+            None => conn.invoke(function).await,
+        };
+        self.health.record(&send_time.elapsed(), &res);
+        res.map(|r| (conn, r))
+    }
+}
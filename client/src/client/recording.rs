@@ -0,0 +1,166 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "liteapi")]
+use ton_liteapi::tl::request::Request;
+#[cfg(feature = "liteapi")]
+use ton_liteapi::tl::response::Response;
+
+use crate::client::{
+    MockResponses, MockTonClient, NoopConnectionCallback, TonClient, TonClientError,
+    TonClientInterface, TonConnection, TonFunctionHandler,
+};
+use crate::tl::{TonFunction, TonResult};
+
+/// Single recorded invocation, stored as one JSON line of a fixture file.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct TonFixtureEntry {
+    pub function: TonFunction,
+    pub result: TonResult,
+}
+
+/// Client writing every invocation made through it to a fixture file.
+///
+/// All invocations are served by a single connection of the wrapped client,
+/// so smc ids remain valid between calls. Tonlib errors are recorded as `TonResult::Error`.
+#[derive(Clone)]
+pub struct RecordingTonClient {
+    connection: TonConnection,
+}
+
+impl RecordingTonClient {
+    /// Creates (or truncates) the fixture file at `path` and records invocations on `client`
+    pub async fn new<C: TonClientInterface, P: AsRef<Path>>(
+        client: &C,
+        path: P,
+    ) -> Result<RecordingTonClient, TonClientError> {
+        let recorder = Recorder {
+            target: client.get_connection().await?,
+            file: Mutex::new(File::create(path)?),
+        };
+        let connection =
+            TonConnection::from_handler(Arc::new(recorder), Arc::new(NoopConnectionCallback {}));
+        Ok(RecordingTonClient { connection })
+    }
+
+    /// Returns a `TonClient` recording through this client
+    pub fn ton_client(&self) -> TonClient {
+        TonClient::from_connection(self.connection.clone())
+    }
+}
+
+#[async_trait]
+impl TonClientInterface for RecordingTonClient {
+    async fn get_connection(&self) -> Result<TonConnection, TonClientError> {
+        Ok(self.connection.clone())
+    }
+
+    async fn invoke_on_connection(
+        &self,
+        function: &TonFunction,
+    ) -> Result<(TonConnection, TonResult), TonClientError> {
+        self.connection.invoke_on_connection(function).await
+    }
+}
+
+struct Recorder {
+    target: TonConnection,
+    file: Mutex<File>,
+}
+
+impl Recorder {
+    fn record(&self, entry: &TonFixtureEntry) -> Result<(), TonClientError> {
+        let line = serde_json::to_string(entry)
+            .map_err(|e| TonClientError::InternalError(format!("Fail to serialize: {}", e)))?;
+        let mut file = self.file.lock().unwrap();
+        writeln!(file, "{}", line)?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl TonFunctionHandler for Recorder {
+    async fn handle(&self, function: &TonFunction) -> Result<TonResult, TonClientError> {
+        let result = self.target.invoke(function).await;
+        let recorded = match &result {
+            Ok(r) => Some(r.clone()),
+            Err(TonClientError::TonlibError { code, message, .. }) => Some(TonResult::Error {
+                code: *code,
+                message: message.clone(),
+            }),
+            Err(_) => None,
+        };
+        if let Some(result) = recorded {
+            self.record(&TonFixtureEntry {
+                function: function.clone(),
+                result,
+            })?;
+        }
+        result
+    }
+
+    /// Lite server queries are passed through without recording
+    #[cfg(feature = "liteapi")]
+    async fn handle_lite_query(&self, request: &Request) -> Result<Response, TonClientError> {
+        self.target.lite_query(request.clone()).await
+    }
+}
+
+/// Client serving invocations from a fixture file written by `RecordingTonClient`.
+///
+/// Entries for the same function are returned in recorded order, the last one is repeated.
+#[derive(Clone)]
+pub struct ReplayTonClient {
+    mock: MockTonClient,
+}
+
+impl ReplayTonClient {
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<ReplayTonClient, TonClientError> {
+        let reader = BufReader::new(File::open(path)?);
+        let mut entries = Vec::new();
+        for line in reader.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let entry = serde_json::from_str(&line).map_err(|e| {
+                TonClientError::InvalidArgument(format!("Fail to parse fixture: {}", e))
+            })?;
+            entries.push(entry);
+        }
+        Ok(Self::from_entries(entries))
+    }
+
+    pub fn from_entries(entries: Vec<TonFixtureEntry>) -> ReplayTonClient {
+        let responses = MockResponses::default();
+        for entry in entries {
+            responses.add(entry.function, entry.result);
+        }
+        ReplayTonClient {
+            mock: MockTonClient::from_responses(Arc::new(responses)),
+        }
+    }
+
+    /// Returns a `TonClient` replaying the fixture
+    pub fn ton_client(&self) -> TonClient {
+        self.mock.ton_client()
+    }
+}
+
+#[async_trait]
+impl TonClientInterface for ReplayTonClient {
+    async fn get_connection(&self) -> Result<TonConnection, TonClientError> {
+        self.mock.get_connection().await
+    }
+
+    async fn invoke_on_connection(
+        &self,
+        function: &TonFunction,
+    ) -> Result<(TonConnection, TonResult), TonClientError> {
+        self.mock.invoke_on_connection(function).await
+    }
+}
@@ -0,0 +1,216 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::client::{RetryStrategy, TonClientError};
+use crate::tl::TonFunction;
+
+/// Decides whether and when `TonClient` retries a failed invocation.
+///
+/// Retries are sent to a different connection of the pool when possible.
+pub trait RetryPolicy: Send + Sync {
+    /// Returns the delay before the next attempt or `None` to give up.
+    ///
+    /// `attempt` is the number of failed attempts so far, starting from 1.
+    fn next_delay(
+        &self,
+        function: &TonFunction,
+        error: &TonClientError,
+        attempt: u32,
+    ) -> Option<Duration>;
+}
+
+/// Retries retryable errors with exponentially growing, jittered delays.
+///
+/// `RawSendMessage` and `RawSendMessageReturnHash` are never retried by default,
+/// since a retry may deliver the message twice.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BackoffRetryPolicy {
+    pub initial_delay_ms: u64,
+    pub max_delay_ms: u64,
+    pub multiplier: f64,
+    /// Fraction of the delay which is randomized, from 0.0 (no jitter) to 1.0
+    pub jitter: f64,
+    pub max_retries: u32,
+    /// Max retries by `TonFunction` name, overriding `max_retries`
+    pub function_max_retries: HashMap<String, u32>,
+}
+
+impl BackoffRetryPolicy {
+    pub fn with_function_max_retries(&mut self, method: &str, max_retries: u32) -> &mut Self {
+        self.function_max_retries
+            .insert(method.to_string(), max_retries);
+        self
+    }
+
+    fn delay(&self, attempt: u32) -> Duration {
+        let exp = self.multiplier.powi(attempt.saturating_sub(1) as i32);
+        let delay_ms = (self.initial_delay_ms as f64 * exp).min(self.max_delay_ms as f64);
+        let jitter = self.jitter.clamp(0.0, 1.0);
+        let factor = 1.0 - jitter * rand::rng().random::<f64>();
+        Duration::from_millis((delay_ms * factor) as u64)
+    }
+}
+
+impl Default for BackoffRetryPolicy {
+    fn default() -> Self {
+        let function_max_retries = ["RawSendMessage", "RawSendMessageReturnHash"]
+            .into_iter()
+            .map(|m| (m.to_string(), 0))
+            .collect();
+        BackoffRetryPolicy {
+            initial_delay_ms: 100,
+            max_delay_ms: 5000,
+            multiplier: 2.0,
+            jitter: 0.5,
+            max_retries: 5,
+            function_max_retries,
+        }
+    }
+}
+
+impl RetryPolicy for BackoffRetryPolicy {
+    fn next_delay(
+        &self,
+        function: &TonFunction,
+        error: &TonClientError,
+        attempt: u32,
+    ) -> Option<Duration> {
+        let method: &'static str = function.into();
+        let max_retries = self
+            .function_max_retries
+            .get(method)
+            .copied()
+            .unwrap_or(self.max_retries);
+        if attempt > max_retries || !error.is_retryable() {
+            return None;
+        }
+        Some(self.delay(attempt))
+    }
+}
+
+impl RetryPolicy for RetryStrategy {
+    fn next_delay(
+        &self,
+        _function: &TonFunction,
+        error: &TonClientError,
+        attempt: u32,
+    ) -> Option<Duration> {
+        let code_500 = matches!(error, TonClientError::TonlibError { code: 500, .. });
+        if attempt as usize > self.max_retries || !code_500 {
+            return None;
+        }
+        Some(Duration::from_millis(self.interval_ms))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::client::{
+        CallerBudgets, ConnectionPool, Inner, MockTonClient, TonClient, TonClientInterface,
+        TonConnection,
+    };
+    use crate::tl::{BlockIdExt, BlocksMasterchainInfo, TonResult};
+
+    fn tonlib_error(code: i32, message: &str) -> TonClientError {
+        TonClientError::TonlibError {
+            method: "BlocksGetMasterchainInfo",
+            code,
+            message: message.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_backoff_delays() {
+        let policy = BackoffRetryPolicy {
+            jitter: 0.0,
+            ..Default::default()
+        };
+        let func = TonFunction::BlocksGetMasterchainInfo {};
+        let error = tonlib_error(500, "LITE_SERVER_NOTREADY");
+        let delays: Vec<_> = (1..=7)
+            .map(|attempt| policy.next_delay(&func, &error, attempt))
+            .collect();
+        let expected = [100, 200, 400, 800, 1600]
+            .map(|ms| Some(Duration::from_millis(ms)))
+            .into_iter()
+            .chain([None, None])
+            .collect::<Vec<_>>();
+        assert_eq!(delays, expected);
+
+        let policy = BackoffRetryPolicy {
+            initial_delay_ms: 10000,
+            jitter: 1.0,
+            ..Default::default()
+        };
+        let delay = policy.next_delay(&func, &error, 1).unwrap();
+        assert!(delay <= Duration::from_millis(5000));
+    }
+
+    #[test]
+    fn test_send_message_is_not_retried() {
+        let mut policy = BackoffRetryPolicy::default();
+        let func = TonFunction::RawSendMessage { body: vec![] };
+        let error = tonlib_error(500, "adnl query timeout");
+        assert_eq!(policy.next_delay(&func, &error, 1), None);
+
+        policy.with_function_max_retries("RawSendMessage", 1);
+        assert!(policy.next_delay(&func, &error, 1).is_some());
+    }
+
+    #[tokio::test]
+    async fn test_retry_on_another_connection() {
+        let func = TonFunction::BlocksGetMasterchainInfo {};
+        let failing = MockTonClient::new();
+        failing.add_response(
+            func.clone(),
+            TonResult::Error {
+                code: 500,
+                message: "LITE_SERVER_NOTREADY".to_string(),
+            },
+        );
+        let block = BlockIdExt {
+            workchain: -1,
+            shard: i64::MIN,
+            seqno: 1,
+            root_hash: vec![0; 32],
+            file_hash: vec![0; 32],
+        };
+        let healthy = MockTonClient::new();
+        healthy.add_response(
+            func.clone(),
+            TonResult::BlocksMasterchainInfo(BlocksMasterchainInfo {
+                last: block.clone(),
+                state_root_hash: vec![0; 32],
+                init: block,
+            }),
+        );
+        let connections: Vec<TonConnection> = vec![
+            failing.get_connection().await.unwrap(),
+            healthy.get_connection().await.unwrap(),
+        ];
+        let policy = BackoffRetryPolicy {
+            initial_delay_ms: 0,
+            max_retries: 1,
+            ..Default::default()
+        };
+        let client = TonClient::from_inner(Arc::new(Inner {
+            retry_policy: Arc::new(policy),
+            request_timeout: None,
+            budgets: CallerBudgets::default(),
+            pool: ConnectionPool::from_connections(connections),
+            archive_pool: ConnectionPool::from_connections(vec![]),
+            archive_router: None,
+        }));
+
+        for _ in 0..10 {
+            let (_, info) = client.get_masterchain_info().await.unwrap();
+            assert_eq!(info.last.seqno, 1);
+        }
+    }
+}
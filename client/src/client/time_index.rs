@@ -0,0 +1,370 @@
+use moka::sync::Cache;
+use tonlib_core::TonAddress;
+
+use crate::client::{TonClientError, TonClientInterface};
+use crate::tl::{BlockId, BlockIdExt, InternalTransactionId};
+
+pub const DEFAULT_TIME_INDEX_CAPACITY: u64 = 10000;
+
+/// Maps unix times to masterchain blocks and account transaction cursors by bisection.
+///
+/// Resolved blocks and cursors are cached, so cutting data at nearby time boundaries is cheap.
+pub struct TimeIndex<C> {
+    client: C,
+    blocks: Cache<i32, (BlockIdExt, i64)>,
+    transactions: Cache<(TonAddress, i64), Option<InternalTransactionId>>,
+}
+
+impl<C: TonClientInterface> TimeIndex<C> {
+    pub fn new(client: C) -> TimeIndex<C> {
+        Self::with_capacity(client, DEFAULT_TIME_INDEX_CAPACITY)
+    }
+
+    pub fn with_capacity(client: C, capacity: u64) -> TimeIndex<C> {
+        TimeIndex {
+            client,
+            blocks: Cache::new(capacity),
+            transactions: Cache::new(capacity),
+        }
+    }
+
+    /// Returns the first masterchain block generated at or after `utime`.
+    ///
+    /// Returns `None` if no such block is generated yet.
+    pub async fn first_master_block_after(
+        &self,
+        utime: i64,
+    ) -> Result<Option<BlockIdExt>, TonClientError> {
+        let (_, info) = self.client.get_masterchain_info().await?;
+        let last_seqno = info.last.seqno;
+        if self.gen_utime(last_seqno).await? < utime {
+            return Ok(None);
+        }
+        // the answer is in [lo, hi]
+        let (mut lo, mut hi) = (1, last_seqno);
+
+        // lookup by utime is a cheap hint, the answer is usually next to it
+        let hint = self
+            .client
+            .lookup_block(4, &master_block_id(0), 0, utime as i32)
+            .await
+            .map(|b| b.seqno)
+            .ok()
+            .filter(|s| (lo..hi).contains(s));
+        if let Some(hint) = hint {
+            if self.gen_utime(hint).await? >= utime {
+                hi = hint;
+                if hint > lo && self.gen_utime(hint - 1).await? < utime {
+                    lo = hint;
+                }
+            } else {
+                lo = hint + 1;
+                if self.gen_utime(lo).await? >= utime {
+                    hi = lo;
+                }
+            }
+        }
+
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            if self.gen_utime(mid).await? >= utime {
+                hi = mid;
+            } else {
+                lo = mid + 1;
+            }
+        }
+        Ok(Some(self.block(lo).await?.0))
+    }
+
+    /// Returns the last masterchain block generated before `utime`.
+    ///
+    /// Returns `None` if `utime` precedes the first block.
+    pub async fn last_master_block_before(
+        &self,
+        utime: i64,
+    ) -> Result<Option<BlockIdExt>, TonClientError> {
+        match self.first_master_block_after(utime).await? {
+            Some(block) if block.seqno > 1 => Ok(Some(self.block(block.seqno - 1).await?.0)),
+            Some(_) => Ok(None),
+            None => {
+                let (_, info) = self.client.get_masterchain_info().await?;
+                Ok(Some(info.last))
+            }
+        }
+    }
+
+    /// Returns id of the last transaction of the account made before `utime`.
+    ///
+    /// Paging back from the returned id, e.g. by `get_raw_transactions_v2`, yields exactly
+    /// the transactions made before `utime`. Returns `None` if there are no such transactions.
+    pub async fn last_transaction_before(
+        &self,
+        address: &TonAddress,
+        utime: i64,
+    ) -> Result<Option<InternalTransactionId>, TonClientError> {
+        let key = (address.clone(), utime);
+        if let Some(tx_id) = self.transactions.get(&key) {
+            return Ok(tx_id);
+        }
+        // without a block after `utime` the latest state is used, and the account
+        // may still get transactions before `utime`, so such results aren't cached
+        let bounding_block = self.first_master_block_after(utime).await?;
+        let state = match &bounding_block {
+            Some(block) => {
+                self.client
+                    .get_raw_account_state_at_block(address, block)
+                    .await?
+            }
+            None => self.client.get_raw_account_state(address).await?,
+        };
+        let mut cursor = state.last_transaction_id;
+        let tx_id = loop {
+            if cursor.lt == 0 {
+                break None;
+            }
+            let txs = self
+                .client
+                .get_raw_transactions_v2(address, &cursor, 16, false)
+                .await?;
+            if let Some(tx) = txs.transactions.iter().find(|tx| tx.utime < utime) {
+                break Some(tx.transaction_id.clone());
+            }
+            if txs.transactions.is_empty() {
+                break None;
+            }
+            cursor = txs.previous_transaction_id;
+        };
+        if bounding_block.is_some() {
+            self.transactions.insert(key, tx_id.clone());
+        }
+        Ok(tx_id)
+    }
+
+    async fn gen_utime(&self, seqno: i32) -> Result<i64, TonClientError> {
+        Ok(self.block(seqno).await?.1)
+    }
+
+    async fn block(&self, seqno: i32) -> Result<(BlockIdExt, i64), TonClientError> {
+        if let Some(block) = self.blocks.get(&seqno) {
+            return Ok(block);
+        }
+        let id = self
+            .client
+            .lookup_block(1, &master_block_id(seqno), 0, 0)
+            .await?;
+        let header = self.client.get_block_header(&id).await?;
+        let block = (id, header.gen_utime);
+        self.blocks.insert(seqno, block.clone());
+        Ok(block)
+    }
+}
+
+fn master_block_id(seqno: i32) -> BlockId {
+    BlockId {
+        workchain: -1,
+        shard: i64::MIN,
+        seqno,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use async_trait::async_trait;
+
+    use super::*;
+    use crate::client::{NoopConnectionCallback, TonConnection, TonFunctionHandler};
+    use crate::tl::{
+        BlocksHeader, BlocksMasterchainInfo, RawFullAccountState, RawTransaction, RawTransactions,
+        TonFunction, TonResult, NULL_TRANSACTION_ID,
+    };
+
+    const LAST_SEQNO: i32 = 1000;
+
+    /// Chain with masterchain block `seqno` generated at `1000 + 5 * seqno`,
+    /// and an account with a transaction in every even block
+    #[derive(Default)]
+    struct ChainHandler {
+        headers: AtomicUsize,
+        latest_states: AtomicUsize,
+    }
+
+    fn block(seqno: i32) -> BlockIdExt {
+        BlockIdExt {
+            workchain: -1,
+            shard: i64::MIN,
+            seqno,
+            root_hash: vec![0; 32],
+            file_hash: vec![0; 32],
+        }
+    }
+
+    fn gen_utime(seqno: i32) -> i64 {
+        1000 + 5 * seqno as i64
+    }
+
+    fn tx_id(seqno: i32) -> InternalTransactionId {
+        InternalTransactionId {
+            lt: seqno as i64 * 1000,
+            hash: vec![0; 32],
+        }
+    }
+
+    #[async_trait]
+    impl TonFunctionHandler for ChainHandler {
+        async fn handle(&self, function: &TonFunction) -> Result<TonResult, TonClientError> {
+            let result = match function {
+                TonFunction::BlocksGetMasterchainInfo {} => {
+                    TonResult::BlocksMasterchainInfo(BlocksMasterchainInfo {
+                        last: block(LAST_SEQNO),
+                        state_root_hash: vec![0; 32],
+                        init: block(0),
+                    })
+                }
+                TonFunction::BlocksLookupBlock { mode: 1, id, .. } => {
+                    TonResult::BlockIdExt(block(id.seqno))
+                }
+                // lite servers return a block close to the requested utime, not necessarily adjacent
+                TonFunction::BlocksLookupBlock { mode: 4, utime, .. } => {
+                    let seqno = ((*utime as i64 - 1000) / 5 - 3).clamp(1, LAST_SEQNO as i64);
+                    TonResult::BlockIdExt(block(seqno as i32))
+                }
+                TonFunction::GetBlockHeader { id } => {
+                    self.headers.fetch_add(1, Ordering::Relaxed);
+                    TonResult::BlocksHeader(BlocksHeader {
+                        id: id.clone(),
+                        global_id: -239,
+                        version: 0,
+                        flags: 0,
+                        after_merge: false,
+                        after_split: false,
+                        before_split: false,
+                        want_merge: false,
+                        want_split: false,
+                        validator_list_hash_short: 0,
+                        catchain_seqno: 0,
+                        min_ref_mc_seqno: 0,
+                        is_key_block: false,
+                        prev_key_block_seqno: 0,
+                        start_lt: 0,
+                        end_lt: 0,
+                        gen_utime: gen_utime(id.seqno),
+                        vert_seqno: None,
+                        prev_blocks: None,
+                    })
+                }
+                TonFunction::WithBlock { id, .. } => {
+                    TonResult::RawFullAccountState(RawFullAccountState {
+                        balance: 0,
+                        code: vec![],
+                        data: vec![],
+                        last_transaction_id: tx_id(id.seqno - id.seqno % 2),
+                        block_id: id.clone(),
+                        frozen_hash: vec![],
+                        sync_utime: gen_utime(id.seqno),
+                    })
+                }
+                TonFunction::RawGetAccountState { .. } => {
+                    self.latest_states.fetch_add(1, Ordering::Relaxed);
+                    TonResult::RawFullAccountState(RawFullAccountState {
+                        balance: 0,
+                        code: vec![],
+                        data: vec![],
+                        last_transaction_id: tx_id(LAST_SEQNO),
+                        block_id: block(LAST_SEQNO),
+                        frozen_hash: vec![],
+                        sync_utime: gen_utime(LAST_SEQNO),
+                    })
+                }
+                TonFunction::RawGetTransactionsV2 {
+                    account_address,
+                    from_transaction_id,
+                    count,
+                    ..
+                } => {
+                    let from_seqno = (from_transaction_id.lt / 1000) as i32;
+                    let transactions = (0..*count as i32)
+                        .map(|i| from_seqno - 2 * i)
+                        .filter(|seqno| *seqno > 0)
+                        .map(|seqno| RawTransaction {
+                            address: account_address.clone(),
+                            utime: gen_utime(seqno),
+                            data: vec![],
+                            transaction_id: tx_id(seqno),
+                            fee: 0,
+                            storage_fee: 0,
+                            other_fee: 0,
+                            in_msg: None,
+                            out_msgs: vec![],
+                        })
+                        .collect::<Vec<_>>();
+                    let previous_transaction_id = match transactions.last() {
+                        Some(tx) if tx.transaction_id.lt > 2000 => {
+                            tx_id((tx.transaction_id.lt / 1000) as i32 - 2)
+                        }
+                        _ => NULL_TRANSACTION_ID.clone(),
+                    };
+                    TonResult::RawTransactions(RawTransactions {
+                        transactions,
+                        previous_transaction_id,
+                    })
+                }
+                f => return Err(TonClientError::InternalError(format!("Unexpected {:?}", f))),
+            };
+            Ok(result)
+        }
+    }
+
+    fn time_index() -> (Arc<ChainHandler>, TimeIndex<TonConnection>) {
+        let handler = Arc::new(ChainHandler::default());
+        let conn =
+            TonConnection::from_handler(handler.clone(), Arc::new(NoopConnectionCallback {}));
+        (handler, TimeIndex::new(conn))
+    }
+
+    #[tokio::test]
+    async fn test_block_by_utime() -> anyhow::Result<()> {
+        let (handler, index) = time_index();
+        for (utime, expected) in [(0, 1), (1005, 1), (3000, 400), (3001, 401), (3004, 401)] {
+            let block = index.first_master_block_after(utime).await?.unwrap();
+            assert_eq!(block.seqno, expected, "utime {}", utime);
+        }
+        assert_eq!(index.first_master_block_after(7000).await?, None);
+
+        let block = index.last_master_block_before(3001).await?.unwrap();
+        assert_eq!(block.seqno, 400);
+        assert_eq!(index.last_master_block_before(0).await?, None);
+        let block = index.last_master_block_before(7000).await?.unwrap();
+        assert_eq!(block.seqno, LAST_SEQNO);
+
+        // cached blocks are not requested again
+        let headers = handler.headers.load(Ordering::Relaxed);
+        index.first_master_block_after(3001).await?;
+        assert_eq!(handler.headers.load(Ordering::Relaxed), headers);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_transaction_by_utime() -> anyhow::Result<()> {
+        let (handler, index) = time_index();
+        let address = TonAddress::NULL;
+        let tx = index.last_transaction_before(&address, 3001).await?;
+        assert_eq!(tx, Some(tx_id(400)));
+        let tx = index.last_transaction_before(&address, 3000).await?;
+        assert_eq!(tx, Some(tx_id(398)));
+        let tx = index.last_transaction_before(&address, 1010).await?;
+        assert_eq!(tx, None);
+        let tx = index.last_transaction_before(&address, 1011).await?;
+        assert_eq!(tx, Some(tx_id(2)));
+
+        // utime after the last block is answered from the latest state, which isn't cached
+        for _ in 0..2 {
+            let tx = index.last_transaction_before(&address, 7000).await?;
+            assert_eq!(tx, Some(tx_id(LAST_SEQNO)));
+        }
+        assert_eq!(handler.latest_states.load(Ordering::Relaxed), 2);
+        Ok(())
+    }
+}
@@ -6,7 +6,7 @@ use tokio::sync::broadcast;
 use tonlib_core::{TonAddress, TonHash};
 
 use super::{
-    BlocksShortTxId, TonClientError, DEFAULT_CONNECTION_CONCURRENCY_LIMIT,
+    BlocksShortTxId, PriorityWeights, TonClientError, DEFAULT_CONNECTION_CONCURRENCY_LIMIT,
     DEFAULT_NOTIFICATION_QUEUE_LENGTH, DEFAULT_UPDATE_INIT_BLOCK,
 };
 use crate::config::MAINNET_CONFIG;
@@ -52,6 +52,28 @@ pub struct TonConnectionParams {
     pub concurrency_limit: usize,
     #[serde(default = "default_update_init_block")]
     pub update_init_block: bool,
+    #[serde(default)]
+    pub backend: TonBackend,
+    /// Check proofs of lite server responses, applies to `TonBackend::LiteApi` only
+    #[serde(default)]
+    pub proof_check: bool,
+    /// Format addresses of lite server responses with testnet flag, applies to `TonBackend::LiteApi` only
+    #[serde(default)]
+    pub testnet: bool,
+    /// Shares of `concurrency_limit` granted to request priorities while requests are queued
+    #[serde(default)]
+    pub priority_weights: PriorityWeights,
+}
+
+/// Implementation used by `TonConnection` to talk to the network.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum TonBackend {
+    /// tonlibjson via `tonlib-sys`
+    #[default]
+    Tonlib,
+    /// Pure-Rust lite client, requires `liteapi` feature
+    LiteApi,
 }
 
 impl Default for TonConnectionParams {
@@ -65,6 +87,10 @@ impl Default for TonConnectionParams {
             notification_queue_length: DEFAULT_NOTIFICATION_QUEUE_LENGTH,
             concurrency_limit: DEFAULT_CONNECTION_CONCURRENCY_LIMIT,
             update_init_block: DEFAULT_UPDATE_INIT_BLOCK,
+            backend: TonBackend::default(),
+            proof_check: false,
+            testnet: false,
+            priority_weights: PriorityWeights::default(),
         }
     }
 }
@@ -102,3 +128,27 @@ impl Default for RetryStrategy {
 lazy_static! {
     pub static ref DEFAULT_RETRY_STRATEGY: RetryStrategy = RetryStrategy::default();
 }
+
+/// Outcome of `TonClient::reload_config`.
+#[derive(Debug, Default)]
+pub struct ConfigReloadReport {
+    /// Number of connections reconnected with the new config
+    pub reconnected: usize,
+    /// Connections failed to reconnect, they keep serving requests with the old connection
+    /// and use the new config on the next reconnect
+    pub failures: Vec<ConnectionReloadFailure>,
+}
+
+impl ConfigReloadReport {
+    pub fn is_ok(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+#[derive(Debug)]
+pub struct ConnectionReloadFailure {
+    /// Whether the connection belongs to the archive pool
+    pub archive: bool,
+    pub index: usize,
+    pub error: TonClientError,
+}
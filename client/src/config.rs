@@ -1,9 +1,16 @@
+use std::collections::HashSet;
+use std::net::{Ipv4Addr, SocketAddrV4};
 use std::path::Path;
 use std::{env, fs};
 
+pub use error::*;
+pub use network::*;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
+mod error;
+mod network;
+
 lazy_static::lazy_static! {
     pub static ref MAINNET_CONFIG: String = load_config(
         "TONLIB_MAINNET_CONF",
@@ -16,8 +23,9 @@ lazy_static::lazy_static! {
     );
 }
 
-#[derive(Serialize, Deserialize)]
-pub(crate) struct TonConfig {
+/// Global config of TON network: lite servers, DHT and validator settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TonConfig {
     #[serde(rename = "@type")]
     conf_type: Value,
     dht: Value,
@@ -25,7 +33,6 @@ pub(crate) struct TonConfig {
     validator: Validator,
 }
 
-#[cfg(feature = "liteapi")]
 impl TonConfig {
     pub fn from_json(config: &str) -> Result<Self, serde_json::Error> {
         serde_json::from_str(config)
@@ -34,6 +41,55 @@ impl TonConfig {
         serde_json::to_string(self)
     }
 
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, TonConfigError> {
+        let config = fs::read_to_string(path)?;
+        Ok(Self::from_json(&config)?)
+    }
+
+    /// Downloads config, e.g. from `https://ton.org/global.config.json`
+    pub async fn from_url(url: &str) -> Result<Self, TonConfigError> {
+        let response = reqwest::get(url).await?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(TonConfigError::LoadFailed {
+                url: url.to_string(),
+                status,
+            });
+        }
+        Ok(Self::from_json(&response.text().await?)?)
+    }
+
+    /// Appends lite servers of `other` which are missing in this config
+    pub fn merge_liteservers(&mut self, other: &TonConfig) {
+        let mut keys: HashSet<String> = self.liteservers.iter().map(|l| l.id.key.clone()).collect();
+        for liteserver in other.liteservers.iter() {
+            if keys.insert(liteserver.id.key.clone()) {
+                self.liteservers.push(liteserver.clone());
+            }
+        }
+    }
+
+    pub fn retain_liteservers<F: FnMut(&LiteEndpoint) -> bool>(&mut self, f: F) {
+        self.liteservers.retain(f);
+    }
+
+    /// Keeps only lite servers with given base64 public keys
+    pub fn pin_liteservers(&mut self, keys: &[&str]) -> Result<(), TonConfigError> {
+        if let Some(missing) = keys
+            .iter()
+            .find(|k| !self.liteservers.iter().any(|l| l.id.key == **k))
+        {
+            return Err(TonConfigError::LiteServerNotFound(missing.to_string()));
+        }
+        self.liteservers
+            .retain(|l| keys.contains(&l.id.key.as_str()));
+        Ok(())
+    }
+
+    pub fn get_init_block(&self) -> Result<crate::tl::BlockIdExt, serde_json::Error> {
+        serde_json::from_value(self.validator.init_block.clone())
+    }
+
     pub fn get_init_block_seqno(&self) -> i32 {
         self.validator.init_block["seqno"].as_i64().unwrap_or(0) as i32
     }
@@ -48,21 +104,27 @@ impl TonConfig {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub(crate) struct LiteEndpoint {
+pub struct LiteEndpoint {
     pub ip: i32,
     pub port: u16,
     pub id: LiteID,
 }
 
+impl LiteEndpoint {
+    pub fn socket_addr(&self) -> SocketAddrV4 {
+        SocketAddrV4::new(Ipv4Addr::from(self.ip as u32), self.port)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub(crate) struct LiteID {
+pub struct LiteID {
     #[serde(rename = "@type")]
     pub config_type: Value,
     pub key: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub(crate) struct Validator {
+struct Validator {
     #[serde(rename = "@type")]
     pub config_type: Value,
     pub zero_state: Value,
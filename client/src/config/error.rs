@@ -0,0 +1,22 @@
+use std::io;
+
+use reqwest::StatusCode;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum TonConfigError {
+    #[error("IO error ({0})")]
+    Io(#[from] io::Error),
+
+    #[error("Invalid config ({0})")]
+    Json(#[from] serde_json::Error),
+
+    #[error("Failed to load config (url: {url}, status: {status})")]
+    LoadFailed { url: String, status: StatusCode },
+
+    #[error("Transport error ({0})")]
+    TransportError(#[from] reqwest::Error),
+
+    #[error("Lite server not found in config (key: {0})")]
+    LiteServerNotFound(String),
+}
@@ -0,0 +1,189 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use tonlib_core::cell::TonCellError;
+use tonlib_core::wallet::mnemonic::KeyPair;
+use tonlib_core::wallet::ton_wallet::TonWallet;
+use tonlib_core::wallet::versioned::DEFAULT_WALLET_ID;
+use tonlib_core::wallet::wallet_version::WalletVersion;
+use tonlib_core::TonAddress;
+
+use crate::config::{TonConfig, TonConfigError, MAINNET_CONFIG, TESTNET_CONFIG};
+
+pub const MAINNET_GLOBAL_ID: i32 = -239;
+pub const TESTNET_GLOBAL_ID: i32 = -3;
+
+/// TON network to connect to, defining its config and network-specific defaults
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum TonNetwork {
+    #[default]
+    Mainnet,
+    Testnet,
+    Custom {
+        config: String,
+        global_id: i32,
+        /// Whether user-friendly addresses are flagged as testnet ones
+        testnet: bool,
+    },
+}
+
+impl TonNetwork {
+    pub fn custom(
+        config: &TonConfig,
+        global_id: i32,
+        testnet: bool,
+    ) -> Result<TonNetwork, TonConfigError> {
+        Ok(TonNetwork::Custom {
+            config: config.to_json()?,
+            global_id,
+            testnet,
+        })
+    }
+
+    pub fn from_file<P: AsRef<Path>>(
+        path: P,
+        global_id: i32,
+        testnet: bool,
+    ) -> Result<TonNetwork, TonConfigError> {
+        Self::custom(&TonConfig::from_file(path)?, global_id, testnet)
+    }
+
+    pub async fn from_url(
+        url: &str,
+        global_id: i32,
+        testnet: bool,
+    ) -> Result<TonNetwork, TonConfigError> {
+        Self::custom(&TonConfig::from_url(url).await?, global_id, testnet)
+    }
+
+    /// Returns config JSON, as accepted by `TonClientBuilder::with_config`
+    pub fn config(&self) -> String {
+        match self {
+            TonNetwork::Mainnet => MAINNET_CONFIG.clone(),
+            TonNetwork::Testnet => TESTNET_CONFIG.clone(),
+            TonNetwork::Custom { config, .. } => config.clone(),
+        }
+    }
+
+    pub fn ton_config(&self) -> Result<TonConfig, TonConfigError> {
+        Ok(TonConfig::from_json(&self.config())?)
+    }
+
+    pub fn global_id(&self) -> i32 {
+        match self {
+            TonNetwork::Mainnet => MAINNET_GLOBAL_ID,
+            TonNetwork::Testnet => TESTNET_GLOBAL_ID,
+            TonNetwork::Custom { global_id, .. } => *global_id,
+        }
+    }
+
+    pub fn is_testnet(&self) -> bool {
+        match self {
+            TonNetwork::Mainnet => false,
+            TonNetwork::Testnet => true,
+            TonNetwork::Custom { testnet, .. } => *testnet,
+        }
+    }
+
+    /// Returns wallet id of default V5R1 wallet (workchain 0, subwallet 0)
+    pub fn wallet_id_v5r1(&self) -> i32 {
+        // client context: flag bit set, workchain, version and subwallet are 0
+        self.global_id() ^ i32::MIN
+    }
+
+    /// Returns default wallet id of `version` in the network
+    pub fn default_wallet_id(&self, version: WalletVersion) -> i32 {
+        match version {
+            WalletVersion::V5R1 => self.wallet_id_v5r1(),
+            _ => DEFAULT_WALLET_ID,
+        }
+    }
+
+    /// Creates wallet in basechain with default wallet id of the network
+    pub fn wallet(
+        &self,
+        version: WalletVersion,
+        key_pair: KeyPair,
+    ) -> Result<TonWallet, TonCellError> {
+        TonWallet::new_with_params(version, key_pair, 0, self.default_wallet_id(version))
+    }
+
+    /// Formats `address` in user-friendly form, with testnet flag of the network
+    pub fn format_address(&self, address: &TonAddress, non_bounceable: bool) -> String {
+        address.to_base64_url_flags(non_bounceable, self.is_testnet())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tonlib_core::wallet::versioned::{DEFAULT_WALLET_ID_V5R1, DEFAULT_WALLET_ID_V5R1_TESTNET};
+
+    use super::*;
+
+    #[test]
+    fn test_network_defaults() -> anyhow::Result<()> {
+        assert_eq!(TonNetwork::Mainnet.wallet_id_v5r1(), DEFAULT_WALLET_ID_V5R1);
+        assert_eq!(
+            TonNetwork::Testnet.wallet_id_v5r1(),
+            DEFAULT_WALLET_ID_V5R1_TESTNET
+        );
+
+        let address =
+            TonAddress::from_base64_url("EQDk2VTvn04SUKJrW7rXahzdF8_Qi6utb0wj43InCu9vdjrR")?;
+        assert_eq!(
+            TonNetwork::Mainnet.format_address(&address, false),
+            "EQDk2VTvn04SUKJrW7rXahzdF8_Qi6utb0wj43InCu9vdjrR"
+        );
+        assert_eq!(
+            TonNetwork::Testnet.format_address(&address, false),
+            address.to_base64_url_flags(false, true)
+        );
+
+        let key_pair = KeyPair {
+            public_key: vec![1; 32],
+            secret_key: vec![2; 64],
+        };
+        let wallet = TonNetwork::Testnet.wallet(WalletVersion::V5R1, key_pair.clone())?;
+        assert_eq!(wallet.wallet_id, DEFAULT_WALLET_ID_V5R1_TESTNET);
+        let wallet = TonNetwork::Testnet.wallet(WalletVersion::V4R2, key_pair)?;
+        assert_eq!(wallet.wallet_id, DEFAULT_WALLET_ID);
+
+        let testnet = TonNetwork::custom(&TonNetwork::Testnet.ton_config()?, -3, true)?;
+        assert_eq!(testnet.global_id(), TESTNET_GLOBAL_ID);
+        assert_eq!(testnet.wallet_id_v5r1(), DEFAULT_WALLET_ID_V5R1_TESTNET);
+        assert!(testnet.is_testnet());
+        Ok(())
+    }
+
+    #[test]
+    fn test_config_liteservers() -> anyhow::Result<()> {
+        let mut config = TonNetwork::Mainnet.ton_config()?;
+        let testnet = TonNetwork::Testnet.ton_config()?;
+        let mainnet_count = config.liteservers.len();
+        let key = config.liteservers[0].id.key.clone();
+
+        config.merge_liteservers(&testnet);
+        assert_eq!(
+            config.liteservers.len(),
+            mainnet_count + testnet.liteservers.len()
+        );
+        config.merge_liteservers(&testnet);
+        assert_eq!(
+            config.liteservers.len(),
+            mainnet_count + testnet.liteservers.len()
+        );
+
+        let addr = config.liteservers[1].socket_addr();
+        config.retain_liteservers(|l| l.socket_addr() != addr);
+        assert!(config.liteservers.iter().all(|l| l.socket_addr() != addr));
+
+        config.pin_liteservers(&[key.as_str()])?;
+        assert_eq!(config.liteservers.len(), 1);
+        assert!(matches!(
+            config.pin_liteservers(&["missing"]),
+            Err(TonConfigError::LiteServerNotFound(_))
+        ));
+        Ok(())
+    }
+}
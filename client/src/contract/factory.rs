@@ -11,7 +11,7 @@ use tonlib_core::TonAddress;
 
 use crate::client::{TonClient, TonClientError, TonClientInterface};
 use crate::contract::{LoadedSmcState, TonContract, TonContractError, TonContractState};
-use crate::tl::{ConfigInfo, InternalTransactionId, RawFullAccountState};
+use crate::tl::{BlockIdExt, ConfigInfo, InternalTransactionId, RawFullAccountState};
 
 mod blockchain_library_provider;
 mod builder;
@@ -129,6 +129,19 @@ impl TonContractFactory {
         Ok(state)
     }
 
+    pub async fn get_account_state_at_block(
+        &self,
+        address: &TonAddress,
+        block_id: &BlockIdExt,
+    ) -> Result<RawFullAccountState, TonContractError> {
+        let state = self
+            .inner
+            .client
+            .get_raw_account_state_at_block(address, block_id)
+            .await?;
+        Ok(state)
+    }
+
     pub async fn get_smc_state_by_transaction(
         &self,
         address: &TonAddress,
@@ -165,7 +178,20 @@ impl TonContractFactory {
             self.get_account_state_by_transaction(address, transaction_id)
                 .await?,
         );
-        let contract_state = TonContractState::new(self, address, &account_state);
+        let contract_state = TonContractState::new_historical(self, address, &account_state);
+        Ok(contract_state)
+    }
+
+    /// Returns contract state as of masterchain block `block_id`.
+    ///
+    /// Get methods of the returned state, emulated or run by tonlib, are executed against that state.
+    pub async fn get_contract_state_at(
+        &self,
+        address: &TonAddress,
+        block_id: &BlockIdExt,
+    ) -> Result<TonContractState, TonContractError> {
+        let account_state = Arc::new(self.get_account_state_at_block(address, block_id).await?);
+        let contract_state = TonContractState::new_historical(self, address, &account_state);
         Ok(contract_state)
     }
 
@@ -9,7 +9,7 @@ use moka::future::Cache;
 use tonlib_core::TonAddress;
 
 use crate::client::{
-    BlockStream, BlockStreamItem, TonBlockFunctions, TonClient, TonClientError, TonClientInterface,
+    BlockStream, BlockStreamItem, TonBlockFunctions, TonClient, TonClientInterface, TonlibErrorKind,
 };
 use crate::contract::{LoadedSmcState, TonContractError};
 use crate::tl::{InternalTransactionId, RawFullAccountState};
@@ -93,9 +93,9 @@ impl ContractFactoryCache {
         match state_result {
             Ok(state) => Ok(state),
             Err(e) => match e.as_ref() {
-                TonContractError::ClientError(TonClientError::TonlibError {
-                    ref message, ..
-                }) if message == "transaction hash mismatch" => {
+                TonContractError::ClientError(e)
+                    if e.tonlib_error_kind() == Some(TonlibErrorKind::TransactionHashMismatch) =>
+                {
                     log::warn!("Failed to get_raw_account_state_by_transaction. Falling back to latest account state{:?}", e);
                     let r = self.inner.client.get_raw_account_state(address).await?;
                     Ok(r.into())
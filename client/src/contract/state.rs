@@ -1,24 +1,36 @@
+#[cfg(feature = "tonlib")]
 use std::collections::HashSet;
 use std::sync::Arc;
 
 use async_trait::async_trait;
+#[cfg(feature = "tonlib")]
 use tonlib_core::cell::{BagOfCells, Cell};
+#[cfg(feature = "tonlib")]
 use tonlib_core::library_helper::{ContractLibraryDict, LibraryHelper};
-use tonlib_core::{TonAddress, TonHash};
+use tonlib_core::TonAddress;
+#[cfg(feature = "tonlib")]
+use tonlib_core::TonHash;
 
+#[cfg(feature = "tonlib")]
 use super::MapCellError;
 use crate::client::{TonClientError, TonClientInterface};
 use crate::contract::{TonContractError, TonContractFactory, TonContractInterface};
+#[cfg(feature = "tonlib")]
 use crate::emulator::c7_register::TvmEmulatorC7;
+#[cfg(feature = "tonlib")]
 use crate::emulator::tvm_emulator::TvmEmulator;
 use crate::tl::RawFullAccountState;
-use crate::types::{TonMethodId, TvmMsgSuccess, TvmStackEntry, TvmSuccess};
+#[cfg(feature = "tonlib")]
+use crate::types::TvmMsgSuccess;
+use crate::types::{TonMethodId, TvmStackEntry, TvmSuccess};
 
 #[derive(Clone)]
 pub struct TonContractState {
     factory: TonContractFactory,
     address: TonAddress,
     account_state: Arc<RawFullAccountState>,
+    /// State isn't the latest one, so tonlib must not fall back to loading the latest state
+    historical: bool,
 }
 
 impl TonContractState {
@@ -31,6 +43,21 @@ impl TonContractState {
             factory: factory.clone(),
             address: address.clone(),
             account_state: account_state.clone(),
+            historical: false,
+        }
+    }
+
+    /// Creates state of the contract at some point in the past, e.g. at a masterchain block.
+    ///
+    /// Unlike `new`, `tonlib_run_get_method` fails if the state can't be loaded by its last transaction.
+    pub fn new_historical(
+        factory: &TonContractFactory,
+        address: &TonAddress,
+        account_state: &Arc<RawFullAccountState>,
+    ) -> TonContractState {
+        TonContractState {
+            historical: true,
+            ..TonContractState::new(factory, address, account_state)
         }
     }
 
@@ -75,6 +102,7 @@ impl TonContractState {
         self.tonlib_run_get_method(method, stack).await
     }
 
+    #[cfg(feature = "tonlib")]
     pub async fn emulate_get_method<M, S>(
         &self,
         method: M,
@@ -162,6 +190,7 @@ impl TonContractState {
         Self::raise_exit_error(self.address(), &method_id, run_result)
     }
 
+    #[cfg(feature = "tonlib")]
     async fn run_emulation_unsafe(
         &self,
         code: &[u8],
@@ -197,6 +226,7 @@ impl TonContractState {
         }
     }
 
+    #[cfg(feature = "tonlib")]
     pub async fn emulate_internal_message(
         &self,
         message: Cell,
@@ -239,10 +269,12 @@ impl TonContractState {
             .factory()
             .get_smc_state_by_transaction(address, transaction_id)
             .await;
-        // this fallback is not necessary
+        // falling back to the latest state is only valid if this state is the latest one
         let state = match maybe_state {
             Ok(state) => Ok(state),
-            Err(TonContractError::ClientError(TonClientError::TonlibError { .. })) => {
+            Err(TonContractError::ClientError(TonClientError::TonlibError { .. }))
+                if !self.historical =>
+            {
                 Ok(Arc::new(self.factory.client().smc_load(address).await?))
             }
             Err(e) => Err(e),
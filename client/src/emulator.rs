@@ -1,6 +1,8 @@
 pub mod c7_register;
 pub mod error;
+#[cfg(feature = "tonlib")]
 pub mod tvm_emulator;
+#[cfg(feature = "tonlib")]
 pub mod tvm_emulator_unsafe; // is used by external clients on it's own, don't make private
 pub mod types;
 pub mod utils;
@@ -7,7 +7,9 @@ use serde::{Deserialize, Serialize};
 use tonlib_core::cell::{BagOfCells, CellSlice};
 
 use crate::emulator::error::TvmEmulatorError;
-use crate::types::{TvmMsgSuccess, TvmStackEntry, TvmSuccess};
+#[cfg(feature = "tonlib")]
+use crate::types::TvmMsgSuccess;
+use crate::types::{TvmStackEntry, TvmSuccess};
 
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -129,6 +131,7 @@ impl TvmEmulatorResponse {
     }
 }
 
+#[cfg(feature = "tonlib")]
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub(crate) struct TvmEmulatorMessageResponse {
@@ -144,6 +147,7 @@ pub(crate) struct TvmEmulatorMessageResponse {
     error: Option<String>,
 }
 
+#[cfg(feature = "tonlib")]
 impl TvmEmulatorMessageResponse {
     pub fn from_json(json_str: &str) -> Result<TvmMsgSuccess, TvmEmulatorError> {
         let response: TvmEmulatorMessageResponse = serde_json::from_str(json_str)?;
@@ -120,6 +120,18 @@ pub enum TonFunction {
         body: Vec<u8>,
     },
 
+    // tonlib_api.tl, line 273
+    #[serde(rename = "raw.createQuery")]
+    RawCreateQuery {
+        destination: AccountAddress,
+        #[serde(with = "Base64Standard")]
+        init_code: Vec<u8>,
+        #[serde(with = "Base64Standard")]
+        init_data: Vec<u8>,
+        #[serde(with = "Base64Standard")]
+        body: Vec<u8>,
+    },
+
     // tonlib_api.tl, line 288
     #[serde(rename = "getAccountState")]
     GetAccountState {
@@ -139,6 +151,31 @@ pub enum TonFunction {
         mode: u32,
     },
 
+    // tonlib_api.tl, line 300
+    #[serde(rename = "query.send")]
+    QuerySend {
+        id: i64,
+    },
+
+    // tonlib_api.tl, line 301
+    #[serde(rename = "query.forget")]
+    QueryForget {
+        id: i64,
+    },
+
+    // tonlib_api.tl, line 302
+    #[serde(rename = "query.estimateFees")]
+    QueryEstimateFees {
+        id: i64,
+        ignore_chksig: bool,
+    },
+
+    // tonlib_api.tl, line 304
+    #[serde(rename = "query.getInfo")]
+    QueryGetInfo {
+        id: i64,
+    },
+
     // tonlib_api.tl, line 306
     #[serde(rename = "smc.load")]
     SmcLoad {
@@ -196,6 +233,16 @@ pub enum TonFunction {
         list: Vec<SmcLibraryQueryExt>,
     },
 
+    // tonlib_api.tl, line 317
+    #[serde(rename = "dns.resolve")]
+    DnsResolve {
+        account_address: AccountAddress,
+        name: String,
+        #[serde(with = "Base64Standard")]
+        category: Vec<u8>,
+        ttl: i32,
+    },
+
     // tonlib_api.tl, line 316
     #[serde(rename = "blocks.getMasterchainInfo")]
     BlocksGetMasterchainInfo {},
@@ -239,14 +286,47 @@ pub enum TonFunction {
         id: BlockIdExt,
     },
 
+    // tonlib_api.tl, line 332
+    #[serde(rename = "blocks.getMasterchainBlockSignatures")]
+    BlocksGetMasterchainBlockSignatures {
+        seqno: i32,
+    },
+
+    // tonlib_api.tl, line 333
+    #[serde(rename = "blocks.getShardBlockProof")]
+    BlocksGetShardBlockProof {
+        id: BlockIdExt,
+        mode: u32,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        from: Option<BlockIdExt>,
+    },
+
     // tonlib_ai.tl, line 342
     #[serde(rename = "liteServer.getInfo")]
     LiteServerGetInfo {},
 
+    // tonlib_api.tl, line 338
+    /// Executes `function` against state of block `id`
+    #[serde(rename = "withBlock")]
+    WithBlock {
+        id: BlockIdExt,
+        function: Box<TonFunction>,
+    },
+
     // tonlib_api.tl, line 352
     SetLogVerbosityLevel {
         new_verbosity_level: u32,
     },
     // tonlib_api.tl, line 355
     GetLogVerbosityLevel {},
+
+    // tonlib_api_ext.tl, line 11
+    #[serde(rename = "blocks.getOutMsgQueueSizes")]
+    BlocksGetOutMsgQueueSizes {
+        mode: u32,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        wc: Option<i32>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        shard: Option<i64>,
+    },
 }
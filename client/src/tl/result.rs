@@ -6,10 +6,11 @@ use strum::{Display, EnumDiscriminants, IntoStaticStr};
 use crate::client::TonClientError;
 use crate::tl::stack::TvmCell;
 use crate::tl::types::{
-    BlockIdExt, BlocksHeader, BlocksMasterchainInfo, BlocksShards, BlocksTransactions,
-    BlocksTransactionsExt, ConfigInfo, FullAccountState, LiteServerInfo, LogVerbosityLevel,
-    OptionsInfo, RawExtMessageInfo, RawFullAccountState, RawTransactions, SmcInfo,
-    SmcLibraryResult, SmcLibraryResultExt, SmcRunResult, UpdateSyncState,
+    BlockIdExt, BlocksBlockSignatures, BlocksHeader, BlocksMasterchainInfo, BlocksOutMsgQueueSizes,
+    BlocksShardBlockProof, BlocksShards, BlocksTransactions, BlocksTransactionsExt, ConfigInfo,
+    DnsResolved, FullAccountState, LiteServerInfo, LogVerbosityLevel, OptionsInfo, QueryFees,
+    QueryInfo, RawExtMessageInfo, RawFullAccountState, RawTransactions, SmcInfo, SmcLibraryResult,
+    SmcLibraryResultExt, SmcRunResult, UpdateSyncState,
 };
 
 #[derive(
@@ -43,6 +44,15 @@ pub enum TonResult {
     // tonlib_api.tl, line 90
     #[serde(rename = "fullAccountState")]
     FullAccountState(FullAccountState),
+    // tonlib_api.tl, line 133
+    #[serde(rename = "dns.resolved")]
+    DnsResolved(DnsResolved),
+    // tonlib_api.tl, line 162
+    #[serde(rename = "query.fees")]
+    QueryFees(QueryFees),
+    // tonlib_api.tl, line 164
+    #[serde(rename = "query.info")]
+    QueryInfo(QueryInfo),
     // tonlib_api.tl, line 167
     #[serde(rename = "tvm.cell")]
     TvmCell(TvmCell),
@@ -82,9 +92,18 @@ pub enum TonResult {
     // tonlib_api.tl, line 225
     #[serde(rename = "blocks.header")]
     BlocksHeader(BlocksHeader),
+    // tonlib_api.tl, line 229
+    #[serde(rename = "blocks.blockSignatures")]
+    BlocksBlockSignatures(BlocksBlockSignatures),
+    // tonlib_api.tl, line 232
+    #[serde(rename = "blocks.shardBlockProof")]
+    BlocksShardBlockProof(BlocksShardBlockProof),
     // tonlib_api.tl, line 243
     #[serde(rename = "configInfo")]
     ConfigInfo(ConfigInfo),
+    // tonlib_api_ext.tl, line 7
+    #[serde(rename = "blocks.outMsgQueueSizes")]
+    BlocksOutMsgQueueSizes(BlocksOutMsgQueueSizes),
 }
 
 impl TonResult {
@@ -207,6 +226,32 @@ impl fmt::Display for TonResult {
             TonResult::ConfigInfo(_) => write!(f, "TonResult::ConfigInfo"),
 
             TonResult::TvmCell(_) => write!(f, "TonResult::TvmCell"),
+
+            TonResult::DnsResolved(dns_resolved) => write!(
+                f,
+                "TonResult::DnsResolved: {} entries",
+                dns_resolved.entries.len()
+            ),
+
+            TonResult::QueryFees(query_fees) => write!(
+                f,
+                "TonResult::QueryFees: source fees {}",
+                query_fees.source_fees.total()
+            ),
+
+            TonResult::QueryInfo(query_info) => {
+                write!(f, "TonResult::QueryInfo: {}", query_info.id)
+            }
+
+            TonResult::BlocksBlockSignatures(block_signatures) => write!(
+                f,
+                "TonResult::BlocksBlockSignatures: {}:{}, seqno{}",
+                block_signatures.id.workchain, block_signatures.id.shard, block_signatures.id.seqno
+            ),
+
+            TonResult::BlocksShardBlockProof(_) => write!(f, "TonResult::BlocksShardBlockProof"),
+
+            TonResult::BlocksOutMsgQueueSizes(_) => write!(f, "TonResult::BlocksOutMsgQueueSizes"),
         }
     }
 }
@@ -57,6 +57,12 @@ pub struct AccountAddress {
     pub account_address: String,
 }
 
+// tonlib_api.tl, line 42
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AdnlAddress {
+    pub adnl_address: String,
+}
+
 // tonlib_api.tl, line 48
 #[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
 pub struct InternalTransactionId {
@@ -409,6 +415,86 @@ pub enum MsgData {
     },
 }
 
+// tonlib_api.tl, line 119-124
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+#[serde(tag = "@type")]
+pub enum DnsEntryData {
+    #[serde(rename = "dns.entryDataUnknown")]
+    Unknown {
+        #[serde(with = "Base64Standard")]
+        bytes: Vec<u8>,
+    },
+    #[serde(rename = "dns.entryDataText")]
+    Text { text: String },
+    #[serde(rename = "dns.entryDataNextResolver")]
+    NextResolver { resolver: AccountAddress },
+    #[serde(rename = "dns.entryDataSmcAddress")]
+    SmcAddress { smc_address: AccountAddress },
+    #[serde(rename = "dns.entryDataAdnlAddress")]
+    AdnlAddress { adnl_address: AdnlAddress },
+    #[serde(rename = "dns.entryDataStorageAddress")]
+    StorageAddress {
+        #[serde(with = "Base64Standard")]
+        bag_id: Vec<u8>,
+    },
+}
+
+// tonlib_api.tl, line 126
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DnsEntry {
+    pub name: String,
+    #[serde(with = "Base64Standard")]
+    pub category: Vec<u8>,
+    pub entry: DnsEntryData,
+}
+
+// tonlib_api.tl, line 133
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DnsResolved {
+    pub entries: Vec<DnsEntry>,
+}
+
+// tonlib_api.tl, line 161
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Fees {
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub in_fwd_fee: i64,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub storage_fee: i64,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub gas_fee: i64,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub fwd_fee: i64,
+}
+
+impl Fees {
+    pub fn total(&self) -> i64 {
+        self.in_fwd_fee + self.storage_fee + self.gas_fee + self.fwd_fee
+    }
+}
+
+// tonlib_api.tl, line 162
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct QueryFees {
+    pub source_fees: Fees,
+    pub destination_fees: Vec<Fees>,
+}
+
+// tonlib_api.tl, line 164
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct QueryInfo {
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub id: i64,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub valid_until: i64,
+    #[serde(with = "Base64Standard")]
+    pub body_hash: Vec<u8>,
+    #[serde(with = "Base64Standard")]
+    pub body: Vec<u8>,
+    #[serde(with = "Base64Standard")]
+    pub init_state: Vec<u8>,
+}
+
 // tonlib_api.tl, line 179
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
 pub struct SmcInfo {
@@ -584,12 +670,73 @@ pub struct BlocksHeader {
     pub prev_blocks: Option<Vec<BlockIdExt>>,
 }
 
+// tonlib_api.tl, line 228
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BlocksSignature {
+    #[serde(with = "Base64Standard")]
+    pub node_id_short: Vec<u8>,
+    #[serde(with = "Base64Standard")]
+    pub signature: Vec<u8>,
+}
+
+// tonlib_api.tl, line 229
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BlocksBlockSignatures {
+    pub id: BlockIdExt,
+    pub signatures: Vec<BlocksSignature>,
+}
+
+// tonlib_api.tl, line 230
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BlocksShardBlockLink {
+    pub id: BlockIdExt,
+    #[serde(with = "Base64Standard")]
+    pub proof: Vec<u8>,
+}
+
+// tonlib_api.tl, line 231
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BlocksBlockLinkBack {
+    pub to_key_block: bool,
+    pub from: BlockIdExt,
+    pub to: BlockIdExt,
+    #[serde(with = "Base64Standard")]
+    pub dest_proof: Vec<u8>,
+    #[serde(with = "Base64Standard")]
+    pub proof: Vec<u8>,
+    #[serde(with = "Base64Standard")]
+    pub state_proof: Vec<u8>,
+}
+
+// tonlib_api.tl, line 232
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BlocksShardBlockProof {
+    pub from: BlockIdExt,
+    pub mc_id: BlockIdExt,
+    pub links: Vec<BlocksShardBlockLink>,
+    pub mc_proof: Vec<BlocksBlockLinkBack>,
+}
+
 // tonlib_api.tl, line 234
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
 pub struct ConfigInfo {
     pub config: TvmCell,
 }
 
+// tonlib_api_ext.tl, line 6
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BlocksOutMsgQueueSize {
+    pub id: BlockIdExt,
+    pub size: i32,
+}
+
+// tonlib_api_ext.tl, line 7
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BlocksOutMsgQueueSizes {
+    pub shards: Vec<BlocksOutMsgQueueSize>,
+    pub ext_msg_queue_size_limit: i32,
+}
+
 #[cfg(test)]
 mod tests {
     use std::borrow::Cow;
@@ -597,7 +744,8 @@ mod tests {
     use tokio_test::assert_err;
     use tonlib_core::{TonTxId, TransactionIdParseError};
 
-    use crate::tl::SmcMethodId;
+    use super::*;
+    use crate::tl::TonResult;
 
     #[test]
     fn internal_transaction_id_parse_format_works() -> anyhow::Result<()> {
@@ -683,4 +831,29 @@ mod tests {
         assert_eq!(method_id, result);
         Ok(())
     }
+
+    #[test]
+    fn test_query_fees_and_dns_serde() -> anyhow::Result<()> {
+        let json = r#"{"@type":"query.fees","source_fees":{"@type":"fees","in_fwd_fee":"1000","storage_fee":"3","gas_fee":"2000","fwd_fee":"0"},"destination_fees":[]}"#;
+        let TonResult::QueryFees(fees) = serde_json::from_str(json)? else {
+            panic!("unexpected result for {}", json);
+        };
+        assert_eq!(fees.source_fees.total(), 3003);
+        assert!(fees.destination_fees.is_empty());
+
+        let json = r#"{"@type":"dns.resolved","entries":[{"@type":"dns.entry","name":"foundation.ton","category":"6Ie0ZWjk8EtjSrJxLn6D4KXhCS+QEe/lmy3n0APMpbg=","entry":{"@type":"dns.entryDataSmcAddress","smc_address":{"@type":"accountAddress","account_address":"EQCD39VS5jcptHL8vMjEXrzGaRcCVYto7HUn4bpAOg8xqB2N"}}}]}"#;
+        let TonResult::DnsResolved(resolved) = serde_json::from_str(json)? else {
+            panic!("unexpected result for {}", json);
+        };
+        assert_eq!(resolved.entries[0].category.len(), 32);
+        assert_eq!(
+            resolved.entries[0].entry,
+            DnsEntryData::SmcAddress {
+                smc_address: AccountAddress {
+                    account_address: "EQCD39VS5jcptHL8vMjEXrzGaRcCVYto7HUn4bpAOg8xqB2N".to_string()
+                }
+            }
+        );
+        Ok(())
+    }
 }
@@ -68,6 +68,15 @@ impl From<&TonMethodId> for SmcMethodId {
     }
 }
 
+impl From<&SmcMethodId> for TonMethodId {
+    fn from(value: &SmcMethodId) -> Self {
+        match value {
+            SmcMethodId::Number { number } => TonMethodId::Number(*number),
+            SmcMethodId::Name { name } => TonMethodId::Name(name.clone()),
+        }
+    }
+}
+
 impl TonMethodId {
     pub fn to_id(&self) -> i32 {
         match self {
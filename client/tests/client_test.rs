@@ -659,6 +659,7 @@ async fn abort_batch_invoke_get_raw_account_state(
 async fn archive_node_client_test() -> anyhow::Result<()> {
     let tonlib_work_dir = "./var/tonlib";
     create_dir_all(Path::new(tonlib_work_dir))?;
+    #[cfg(feature = "tonlib")]
     TonClient::set_log_verbosity_level(2);
 
     let mut client_builder = TonClientBuilder::new();
@@ -15,6 +15,7 @@ static LOG: Once = Once::new();
 #[allow(dead_code)]
 pub fn init_logging() {
     LOG.call_once(|| {
+        #[cfg(feature = "tonlib")]
         TonClient::set_log_verbosity_level(1);
         let stderr = ConsoleAppender::builder()
             .target(Target::Stderr)
@@ -0,0 +1,265 @@
+use std::convert::Infallible;
+use std::net::{Ipv4Addr, SocketAddrV4, TcpListener};
+use std::str::FromStr;
+use std::time::Duration;
+
+use adnl::crypto::{KeyPair, SecretKey};
+use base64::prelude::BASE64_STANDARD;
+use base64::Engine;
+use ton_liteapi::layers::{UnwrapMessagesLayer, WrapErrorLayer};
+use ton_liteapi::tl::common::{AccountId, BlockIdExt as LiteBlockIdExt, Int256, ZeroStateIdExt};
+use ton_liteapi::tl::request::{GetAccountState, Request, WrappedRequest};
+use ton_liteapi::tl::response::{
+    AccountState, BlockHeader, MasterchainInfo, PartialBlockProof, Response, SendMsgStatus, Version,
+};
+use ton_liteapi::types::LiteError;
+use tonlib_client::client::{
+    ConnectionCheck, GetAccountStatePrunned, LiteClient, TonBackend, TonClient, TonClientError,
+    TonClientInterface, TonProofError,
+};
+use tonlib_client::config::MAINNET_CONFIG;
+use tonlib_client::tl::{BlockId, BlockIdExt, TonFunction};
+use tonlib_core::cell::{BagOfCells, CellBuilder};
+use tonlib_core::TonAddress;
+use tower::ServiceBuilder;
+
+const LAST_SEQNO: u32 = 42;
+
+fn block_id(workchain: i32, shard: u64, seqno: u32) -> LiteBlockIdExt {
+    LiteBlockIdExt {
+        workchain,
+        shard,
+        seqno,
+        root_hash: Int256([seqno as u8; 32]),
+        file_hash: Int256([seqno as u8 + 1; 32]),
+    }
+}
+
+async fn handler(req: WrappedRequest) -> Result<Response, LiteError> {
+    match req.request {
+        Request::GetMasterchainInfo => Ok(Response::MasterchainInfo(MasterchainInfo {
+            last: block_id(-1, 0x8000_0000_0000_0000, LAST_SEQNO),
+            state_root_hash: Int256([7; 32]),
+            init: ZeroStateIdExt {
+                workchain: -1,
+                root_hash: Int256([1; 32]),
+                file_hash: Int256([2; 32]),
+            },
+        })),
+        Request::GetVersion => Ok(Response::Version(Version {
+            mode: 0,
+            version: 0x101,
+            capabilities: 7,
+            now: 1700000000,
+        })),
+        Request::LookupBlock(lookup) => Ok(Response::BlockHeader(BlockHeader {
+            id: block_id(lookup.id.workchain, lookup.id.shard, lookup.id.seqno),
+            mode: (),
+            with_state_update: None,
+            with_value_flow: None,
+            with_extra: None,
+            with_shard_hashes: None,
+            with_prev_blk_signatures: None,
+            header_proof: vec![],
+        })),
+        Request::SendMessage(_) => Ok(Response::SendMsgStatus(SendMsgStatus { status: 1 })),
+        Request::GetAccountState(req) | Request::GetAccountStatePrunned(req) => {
+            Ok(Response::AccountState(AccountState {
+                shardblk: req.id.clone(),
+                id: req.id,
+                shard_proof: vec![],
+                proof: vec![],
+                state: vec![],
+            }))
+        }
+        // claims to prove the chain, but stops before the requested block
+        Request::GetBlockProof(req) => Ok(Response::PartialBlockProof(PartialBlockProof {
+            complete: true,
+            from: req.known_block.clone(),
+            to: block_id(-1, 0x8000_0000_0000_0000, LAST_SEQNO - 1),
+            steps: vec![],
+        })),
+        _ => Err(LiteError::UnexpectedMessage),
+    }
+}
+
+/// Starts a local lite server stand-in and returns its address and public key.
+async fn start_server() -> (SocketAddrV4, Vec<u8>) {
+    let keypair = KeyPair::from(&SecretKey::from_bytes([3u8; 32]));
+    let public_key = keypair.public_key.as_bytes().to_vec();
+    let port = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let addr = SocketAddrV4::new(Ipv4Addr::LOCALHOST, port);
+    let make_service = tower::service_fn(|_| async {
+        let service = ServiceBuilder::new()
+            .layer(UnwrapMessagesLayer)
+            .layer(WrapErrorLayer)
+            .service_fn(handler);
+        Ok::<_, Infallible>(service)
+    });
+    tokio::spawn(async move {
+        let _ = ton_liteapi::server::serve(&addr, keypair, make_service).await;
+    });
+    // wait until the server accepts connections
+    while tokio::net::TcpStream::connect(addr).await.is_err() {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    (addr, public_key)
+}
+
+fn local_config(addr: &SocketAddrV4, public_key: &[u8]) -> anyhow::Result<String> {
+    let mut config: serde_json::Value = serde_json::from_str(&MAINNET_CONFIG)?;
+    config["liteservers"] = serde_json::json!([{
+        "ip": u32::from(*addr.ip()) as i32,
+        "port": addr.port(),
+        "id": {
+            "@type": "pub.ed25519",
+            "key": BASE64_STANDARD.encode(public_key),
+        },
+    }]);
+    Ok(config.to_string())
+}
+
+#[tokio::test]
+async fn test_lite_client_masterchain_info() -> anyhow::Result<()> {
+    let (addr, public_key) = start_server().await;
+    let client = LiteClient::new(addr, &public_key);
+
+    let (_, info) = client.get_masterchain_info().await?;
+    assert_eq!(info.last.seqno, LAST_SEQNO as i32);
+    assert_eq!(info.last.shard, i64::MIN);
+    assert_eq!(info.last.root_hash, vec![LAST_SEQNO as u8; 32]);
+    assert_eq!(info.state_root_hash, vec![7; 32]);
+    assert_eq!(info.init.seqno, 0);
+    assert_eq!(info.init.root_hash, vec![1; 32]);
+
+    let (_, last) = client.sync().await?;
+    assert_eq!(last, info.last);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_lite_client_functions() -> anyhow::Result<()> {
+    let (addr, public_key) = start_server().await;
+    let client = LiteClient::new(addr, &public_key);
+
+    let server_info = client.lite_server_get_info().await?;
+    assert_eq!(server_info.version, 0x101);
+    assert_eq!(server_info.capabilities, 7);
+    assert_eq!(server_info.now, 1700000000);
+
+    let block_id = BlockId {
+        workchain: 0,
+        shard: i64::MIN,
+        seqno: 17,
+    };
+    let block = client.lookup_block(1, &block_id, 0, 0).await?;
+    assert_eq!(block.to_block_id(), block_id);
+    assert_eq!(block.root_hash, vec![17; 32]);
+
+    let msg = CellBuilder::new().store_u32(32, 0xdeadbeef)?.build()?;
+    let boc = BagOfCells::from_root(msg.clone()).serialize(false)?;
+    client.send_raw_message(&boc).await?;
+    let hash = client.send_raw_message_return_hash(&boc).await?;
+    assert_eq!(hash, msg.cell_hash().to_vec());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_lite_client_errors() -> anyhow::Result<()> {
+    let (addr, public_key) = start_server().await;
+    let client = LiteClient::new(addr, &public_key);
+
+    let result = client.get_config_all(0).await;
+    match result {
+        Err(TonClientError::TonlibError { method, code, .. }) => {
+            assert_eq!(method, "GetConfigAll");
+            assert_eq!(code, 500);
+        }
+        r => panic!("Unexpected result: {:?}", r),
+    }
+
+    let result = client.invoke(&TonFunction::GetLogVerbosityLevel {}).await;
+    assert!(matches!(result, Err(TonClientError::InternalError(_))));
+
+    // connection is still usable after server errors
+    client.get_masterchain_info().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_lite_client_proof_check() -> anyhow::Result<()> {
+    let (addr, public_key) = start_server().await;
+    let trusted_key_block = BlockIdExt {
+        workchain: -1,
+        shard: i64::MIN,
+        seqno: 1,
+        root_hash: vec![1; 32],
+        file_hash: vec![2; 32],
+    };
+    let client = LiteClient::new_with_proof_check(addr, &public_key, &trusted_key_block)?;
+    assert!(client.is_proof_check_enabled());
+
+    let address = TonAddress::from_str("EQB3ncyBUTjZUA5EnFKR5_EnOMI9V1tTEAAPaiU71gc4TiUt")?;
+    let result = client.get_raw_account_state(&address).await;
+    match result {
+        Err(TonClientError::ProofError(TonProofError::BlockMismatch { expected, actual })) => {
+            assert_eq!(expected.seqno, LAST_SEQNO as i32);
+            assert_eq!(actual.seqno, trusted_key_block.seqno);
+        }
+        r => panic!("Unexpected result: {:?}", r),
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_ton_client_lite_backend() -> anyhow::Result<()> {
+    let (addr, public_key) = start_server().await;
+    let client = TonClient::builder()
+        .with_config(&local_config(&addr, &public_key)?)
+        .with_backend(TonBackend::LiteApi)
+        .with_connection_check(ConnectionCheck::None)
+        .with_pool_size(2)
+        .without_keystore()
+        .build()
+        .await?;
+
+    let (conn, info) = client.get_masterchain_info().await?;
+    assert_eq!(info.last.seqno, LAST_SEQNO as i32);
+    let (_, info) = conn.get_masterchain_info().await?;
+    assert_eq!(info.last.seqno, LAST_SEQNO as i32);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_ton_client_lite_request() -> anyhow::Result<()> {
+    let (addr, public_key) = start_server().await;
+    let client = TonClient::builder()
+        .with_config(&local_config(&addr, &public_key)?)
+        .with_backend(TonBackend::LiteApi)
+        .with_connection_check(ConnectionCheck::None)
+        .without_keystore()
+        .build()
+        .await?;
+
+    let block = block_id(-1, 0x8000_0000_0000_0000, LAST_SEQNO);
+    let request = GetAccountStatePrunned(GetAccountState {
+        id: block.clone(),
+        account: AccountId {
+            workchain: 0,
+            id: Int256([5; 32]),
+        },
+    });
+    let state = client.lite_request(request).await?;
+    assert_eq!(state.id, block);
+
+    let conn = client.get_connection().await?;
+    match conn.lite_query(Request::GetVersion).await? {
+        Response::Version(version) => assert_eq!(version.version, 0x101),
+        r => panic!("Unexpected response: {:?}", r),
+    }
+    Ok(())
+}
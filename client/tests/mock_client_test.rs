@@ -0,0 +1,324 @@
+use std::fs;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio_util::sync::CancellationToken;
+use tonlib_client::client::{
+    BlockStream, MockTonClient, NoopConnectionCallback, RecordingTonClient, ReplayTonClient,
+    TonClientError, TonClientInterface, TonConnection, TonFunctionHandler,
+};
+use tonlib_client::contract::{TonContractError, TonContractFactory};
+use tonlib_client::tl::{
+    AccountAddress, BlockIdExt, BlocksMasterchainInfo, BlocksShards, ConfigInfo, Fees, QueryFees,
+    QueryInfo, RawFullAccountState, SmcInfo, TonFunction, TonResult, TvmCell, NULL_TRANSACTION_ID,
+};
+use tonlib_client::types::TvmStackEntry;
+use tonlib_core::TonAddress;
+
+fn master_block(seqno: i32) -> BlockIdExt {
+    BlockIdExt {
+        workchain: -1,
+        shard: i64::MIN,
+        seqno,
+        root_hash: vec![seqno as u8; 32],
+        file_hash: vec![seqno as u8 + 1; 32],
+    }
+}
+
+fn lookup_master_block(seqno: i32) -> TonFunction {
+    TonFunction::BlocksLookupBlock {
+        mode: 1,
+        id: master_block(seqno).to_block_id(),
+        lt: 0,
+        utime: 0,
+    }
+}
+
+fn masterchain_info(seqno: i32) -> TonResult {
+    TonResult::BlocksMasterchainInfo(BlocksMasterchainInfo {
+        last: master_block(seqno),
+        state_root_hash: vec![0; 32],
+        init: master_block(0),
+    })
+}
+
+fn block_stream_mock() -> MockTonClient {
+    let mock = MockTonClient::new();
+    mock.add_response(
+        TonFunction::BlocksGetMasterchainInfo {},
+        masterchain_info(10),
+    );
+    for seqno in [9, 10] {
+        mock.add_response(
+            lookup_master_block(seqno),
+            TonResult::BlockIdExt(master_block(seqno)),
+        );
+        mock.add_response(
+            TonFunction::BlocksGetShards {
+                id: master_block(seqno),
+            },
+            TonResult::BlocksShards(BlocksShards { shards: vec![] }),
+        );
+    }
+    mock
+}
+
+#[tokio::test]
+async fn test_mock_client_responses() {
+    let mock = MockTonClient::new();
+    let func = TonFunction::BlocksGetMasterchainInfo {};
+    mock.add_response(func.clone(), masterchain_info(1));
+    mock.add_response(func.clone(), masterchain_info(2));
+
+    // responses are returned in order, the last one is repeated
+    for expected in [1, 2, 2] {
+        let (_, info) = mock.get_masterchain_info().await.unwrap();
+        assert_eq!(info.last.seqno, expected);
+    }
+
+    mock.add_response(
+        TonFunction::GetConfigAll { mode: 0 },
+        TonResult::Error {
+            code: 500,
+            message: "LITE_SERVER_NOTREADY".to_string(),
+        },
+    );
+    match mock.get_config_all(0).await {
+        Err(TonClientError::TonlibError { method, code, .. }) => {
+            assert_eq!(method, "GetConfigAll");
+            assert_eq!(code, 500);
+        }
+        r => panic!("Unexpected result: {:?}", r),
+    }
+
+    let r = mock.get_block_shards(&master_block(1)).await;
+    assert!(matches!(r, Err(TonClientError::InternalError(_))));
+}
+
+#[tokio::test]
+async fn test_mock_client_block_stream() {
+    let mock = block_stream_mock();
+    let mut stream = BlockStream::new(&mock, 10);
+    let item = stream.next().await.unwrap();
+    assert_eq!(item.master_shard, master_block(10));
+    assert!(item.shards.is_empty());
+}
+
+#[tokio::test]
+async fn test_mock_client_contract_factory() {
+    let mock = MockTonClient::new();
+    mock.add_response(
+        TonFunction::GetConfigAll { mode: 0 },
+        TonResult::ConfigInfo(ConfigInfo {
+            config: TvmCell {
+                bytes: vec![1, 2, 3],
+            },
+        }),
+    );
+    let factory = TonContractFactory::builder(&mock.ton_client())
+        .build()
+        .await
+        .unwrap();
+    let config = factory.get_config_cell_serial().await.unwrap();
+    assert_eq!(config, &[1, 2, 3]);
+}
+
+#[tokio::test]
+async fn test_mock_client_historical_state() {
+    let address =
+        TonAddress::from_base64_url("EQDk2VTvn04SUKJrW7rXahzdF8_Qi6utb0wj43InCu9vdjrR").unwrap();
+    let mock = block_stream_mock();
+    let get_state = TonFunction::RawGetAccountState {
+        account_address: AccountAddress {
+            account_address: address.to_hex(),
+        },
+    };
+    mock.add_response(
+        TonFunction::WithBlock {
+            id: master_block(9),
+            function: Box::new(get_state),
+        },
+        TonResult::RawFullAccountState(RawFullAccountState {
+            balance: 42,
+            code: vec![],
+            data: vec![],
+            last_transaction_id: NULL_TRANSACTION_ID.clone(),
+            block_id: master_block(9),
+            frozen_hash: vec![],
+            sync_utime: 0,
+        }),
+    );
+    let state = mock
+        .get_raw_account_state_at_seqno(&address, 9)
+        .await
+        .unwrap();
+    assert_eq!(state.balance, 42);
+    assert!(mock
+        .get_raw_account_state_at_seqno(&address, 10)
+        .await
+        .is_err());
+
+    let factory = TonContractFactory::builder(&mock.ton_client())
+        .build()
+        .await
+        .unwrap();
+    let contract_state = factory
+        .get_contract_state_at(&address, &master_block(9))
+        .await
+        .unwrap();
+    assert_eq!(contract_state.get_account_state().balance, 42);
+
+    // historical state never falls back to the latest one
+    let account_address = AccountAddress {
+        account_address: address.to_hex(),
+    };
+    mock.add_response(
+        TonFunction::SmcLoadByTransaction {
+            account_address: account_address.clone(),
+            transaction_id: NULL_TRANSACTION_ID.clone(),
+        },
+        TonResult::Error {
+            code: 500,
+            message: "cannot locate transaction".to_string(),
+        },
+    );
+    mock.add_response(
+        TonFunction::SmcLoad { account_address },
+        TonResult::SmcInfo(SmcInfo { id: 1 }),
+    );
+    let result = contract_state
+        .tonlib_run_get_method("seqno", Vec::<TvmStackEntry>::new())
+        .await;
+    assert!(matches!(
+        result,
+        Err(TonContractError::ClientError(
+            TonClientError::TonlibError { .. }
+        ))
+    ));
+}
+
+#[tokio::test]
+async fn test_record_and_replay() {
+    let path = std::env::temp_dir().join(format!("tonlib-fixture-{}.jsonl", std::process::id()));
+    let recorder = RecordingTonClient::new(&block_stream_mock(), &path)
+        .await
+        .unwrap();
+    let recorded = BlockStream::new(&recorder.ton_client(), 10)
+        .next()
+        .await
+        .unwrap();
+
+    let fixture = fs::read_to_string(&path).unwrap();
+    assert_eq!(fixture.lines().count(), 5);
+
+    let replay = ReplayTonClient::from_file(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    let replayed = BlockStream::new(&replay, 10).next().await.unwrap();
+    assert_eq!(replayed.master_shard, recorded.master_shard);
+    assert_eq!(replayed.shards, recorded.shards);
+}
+
+#[tokio::test]
+async fn test_client_connection_scores() {
+    let mock = block_stream_mock();
+    let client = mock.ton_client();
+    client.get_masterchain_info().await.unwrap();
+    assert!(client.get_block_shards(&master_block(1)).await.is_err());
+
+    let scores = client.connection_scores();
+    assert_eq!(scores.len(), 1);
+    assert_eq!(scores[0].requests, 2);
+    assert_eq!(scores[0].last_seqno, Some(10));
+    assert_eq!(scores[0].seqno_lag, 0);
+    assert_eq!(scores[0].consecutive_failures, 1);
+}
+
+/// Handler of a lite server which never answers
+struct StuckHandler;
+
+#[async_trait]
+impl TonFunctionHandler for StuckHandler {
+    async fn handle(&self, _function: &TonFunction) -> Result<TonResult, TonClientError> {
+        std::future::pending().await
+    }
+}
+
+#[tokio::test]
+async fn test_invoke_with_timeout_and_cancellation() {
+    let conn =
+        TonConnection::from_handler(Arc::new(StuckHandler), Arc::new(NoopConnectionCallback {}));
+    let func = TonFunction::BlocksGetMasterchainInfo {};
+
+    let r = conn
+        .invoke_with_timeout(&func, Duration::from_millis(10))
+        .await;
+    match r {
+        Err(TonClientError::Timeout { method, timeout }) => {
+            assert_eq!(method, "BlocksGetMasterchainInfo");
+            assert_eq!(timeout, Duration::from_millis(10));
+        }
+        r => panic!("Unexpected result: {:?}", r),
+    }
+
+    let token = CancellationToken::new();
+    let cancel = token.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        cancel.cancel();
+    });
+    let r = conn.invoke_with_cancellation(&func, &token).await;
+    assert!(matches!(r, Err(TonClientError::Cancelled { .. })));
+}
+
+#[tokio::test]
+async fn test_mock_client_estimate_fees() {
+    let mock = MockTonClient::new();
+    let destination = TonAddress::NULL;
+    let fees = Fees {
+        in_fwd_fee: 1000,
+        storage_fee: 3,
+        gas_fee: 2000,
+        fwd_fee: 0,
+    };
+    mock.add_response(
+        TonFunction::RawCreateQuery {
+            destination: AccountAddress {
+                account_address: destination.to_hex(),
+            },
+            init_code: vec![],
+            init_data: vec![],
+            body: vec![1, 2, 3],
+        },
+        TonResult::QueryInfo(QueryInfo {
+            id: 5,
+            valid_until: 0,
+            body_hash: vec![0; 32],
+            body: vec![1, 2, 3],
+            init_state: vec![],
+        }),
+    );
+    mock.add_response(
+        TonFunction::QueryEstimateFees {
+            id: 5,
+            ignore_chksig: true,
+        },
+        TonResult::QueryFees(QueryFees {
+            source_fees: fees.clone(),
+            destination_fees: vec![],
+        }),
+    );
+    mock.add_response(TonFunction::QueryForget { id: 5 }, TonResult::Ok {});
+
+    let result = mock
+        .estimate_fees(&destination, &[], &[], &[1, 2, 3], true)
+        .await
+        .unwrap();
+    assert_eq!(result.source_fees, fees);
+    assert_eq!(result.source_fees.total(), 3003);
+
+    let error = mock
+        .estimate_fees(&destination, &[], &[], &[1, 2, 3], false)
+        .await;
+    assert!(error.is_err());
+}
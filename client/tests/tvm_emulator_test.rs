@@ -1,3 +1,5 @@
+#![cfg(feature = "tonlib")]
+
 mod common;
 
 use std::ops::Neg;
@@ -1,4 +1,4 @@
-use std::fmt::Debug;
+use std::fmt::{Debug, Display};
 use std::hash::Hash;
 use std::io;
 use std::ops::Deref;
@@ -8,8 +8,10 @@ pub use bag_of_cells::*;
 use base64::Engine;
 use bitstream_io::{BigEndian, BitWrite, BitWriter};
 pub use builder::*;
+pub use cell_store::*;
 pub use error::*;
 use hmac::digest::Digest;
+pub use lazy_boc::*;
 use lazy_static::lazy_static;
 pub use parser::*;
 pub use raw::*;
@@ -19,21 +21,29 @@ pub use ton_cell_num::*;
 pub use util::*;
 
 use crate::cell::cell_type::CellType;
+use crate::cell::fift::{parse_cell_fift, write_cell_fift};
 use crate::cell::level_mask::LevelMask;
 use crate::types::DEFAULT_CELL_HASH;
 use crate::TonHash;
 
 mod bag_of_cells;
 mod builder;
+mod cell_store;
 mod cell_type;
 pub mod dict;
+pub mod diff;
 mod error;
+mod fift;
+mod lazy_boc;
 mod level_mask;
 mod ton_cell_num;
 
 mod parser;
 mod raw;
 mod raw_boc_from_boc;
+mod raw_stream;
+#[cfg(feature = "serde")]
+pub mod serde_boc;
 mod slice;
 #[cfg(test)]
 mod test_boc;
@@ -91,6 +101,37 @@ impl Cell {
         Ok(result)
     }
 
+    /// Creates an absent cell of an incomplete BoC from hashes and depths of its significant levels
+    pub(crate) fn absent(
+        level_mask: u32,
+        hashes: Vec<TonHash>,
+        depths: Vec<u16>,
+    ) -> Result<Self, TonCellError> {
+        let cell_type = CellType::Absent;
+        let level_mask = LevelMask::new(level_mask);
+        let hash_count = level_mask.hash_count();
+        if hashes.len() != hash_count || depths.len() != hash_count {
+            return Err(TonCellError::InvalidCellData(format!(
+                "Absent cell with level mask {} must have {hash_count} hashes and depths, got {} and {}",
+                level_mask.mask(),
+                hashes.len(),
+                depths.len()
+            )));
+        }
+        let (hashes, depths) =
+            cell_type.resolve_hashes_and_depths(hashes, depths, &[], 0, level_mask)?;
+
+        Ok(Self {
+            data: vec![],
+            bit_len: 0,
+            references: vec![],
+            cell_type,
+            level_mask,
+            hashes,
+            depths,
+        })
+    }
+
     pub fn parser(&self) -> CellParser<'_> {
         CellParser::new(self)
     }
@@ -162,6 +203,21 @@ impl Cell {
         self.cell_type == CellType::Library
     }
 
+    /// Whether the cell is absent in an incomplete BoC.
+    ///
+    /// Absent cells have no data and references, only their hashes and depths are known.
+    pub fn is_absent(&self) -> bool {
+        self.cell_type == CellType::Absent
+    }
+
+    /// Hashes and depths of significant levels, as they're stored in BoC
+    pub(crate) fn significant_hashes_and_depths(&self) -> (Vec<TonHash>, Vec<u16>) {
+        (0..=self.level_mask.level())
+            .filter(|&level| self.level_mask.is_significant(level))
+            .map(|level| (self.get_hash(level), self.get_depth(level)))
+            .unzip()
+    }
+
     pub fn cell_hash_base64(&self) -> String {
         self.cell_hash().to_base64()
     }
@@ -226,6 +282,23 @@ impl Cell {
         }
     }
 
+    /// Prints cell tree in Fift format, see [`Cell::from_fift`]
+    pub fn to_fift_string(&self) -> String {
+        self.to_string()
+    }
+
+    /// Parses cell tree printed by Fift, like
+    /// ```text
+    /// x{C_}
+    ///  x{B5EE9C72}
+    ///   x{B_}
+    /// ```
+    /// Each reference is indented deeper than its parent, `_` is a completion tag
+    /// and exotic cells are prefixed with `SPECIAL`.
+    pub fn from_fift(text: &str) -> Result<Cell, TonCellError> {
+        parse_cell_fift(text)
+    }
+
     pub fn to_arc(self) -> ArcCell {
         Arc::new(self)
     }
@@ -251,6 +324,12 @@ impl Debug for Cell {
     }
 }
 
+impl Display for Cell {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write_cell_fift(f, self, 0)
+    }
+}
+
 impl Default for Cell {
     fn default() -> Self {
         Self {
@@ -554,7 +633,8 @@ mod test {
         let r2 = get_refs_descriptor(CellType::Ordinary, &[], 4).is_err();
         assert!(r2);
 
-        let r3 = get_refs_descriptor(CellType::Ordinary, &[empty_cell.clone()], 3).unwrap();
+        let r3 =
+            get_refs_descriptor(CellType::Ordinary, std::slice::from_ref(&empty_cell), 3).unwrap();
         assert_eq!(r3, 97);
 
         let r4 =
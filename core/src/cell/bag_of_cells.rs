@@ -1,10 +1,36 @@
+use std::io::{Read, Write};
 use std::sync::Arc;
 
 use base64::engine::general_purpose::STANDARD;
+#[cfg(feature = "tokio")]
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 
 use crate::cell::raw_boc_from_boc::convert_to_raw_boc;
+use crate::cell::raw_stream::{RawBocDecoder, RawBocEncoder};
 use crate::cell::*;
 
+/// Order in which cells are laid out in serialized BoC
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Hash, Default)]
+pub enum BocCellOrder {
+    /// Level by level starting from roots, the order used by `serialize`
+    #[default]
+    BreadthFirst,
+    /// Every subtree is stored contiguously, the order used by the reference implementation
+    DepthFirst,
+}
+
+/// Optional parts of serialized BoC, see `BagOfCells::serialize_with_options`
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Hash, Default)]
+pub struct BocSerializeOptions {
+    /// Write index of cell offsets
+    pub has_idx: bool,
+    /// Append crc32c checksum
+    pub has_crc32c: bool,
+    /// Mark cells with several parents in index, requires `has_idx`
+    pub has_cache_bits: bool,
+    pub cell_order: BocCellOrder,
+}
+
 #[derive(PartialEq, Eq, Debug, Clone, Hash)]
 pub struct BagOfCells {
     pub roots: Vec<ArcCell>,
@@ -51,11 +77,64 @@ impl BagOfCells {
         }
     }
 
+    /// Parses BoC in any of the supported formats.
+    ///
+    /// Absent cells of incomplete BoCs are kept with their hashes and depths only, see
+    /// [`Cell::is_absent`]. Hashes of their ancestors are the original ones, absent cells have no
+    /// data, and the BoC is serialized back with the same absent cells.
     pub fn parse(serial: &[u8]) -> Result<BagOfCells, TonCellError> {
         if serial.is_empty() {
             return Err(TonCellError::boc_deserialization_error("Empty BoC"));
         }
         let raw = RawBagOfCells::parse(serial)?;
+        Self::from_raw(raw, None)
+    }
+
+    /// Parses BoC reusing cells of `store`, so equal subtrees of different BoCs are shared
+    pub fn parse_with_store(serial: &[u8], store: &CellStore) -> Result<BagOfCells, TonCellError> {
+        if serial.is_empty() {
+            return Err(TonCellError::boc_deserialization_error("Empty BoC"));
+        }
+        let raw = RawBagOfCells::parse(serial)?;
+        Self::from_raw(raw, Some(store))
+    }
+
+    /// Reads BoC from `reader`, verifying crc32c on the fly.
+    ///
+    /// BoC is read in small pieces, down to two bytes per cell descriptor, and nothing is read
+    /// past its end. Wrap unbuffered readers such as `File` or `TcpStream` into `BufReader`.
+    pub fn read<R: Read>(mut reader: R) -> Result<BagOfCells, TonCellError> {
+        let mut decoder = RawBocDecoder::new();
+        let mut buf = vec![];
+        while decoder.needed() > 0 {
+            buf.resize(decoder.needed(), 0);
+            reader
+                .read_exact(&mut buf)
+                .map_boc_deserialization_error()?;
+            decoder.feed(&buf)?;
+        }
+        Self::from_raw(decoder.finish()?, None)
+    }
+
+    /// Async version of [`BagOfCells::read`], wrap unbuffered readers into `tokio::io::BufReader`
+    #[cfg(feature = "tokio")]
+    pub async fn read_async<R: AsyncRead + Unpin>(
+        mut reader: R,
+    ) -> Result<BagOfCells, TonCellError> {
+        let mut decoder = RawBocDecoder::new();
+        let mut buf = vec![];
+        while decoder.needed() > 0 {
+            buf.resize(decoder.needed(), 0);
+            reader
+                .read_exact(&mut buf)
+                .await
+                .map_boc_deserialization_error()?;
+            decoder.feed(&buf)?;
+        }
+        Self::from_raw(decoder.finish()?, None)
+    }
+
+    fn from_raw(raw: RawBagOfCells, store: Option<&CellStore>) -> Result<BagOfCells, TonCellError> {
         let num_cells = raw.cells.len();
         let mut cells: Vec<ArcCell> = Vec::with_capacity(num_cells);
 
@@ -70,14 +149,14 @@ impl BagOfCells {
                 references.push(cells[num_cells - 1 - ref_index].clone());
             }
 
-            let cell = Cell::new(
-                raw_cell.data,
-                raw_cell.bit_len,
-                references,
-                raw_cell.is_exotic,
-            )
-            .map_boc_deserialization_error()?;
-            cells.push(cell.to_arc());
+            let cell = raw_cell
+                .into_cell(references)
+                .map_boc_deserialization_error()?;
+            let cell = match store {
+                Some(store) => store.intern(cell),
+                None => cell.to_arc(),
+            };
+            cells.push(cell);
         }
 
         let roots = raw
@@ -102,7 +181,48 @@ impl BagOfCells {
     }
 
     pub fn serialize(&self, has_crc32: bool) -> Result<Vec<u8>, TonCellError> {
-        let raw = convert_to_raw_boc(self)?;
-        raw.serialize(has_crc32)
+        self.serialize_with_options(&BocSerializeOptions {
+            has_crc32c: has_crc32,
+            ..Default::default()
+        })
+    }
+
+    pub fn serialize_with_options(
+        &self,
+        options: &BocSerializeOptions,
+    ) -> Result<Vec<u8>, TonCellError> {
+        let raw = convert_to_raw_boc(self, options.cell_order)?;
+        raw.serialize(options)
+    }
+
+    /// Writes serialized BoC to `writer` without building it in memory as a whole
+    pub fn write<W: Write>(
+        &self,
+        mut writer: W,
+        options: &BocSerializeOptions,
+    ) -> Result<(), TonCellError> {
+        let raw = convert_to_raw_boc(self, options.cell_order)?;
+        let mut encoder = RawBocEncoder::new(&raw, options)?;
+        while let Some(chunk) = encoder.next_chunk()? {
+            writer.write_all(chunk).map_boc_serialization_error()?;
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "tokio")]
+    pub async fn write_async<W: AsyncWrite + Unpin>(
+        &self,
+        mut writer: W,
+        options: &BocSerializeOptions,
+    ) -> Result<(), TonCellError> {
+        let raw = convert_to_raw_boc(self, options.cell_order)?;
+        let mut encoder = RawBocEncoder::new(&raw, options)?;
+        while let Some(chunk) = encoder.next_chunk()? {
+            writer
+                .write_all(chunk)
+                .await
+                .map_boc_serialization_error()?;
+        }
+        Ok(())
     }
 }
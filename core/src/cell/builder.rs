@@ -8,9 +8,11 @@ use num_bigint::{BigInt, BigUint};
 use num_traits::Zero;
 
 use super::TonCellNum;
-use crate::cell::dict::{DictBuilder, ValWriter};
+use crate::cell::dict::{
+    AugDictBuilder, AugDictData, AugMerger, AugWriter, DictBuilder, ValWriter,
+};
 use crate::cell::error::{MapTonCellError, TonCellError};
-use crate::cell::{ArcCell, Cell, CellParser};
+use crate::cell::{ArcCell, Cell, CellParser, CellStore};
 use crate::tlb_types::block::msg_address::MsgAddress;
 use crate::tlb_types::tlb::TLB;
 use crate::{TonAddress, TonHash};
@@ -92,10 +94,35 @@ impl CellBuilder {
         self.store_number(bit_len, &val)
     }
 
+    /// Stores `val` without sign handling, so negative numbers need all 64 bits,
+    /// unlike [`CellBuilder::store_i128`]
     pub fn store_i64(&mut self, bit_len: usize, val: i64) -> Result<&mut Self, TonCellError> {
         self.store_number(bit_len, &val)
     }
 
+    pub fn store_u128(&mut self, bit_len: usize, val: u128) -> Result<&mut Self, TonCellError> {
+        self.store_number(bit_len, &val)
+    }
+
+    /// Stores `val` as `bit_len`-bit two's complement number.
+    ///
+    /// Narrower signed stores such as [`CellBuilder::store_i64`] fail for negative numbers
+    /// shorter than the type.
+    pub fn store_i128(&mut self, bit_len: usize, val: i128) -> Result<&mut Self, TonCellError> {
+        match bit_len {
+            1..128 => {
+                let shift = 128 - bit_len as u32;
+                if (val << shift) >> shift != val {
+                    return Err(TonCellError::CellBuilderError(format!(
+                        "Cannot write number {val} in {bit_len} bits"
+                    )));
+                }
+                self.store_u128(bit_len, val as u128 & (u128::MAX >> shift))
+            }
+            _ => self.store_number(bit_len, &val),
+        }
+    }
+
     pub fn store_uint(&mut self, bit_len: usize, val: &BigUint) -> Result<&mut Self, TonCellError> {
         self.store_number(bit_len, val)
     }
@@ -296,6 +323,50 @@ impl CellBuilder {
         }
     }
 
+    /// Stores `HashmapAug n X Y`, extra values of forks are calculated with `aug_merger`
+    pub fn store_dict_aug_data<K, V, A>(
+        &mut self,
+        key_len_bits: usize,
+        value_writer: ValWriter<V>,
+        aug_writer: AugWriter<A>,
+        aug_merger: AugMerger<A>,
+        data: AugDictData<K, V, A>,
+    ) -> Result<&mut Self, TonCellError>
+    where
+        BigUint: From<K>,
+    {
+        let dict_builder =
+            AugDictBuilder::new(key_len_bits, value_writer, aug_writer, aug_merger, data)?;
+        let (dict_cell, _) = dict_builder.build()?;
+        self.store_cell(&dict_cell)
+    }
+
+    /// Stores `HashmapAugE n X Y`, extra value of empty dict is `A::default()`
+    pub fn store_dict_aug<K, V, A: Default>(
+        &mut self,
+        key_len_bits: usize,
+        value_writer: ValWriter<V>,
+        aug_writer: AugWriter<A>,
+        aug_merger: AugMerger<A>,
+        data: AugDictData<K, V, A>,
+    ) -> Result<&mut Self, TonCellError>
+    where
+        BigUint: From<K>,
+    {
+        if data.is_empty() {
+            self.store_bit(false)?;
+            aug_writer(self, &A::default())?;
+        } else {
+            let dict_builder =
+                AugDictBuilder::new(key_len_bits, value_writer, aug_writer, aug_merger, data)?;
+            let (dict_cell, extra) = dict_builder.build()?;
+            self.store_bit(true)?;
+            self.store_child(dict_cell)?;
+            aug_writer(self, &extra)?;
+        }
+        Ok(self)
+    }
+
     pub fn store_tonhash(&mut self, ton_hash: &TonHash) -> Result<&mut Self, TonCellError> {
         self.store_slice(ton_hash.as_slice())
     }
@@ -444,6 +515,11 @@ impl CellBuilder {
             ))
         }
     }
+
+    /// Builds cell and returns its shared copy from `store`
+    pub fn build_with_store(&mut self, store: &CellStore) -> Result<ArcCell, TonCellError> {
+        Ok(store.intern(self.build()?))
+    }
 }
 
 impl Default for CellBuilder {
@@ -786,6 +862,42 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_store_load_128() -> Result<(), TonCellError> {
+        let mut builder = CellBuilder::new();
+        builder.store_bit(true)?;
+        builder.store_u128(128, u128::MAX)?;
+        builder.store_i128(128, i128::MIN)?;
+        builder.store_u128(100, (1 << 100) - 1)?;
+        assert!(builder.store_u128(100, 1 << 100).is_err());
+        builder.store_i128(77, -5)?;
+        builder.store_i128(77, -(1 << 76))?;
+        builder.store_i128(77, (1 << 76) - 1)?;
+        builder.store_i128(1, -1)?;
+        assert!(builder.store_i128(77, 1 << 76).is_err());
+        assert!(builder.store_i128(77, -(1 << 76) - 1).is_err());
+        let cell = builder.build()?;
+        let mut parser = cell.parser();
+        assert!(parser.load_bit()?);
+        assert_eq!(parser.load_u128(128)?, u128::MAX);
+        assert_eq!(parser.load_i128(128)?, i128::MIN);
+        assert_eq!(parser.load_u128(100)?, (1 << 100) - 1);
+        assert_eq!(parser.load_i128(77)?, -5);
+        assert_eq!(parser.load_i128(77)?, -(1 << 76));
+        assert_eq!(parser.load_i128(77)?, (1 << 76) - 1);
+        assert_eq!(parser.load_i128(1)?, -1);
+        parser.ensure_empty()?;
+
+        // negative numbers are stored as two's complement, like `store_int`
+        let expected = CellBuilder::new()
+            .store_int(77, &BigInt::from(-5))?
+            .build()?;
+        let cell = CellBuilder::new().store_i128(77, -5)?.build()?;
+        assert_eq!(cell, expected);
+        assert_eq!(cell.parser().load_int(77)?, BigInt::from(-5));
+        Ok(())
+    }
+
     #[test]
     fn test_store_load_117146891372() -> Result<(), TonCellError> {
         let mut test = CellBuilder::new();
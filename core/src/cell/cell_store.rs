@@ -0,0 +1,183 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard, Weak};
+
+use crate::cell::{ArcCell, Cell, TonCellError};
+use crate::TonHash;
+
+const MIN_PURGE_THRESHOLD: usize = 1024;
+
+/// Interner of cells keyed by `cell_hash()`.
+///
+/// Cells passed through the store are deduplicated, so equal subtrees of different BoCs
+/// share the same `ArcCell`. The store keeps only weak references: a cell is evicted once
+/// all its users drop it. Dead entries are purged when the store doubles in size.
+/// Absent cells of incomplete BoCs are passed through as is, they're never stored.
+#[derive(Debug, Default)]
+pub struct CellStore {
+    inner: Mutex<CellStoreInner>,
+}
+
+#[derive(Debug, Default)]
+struct CellStoreInner {
+    cells: HashMap<TonHash, Weak<Cell>>,
+    purge_threshold: usize,
+}
+
+impl CellStore {
+    pub fn new() -> CellStore {
+        CellStore::default()
+    }
+
+    /// Returns shared cell equal to `cell`, storing `cell` if there is no such cell yet
+    pub fn intern(&self, cell: Cell) -> ArcCell {
+        if cell.is_absent() {
+            return cell.to_arc();
+        }
+        let mut inner = self.lock();
+        let hash = cell.cell_hash();
+        if let Some(existing) = inner.cells.get(&hash).and_then(Weak::upgrade) {
+            return existing;
+        }
+        let arc = cell.to_arc();
+        inner.insert(hash, &arc);
+        arc
+    }
+
+    /// Same as [`CellStore::intern`], but keeps `cell` itself if there is no equal cell yet
+    pub fn intern_arc(&self, cell: ArcCell) -> ArcCell {
+        if cell.is_absent() {
+            return cell;
+        }
+        let mut inner = self.lock();
+        let hash = cell.cell_hash();
+        if let Some(existing) = inner.cells.get(&hash).and_then(Weak::upgrade) {
+            return existing;
+        }
+        inner.insert(hash, &cell);
+        cell
+    }
+
+    /// Interns `cell` with all its references, replacing them with shared cells
+    pub fn intern_tree(&self, cell: &ArcCell) -> Result<ArcCell, TonCellError> {
+        if cell.is_absent() {
+            return Ok(cell.clone());
+        }
+        if let Some(existing) = self.get(&cell.cell_hash()) {
+            return Ok(existing);
+        }
+        let references = cell
+            .references()
+            .iter()
+            .map(|r| self.intern_tree(r))
+            .collect::<Result<Vec<_>, _>>()?;
+        let unchanged = references
+            .iter()
+            .zip(cell.references())
+            .all(|(interned, original)| Arc::ptr_eq(interned, original));
+        if unchanged {
+            Ok(self.intern_arc(cell.clone()))
+        } else {
+            let rebuilt = Cell::new(
+                cell.data().to_vec(),
+                cell.bit_len(),
+                references,
+                cell.is_exotic(),
+            )?;
+            Ok(self.intern(rebuilt))
+        }
+    }
+
+    pub fn get(&self, hash: &TonHash) -> Option<ArcCell> {
+        self.lock().cells.get(hash).and_then(Weak::upgrade)
+    }
+
+    /// Number of entries, including the ones that are not purged yet
+    pub fn len(&self) -> usize {
+        self.lock().cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lock().cells.is_empty()
+    }
+
+    /// Removes entries of cells which are not used anymore
+    pub fn purge(&self) {
+        self.lock().purge();
+    }
+
+    pub fn clear(&self) {
+        let mut inner = self.lock();
+        inner.cells.clear();
+        inner.purge_threshold = 0;
+    }
+
+    fn lock(&self) -> MutexGuard<'_, CellStoreInner> {
+        // the map stays consistent even if other thread panicked while holding the lock
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl CellStoreInner {
+    fn insert(&mut self, hash: TonHash, cell: &ArcCell) {
+        self.cells.insert(hash, Arc::downgrade(cell));
+        if self.cells.len() >= self.purge_threshold.max(MIN_PURGE_THRESHOLD) {
+            self.purge();
+        }
+    }
+
+    fn purge(&mut self) {
+        self.cells.retain(|_, cell| cell.strong_count() > 0);
+        self.purge_threshold = self.cells.len() * 2;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cell::{BagOfCells, CellBuilder};
+
+    fn tree(value: u32) -> Result<Cell, TonCellError> {
+        let code = CellBuilder::new().store_u32(32, 0xc0de)?.build()?;
+        let data = CellBuilder::new().store_u32(32, value)?.build()?;
+        CellBuilder::new()
+            .store_child(code)?
+            .store_child(data)?
+            .build()
+    }
+
+    #[test]
+    fn test_cell_store_dedup() -> Result<(), TonCellError> {
+        let store = CellStore::new();
+        let first = BagOfCells::from_root(tree(1)?).serialize(false)?;
+        let second = BagOfCells::from_root(tree(2)?).serialize(false)?;
+        let first = BagOfCells::parse_with_store(&first, &store)?.single_root()?;
+        let second = BagOfCells::parse_with_store(&second, &store)?.single_root()?;
+
+        assert!(Arc::ptr_eq(first.reference(0)?, second.reference(0)?));
+        assert!(!Arc::ptr_eq(first.reference(1)?, second.reference(1)?));
+        assert_eq!(store.len(), 5);
+
+        let built = CellBuilder::new()
+            .store_u32(32, 0xc0de)?
+            .build_with_store(&store)?;
+        assert!(Arc::ptr_eq(&built, first.reference(0)?));
+
+        let again = store.intern_tree(&tree(1)?.to_arc())?;
+        assert!(Arc::ptr_eq(&again, &first));
+        Ok(())
+    }
+
+    #[test]
+    fn test_cell_store_eviction() -> Result<(), TonCellError> {
+        let store = CellStore::new();
+        let root = store.intern_tree(&tree(1)?.to_arc())?;
+        let hash = root.cell_hash();
+        assert!(store.get(&hash).is_some());
+
+        drop(root);
+        assert!(store.get(&hash).is_none());
+        store.purge();
+        assert!(store.is_empty());
+        Ok(())
+    }
+}
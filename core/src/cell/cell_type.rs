@@ -17,6 +17,8 @@ pub(crate) enum CellType {
     Library,
     MerkleProof,
     MerkleUpdate,
+    /// Absent cell of an incomplete BoC, only its hashes and depths are known
+    Absent,
 }
 
 #[derive(Debug, Clone)]
@@ -60,6 +62,9 @@ impl CellType {
             CellType::Library => self.validate_library(bit_len),
             CellType::MerkleProof => self.validate_merkle_proof(data, bit_len, references),
             CellType::MerkleUpdate => self.validate_merkle_update(data, bit_len, references),
+            CellType::Absent => Err(TonCellError::InvalidExoticCellData(
+                "Absent cell can't be built from data".to_owned(),
+            )),
         }
     }
 
@@ -82,6 +87,11 @@ impl CellType {
                 .level_mask
                 .apply_or(references[1].level_mask)
                 .shift_right(),
+            CellType::Absent => {
+                return Err(TonCellError::InvalidExoticCellData(
+                    "Level mask of absent cell can't be computed from data".to_owned(),
+                ))
+            }
         };
 
         Ok(result)
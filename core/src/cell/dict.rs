@@ -1,13 +1,24 @@
+mod aug_builder;
+mod aug_parser;
 mod builder;
+mod label;
 mod leading_bit_utils;
 mod parser;
 pub mod predefined_readers;
 pub mod predefined_writers;
+mod ton_dict;
 mod types;
+mod var_parser;
 
+pub(crate) use aug_builder::AugDictBuilder;
+pub(crate) use aug_parser::AugDictParser;
 pub(crate) use builder::DictBuilder;
 pub(crate) use parser::DictParser;
-pub use types::{KeyReader, SnakeFormatDict, ValReader, ValWriter};
+pub use ton_dict::{TonDict, TonDictIter};
+pub use types::{
+    AugDictData, AugMerger, AugWriter, KeyReader, SnakeFormatDict, ValReader, ValWriter, VarDictKey,
+};
+pub(crate) use var_parser::{VarDictKind, VarDictParser};
 
 #[cfg(test)]
 mod tests;
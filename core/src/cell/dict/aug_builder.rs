@@ -0,0 +1,87 @@
+use super::label::{common_prefix_len, store_label, uint_to_bits};
+use crate::cell::dict::{AugDictData, AugMerger, AugWriter, ValWriter};
+use crate::cell::{Cell, CellBuilder, TonCellError};
+
+/// Builder of `HashmapAug n X Y`, extra values of forks are calculated with `aug_merger`
+pub(crate) struct AugDictBuilder<V, A> {
+    key_len_bits: usize,
+    value_writer: ValWriter<V>,
+    aug_writer: AugWriter<A>,
+    aug_merger: AugMerger<A>,
+    entries: Vec<(Vec<bool>, V, A)>, // sorted by key
+}
+
+impl<V, A> AugDictBuilder<V, A> {
+    pub(crate) fn new<K>(
+        key_len_bits: usize,
+        value_writer: ValWriter<V>,
+        aug_writer: AugWriter<A>,
+        aug_merger: AugMerger<A>,
+        data: AugDictData<K, V, A>,
+    ) -> Result<Self, TonCellError>
+    where
+        num_bigint::BigUint: From<K>,
+    {
+        let mut entries = data
+            .into_iter()
+            .map(|(key, (value, extra))| {
+                let key = uint_to_bits(&key.into(), key_len_bits)?;
+                Ok((key, value, extra))
+            })
+            .collect::<Result<Vec<_>, TonCellError>>()?;
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+
+        Ok(AugDictBuilder {
+            key_len_bits,
+            value_writer,
+            aug_writer,
+            aug_merger,
+            entries,
+        })
+    }
+
+    /// Returns root cell and its extra value
+    pub(crate) fn build(mut self) -> Result<(Cell, A), TonCellError> {
+        if self.entries.is_empty() {
+            return Err(TonCellError::CellBuilderError(
+                "can't build empty HashmapAug".to_string(),
+            ));
+        }
+        let entries = std::mem::take(&mut self.entries);
+        self.build_node(entries, 0)
+    }
+
+    // all entries have the same first `offset` bits of key
+    fn build_node(
+        &self,
+        mut entries: Vec<(Vec<bool>, V, A)>,
+        offset: usize,
+    ) -> Result<(Cell, A), TonCellError> {
+        let max_len = self.key_len_bits - offset;
+        let mut builder = CellBuilder::new();
+        if entries.len() == 1 {
+            let (key, value, extra) = entries.pop().unwrap(); // unwrap is safe: entries has 1 element
+            store_label(&mut builder, &key[offset..], max_len)?;
+            (self.aug_writer)(&mut builder, &extra)?;
+            (self.value_writer)(&mut builder, value)?;
+            return Ok((builder.build()?, extra));
+        }
+
+        let first = &entries[0].0[offset..];
+        let last = &entries[entries.len() - 1].0[offset..];
+        let label_len = common_prefix_len(first, last);
+        store_label(&mut builder, &first[..label_len], max_len)?;
+
+        let branch_pos = offset + label_len;
+        let split = entries.partition_point(|(key, _, _)| !key[branch_pos]);
+        let right_entries = entries.split_off(split);
+        let (left, left_extra) = self.build_node(entries, branch_pos + 1)?;
+        let (right, right_extra) = self.build_node(right_entries, branch_pos + 1)?;
+        let extra = (self.aug_merger)(&left_extra, &right_extra)?;
+
+        builder.store_child(left)?;
+        builder.store_child(right)?;
+        (self.aug_writer)(&mut builder, &extra)?;
+        Ok((builder.build()?, extra))
+    }
+}
@@ -0,0 +1,67 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use super::label::{bits_to_uint, load_label};
+use crate::cell::dict::{AugDictData, KeyReader, ValReader};
+use crate::cell::{CellParser, TonCellError};
+
+/// Parser of `HashmapAug n X Y`, extra values of forks are skipped
+pub(crate) struct AugDictParser<K, V, A> {
+    key_len_bits: usize,
+    key_reader: KeyReader<K>,
+    val_reader: ValReader<V>,
+    aug_reader: ValReader<A>,
+}
+
+impl<K: Eq + Hash, V, A> AugDictParser<K, V, A> {
+    pub(crate) fn new(
+        key_len_bits: usize,
+        key_reader: KeyReader<K>,
+        val_reader: ValReader<V>,
+        aug_reader: ValReader<A>,
+    ) -> AugDictParser<K, V, A> {
+        AugDictParser {
+            key_len_bits,
+            key_reader,
+            val_reader,
+            aug_reader,
+        }
+    }
+
+    pub(crate) fn parse(
+        &self,
+        parser: &mut CellParser,
+    ) -> Result<AugDictData<K, V, A>, TonCellError> {
+        let mut result = HashMap::new();
+        self.parse_impl(parser, &mut vec![], &mut result)?;
+        Ok(result)
+    }
+
+    fn parse_impl(
+        &self,
+        parser: &mut CellParser,
+        path: &mut Vec<bool>,
+        dst: &mut AugDictData<K, V, A>,
+    ) -> Result<(), TonCellError> {
+        // will rollback path to original length at the end of the function
+        let origin_path_len = path.len();
+
+        let label = load_label(parser, self.key_len_bits - path.len())?;
+        path.extend(label);
+        if path.len() == self.key_len_bits {
+            let extra = (self.aug_reader)(parser)?;
+            let value = (self.val_reader)(parser)?;
+            let key = (self.key_reader)(&bits_to_uint(path))?;
+            dst.insert(key, (value, extra));
+        } else {
+            for bit in [false, true] {
+                let child = parser.next_reference()?;
+                path.push(bit);
+                self.parse_impl(&mut child.parser(), path, dst)?;
+                path.pop();
+            }
+        }
+        path.truncate(origin_path_len);
+        Ok(())
+    }
+}
@@ -0,0 +1,171 @@
+use num_bigint::BigUint;
+use num_traits::Zero;
+
+use crate::cell::TonCellError::{InvalidCellData, InvalidInput};
+use crate::cell::{Cell, CellBuilder, CellParser, TonCellError};
+
+/// Number of bits used to store label length `#<= max_len`
+pub(super) fn label_len_bits(max_len: usize) -> usize {
+    (usize::BITS - max_len.leading_zeros()) as usize
+}
+
+/// Reads `HmLabel ~n max_len`
+pub(super) fn load_label(
+    parser: &mut CellParser,
+    max_len: usize,
+) -> Result<Vec<bool>, TonCellError> {
+    let len_bits = label_len_bits(max_len);
+    let label = if !parser.load_bit()? {
+        let len = parser.load_unary_length()?;
+        load_bits(parser, len.min(max_len + 1))?
+    } else if !parser.load_bit()? {
+        let len = parser.load_u32(len_bits)? as usize;
+        load_bits(parser, len.min(max_len + 1))?
+    } else {
+        let bit = parser.load_bit()?;
+        let len = parser.load_u32(len_bits)? as usize;
+        vec![bit; len.min(max_len + 1)]
+    };
+    if label.len() > max_len {
+        return Err(InvalidCellData(format!(
+            "Dict label is longer than remaining key length {max_len}"
+        )));
+    }
+    Ok(label)
+}
+
+/// Writes `HmLabel ~n max_len` choosing the same label type as `DictBuilder`
+pub(super) fn store_label(
+    builder: &mut CellBuilder,
+    label: &[bool],
+    max_len: usize,
+) -> Result<(), TonCellError> {
+    let len_bits = label_len_bits(max_len);
+    let len = label.len();
+    let short_label_len = 2 + len * 2;
+    let long_label_len = 2 + len_bits + len;
+    let same_label_len = if len > 0 && label.iter().all(|&bit| bit == label[0]) {
+        3 + len_bits
+    } else {
+        usize::MAX
+    };
+
+    if same_label_len < short_label_len {
+        builder.store_bit(true)?;
+        builder.store_bit(true)?;
+        builder.store_bit(label[0])?;
+        builder.store_u32(len_bits, len as u32)?;
+    } else if long_label_len < short_label_len {
+        builder.store_bit(true)?;
+        builder.store_bit(false)?;
+        builder.store_u32(len_bits, len as u32)?;
+        store_bits(builder, label)?;
+    } else {
+        builder.store_bit(false)?;
+        for _ in 0..len {
+            builder.store_bit(true)?;
+        }
+        builder.store_bit(false)?;
+        store_bits(builder, label)?;
+    }
+    Ok(())
+}
+
+/// Rebuilds `node` with label `prefix ++ label[skip..]`, keeping the rest of the node
+pub(super) fn relabel(
+    node: &Cell,
+    max_len: usize,
+    prefix: &[bool],
+    skip: usize,
+    new_max_len: usize,
+) -> Result<Cell, TonCellError> {
+    let mut parser = node.parser();
+    let label = load_label(&mut parser, max_len)?;
+    let mut new_label = prefix.to_vec();
+    new_label.extend_from_slice(&label[skip..]);
+
+    let mut builder = CellBuilder::new();
+    store_label(&mut builder, &new_label, new_max_len)?;
+    builder.store_remaining_bits(&mut parser)?;
+    while parser.remaining_refs() > 0 {
+        builder.store_reference(&parser.next_reference()?)?;
+    }
+    builder.build()
+}
+
+pub(super) fn common_prefix_len(a: &[bool], b: &[bool]) -> usize {
+    a.iter().zip(b).take_while(|(a, b)| a == b).count()
+}
+
+pub(super) fn uint_to_bits(val: &BigUint, bit_len: usize) -> Result<Vec<bool>, TonCellError> {
+    if val.bits() as usize > bit_len {
+        let msg = format!(
+            "Invalid key length: Expected max_len={bit_len}, got len={}",
+            val.bits()
+        );
+        return Err(InvalidInput(msg));
+    }
+    Ok((0..bit_len as u64).rev().map(|i| val.bit(i)).collect())
+}
+
+pub(super) fn bits_to_uint(bits: &[bool]) -> BigUint {
+    bits.iter().fold(BigUint::zero(), |acc, &bit| {
+        (acc << 1u32) | BigUint::from(bit as u8)
+    })
+}
+
+fn load_bits(parser: &mut CellParser, len: usize) -> Result<Vec<bool>, TonCellError> {
+    (0..len).map(|_| parser.load_bit()).collect()
+}
+
+fn store_bits(builder: &mut CellBuilder, bits: &[bool]) -> Result<(), TonCellError> {
+    for &bit in bits {
+        builder.store_bit(bit)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_label_roundtrip() -> Result<(), TonCellError> {
+        let labels = [
+            vec![],
+            vec![true],
+            vec![false; 7],
+            vec![true, false, true, true, false, false, true, false, true],
+        ];
+        for label in labels {
+            for max_len in [label.len(), 32, 267] {
+                let mut builder = CellBuilder::new();
+                store_label(&mut builder, &label, max_len)?;
+                let cell = builder.build()?;
+                let mut parser = cell.parser();
+                assert_eq!(load_label(&mut parser, max_len)?, label);
+                parser.ensure_empty()?;
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_label_len_bits() {
+        assert_eq!(label_len_bits(0), 0);
+        assert_eq!(label_len_bits(1), 1);
+        assert_eq!(label_len_bits(8), 4);
+        assert_eq!(label_len_bits(255), 8);
+        assert_eq!(label_len_bits(256), 9);
+    }
+
+    #[test]
+    fn test_bits_uint() -> Result<(), TonCellError> {
+        let val = BigUint::from(0b1011u32);
+        let bits = uint_to_bits(&val, 6)?;
+        assert_eq!(bits, [false, false, true, false, true, true]);
+        assert_eq!(bits_to_uint(&bits), val);
+        assert!(uint_to_bits(&val, 3).is_err());
+        Ok(())
+    }
+}
@@ -5,12 +5,14 @@ use std::ops::Deref;
 use num_bigint::BigUint;
 use tokio_test::assert_ok;
 
+use super::label::store_label;
 use crate::cell::dict::predefined_readers::{
     key_reader_256bit, key_reader_u16, key_reader_u32, key_reader_u64, key_reader_u8,
     key_reader_uint, val_reader_ref_cell, val_reader_uint,
 };
 use crate::cell::dict::predefined_writers::{val_writer_ref_cell, val_writer_unsigned_min_size};
-use crate::cell::{ArcCell, BagOfCells, Cell, CellBuilder};
+use crate::cell::dict::VarDictKey;
+use crate::cell::{ArcCell, BagOfCells, Cell, CellBuilder, CellParser, TonCellError};
 use crate::TonHash;
 
 #[test]
@@ -231,3 +233,184 @@ fn test_reader_cell() -> anyhow::Result<()> {
     assert_eq!(data, parsed);
     Ok(())
 }
+
+fn aug_reader_u64(parser: &mut CellParser) -> Result<u64, TonCellError> {
+    parser.load_u64(64)
+}
+
+fn aug_writer_u64(builder: &mut CellBuilder, extra: &u64) -> Result<(), TonCellError> {
+    builder.store_u64(64, *extra)?;
+    Ok(())
+}
+
+fn aug_merger_sum(left: &u64, right: &u64) -> Result<u64, TonCellError> {
+    Ok(left + right)
+}
+
+#[test]
+fn test_dict_aug() -> anyhow::Result<()> {
+    let data = HashMap::from([
+        (0u16, (BigUint::from(4u32), 40u64)),
+        (1, (BigUint::from(5u32), 50)),
+        (300, (BigUint::from(6u32), 60)),
+        (65535, (BigUint::from(7u32), 70)),
+    ]);
+    let mut builder = CellBuilder::new();
+    builder.store_dict_aug(
+        16,
+        val_writer_unsigned_min_size,
+        aug_writer_u64,
+        aug_merger_sum,
+        data.clone(),
+    )?;
+    let dict_cell = builder.build()?;
+
+    let (parsed, extra) =
+        dict_cell
+            .parser()
+            .load_dict_aug(16, key_reader_u16, val_reader_uint, aug_reader_u64)?;
+    assert_eq!(parsed, data);
+    assert_eq!(extra, 220);
+
+    // extra of the root fork is written after references to children
+    let root = dict_cell.reference(0)?;
+    let mut parser = root.parser();
+    parser.skip_bits(root.bit_len() - 64)?;
+    assert_eq!(parser.load_u64(64)?, 220);
+    Ok(())
+}
+
+#[test]
+fn test_dict_aug_empty() -> anyhow::Result<()> {
+    let mut builder = CellBuilder::new();
+    builder.store_dict_aug(
+        16,
+        val_writer_unsigned_min_size,
+        aug_writer_u64,
+        aug_merger_sum,
+        HashMap::<u16, (BigUint, u64)>::new(),
+    )?;
+    let dict_cell = builder.build()?;
+    assert_eq!(dict_cell.bit_len(), 65);
+
+    let (parsed, extra) =
+        dict_cell
+            .parser()
+            .load_dict_aug(16, key_reader_u16, val_reader_uint, aug_reader_u64)?;
+    assert!(parsed.is_empty());
+    assert_eq!(extra, 0);
+    Ok(())
+}
+
+#[test]
+fn test_dict_aug_without_extra_matches_plain_dict() -> anyhow::Result<()> {
+    let data = HashMap::from([
+        (0u8, BigUint::from(4u32)),
+        (1, BigUint::from(5u32)),
+        (2, BigUint::from(6u32)),
+        (64, BigUint::from(7u32)),
+    ]);
+    let aug_data = data
+        .iter()
+        .map(|(k, v)| (*k, (v.clone(), ())))
+        .collect::<HashMap<_, _>>();
+    let aug_writer_unit = |_: &mut CellBuilder, _: &()| Ok(());
+    let aug_merger_unit = |_: &(), _: &()| Ok(());
+
+    let mut builder = CellBuilder::new();
+    builder.store_dict_aug_data(
+        8,
+        val_writer_unsigned_min_size,
+        aug_writer_unit,
+        aug_merger_unit,
+        aug_data,
+    )?;
+    let aug_cell = builder.build()?;
+
+    let mut builder = CellBuilder::new();
+    builder.store_dict_data(8, val_writer_unsigned_min_size, data)?;
+    assert_eq!(aug_cell, builder.build()?);
+    Ok(())
+}
+
+#[test]
+fn test_pfx_dict() -> anyhow::Result<()> {
+    let mut left = CellBuilder::new();
+    store_label(&mut left, &[true, false], 7)?;
+    left.store_bit(false)?.store_u8(8, 10)?;
+    let mut right = CellBuilder::new();
+    store_label(&mut right, &[true; 3], 7)?;
+    right.store_bit(false)?.store_u8(8, 20)?;
+
+    let mut root = CellBuilder::new();
+    store_label(&mut root, &[], 8)?;
+    root.store_bit(true)?
+        .store_child(left.build()?)?
+        .store_child(right.build()?)?;
+    let dict_cell = CellBuilder::new()
+        .store_bit(true)?
+        .store_child(root.build()?)?
+        .build()?;
+
+    let parsed = dict_cell.parser().load_pfx_dict(8, |p| p.load_u8(8))?;
+    let expected = HashMap::from([
+        (
+            VarDictKey {
+                bit_len: 3,
+                value: BigUint::from(0b010u32),
+            },
+            10,
+        ),
+        (
+            VarDictKey {
+                bit_len: 4,
+                value: BigUint::from(0b1111u32),
+            },
+            20,
+        ),
+    ]);
+    assert_eq!(parsed, expected);
+    Ok(())
+}
+
+#[test]
+fn test_var_dict() -> anyhow::Result<()> {
+    let mut left = CellBuilder::new();
+    store_label(&mut left, &[true], 5)?;
+    left.store_u8(2, 0b00)?.store_u8(8, 7)?;
+    let mut right = CellBuilder::new();
+    store_label(&mut right, &[], 5)?;
+    right.store_u8(2, 0b00)?.store_u8(8, 8)?;
+
+    let mut fork = CellBuilder::new();
+    store_label(&mut fork, &[], 6)?;
+    fork.store_u8(2, 0b01)?
+        .store_child(left.build()?)?
+        .store_child(right.build()?)?
+        .store_bit(true)?
+        .store_u8(8, 6)?;
+
+    let mut root = CellBuilder::new();
+    store_label(&mut root, &[true], 8)?;
+    root.store_bit(true)?
+        .store_bit(false)?
+        .store_child(fork.build()?)?
+        .store_u8(8, 5)?;
+
+    let parsed = root
+        .build()?
+        .parser()
+        .load_var_dict_data(8, |p| p.load_u8(8))?;
+    let key = |bit_len: usize, value: u32| VarDictKey {
+        bit_len,
+        value: BigUint::from(value),
+    };
+    let expected = HashMap::from([
+        (key(1, 0b1), 5),
+        (key(2, 0b10), 6),
+        (key(4, 0b1001), 7),
+        (key(3, 0b101), 8),
+    ]);
+    assert_eq!(parsed, expected);
+    Ok(())
+}
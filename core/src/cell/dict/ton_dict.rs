@@ -0,0 +1,608 @@
+use num_bigint::BigUint;
+
+use super::label::{
+    bits_to_uint, common_prefix_len, load_label, relabel, store_label, uint_to_bits,
+};
+use crate::cell::dict::{KeyReader, ValReader, ValWriter};
+use crate::cell::TonCellError::InvalidInput;
+use crate::cell::{ArcCell, Cell, CellBuilder, CellParser, TonCellError};
+
+/// `HashmapE n X` kept in its serialized form.
+///
+/// Unlike `CellParser::load_dict` it doesn't decode the whole dictionary:
+/// `get`, `set` and `remove` walk a single path of labels, and modifications rebuild
+/// only the cells on that path while the rest of the tree is shared with the original.
+///
+/// Keys are ordered as unsigned big-endian bit strings.
+pub struct TonDict<K, V> {
+    key_len_bits: usize,
+    root: Option<ArcCell>,
+    key_reader: KeyReader<K>,
+    val_reader: ValReader<V>,
+    val_writer: ValWriter<V>,
+}
+
+impl<K, V> TonDict<K, V>
+where
+    K: Clone,
+    BigUint: From<K>,
+{
+    pub fn new(
+        key_len_bits: usize,
+        key_reader: KeyReader<K>,
+        val_reader: ValReader<V>,
+        val_writer: ValWriter<V>,
+    ) -> TonDict<K, V> {
+        Self::with_root(key_len_bits, None, key_reader, val_reader, val_writer)
+    }
+
+    /// Wraps `root` cell of `Hashmap n X`, `None` stands for empty dictionary
+    pub fn with_root(
+        key_len_bits: usize,
+        root: Option<ArcCell>,
+        key_reader: KeyReader<K>,
+        val_reader: ValReader<V>,
+        val_writer: ValWriter<V>,
+    ) -> TonDict<K, V> {
+        TonDict {
+            key_len_bits,
+            root,
+            key_reader,
+            val_reader,
+            val_writer,
+        }
+    }
+
+    /// Reads `HashmapE n X`
+    pub fn load(
+        parser: &mut CellParser,
+        key_len_bits: usize,
+        key_reader: KeyReader<K>,
+        val_reader: ValReader<V>,
+        val_writer: ValWriter<V>,
+    ) -> Result<TonDict<K, V>, TonCellError> {
+        let root = parser.load_maybe_cell_ref()?;
+        Ok(Self::with_root(
+            key_len_bits,
+            root,
+            key_reader,
+            val_reader,
+            val_writer,
+        ))
+    }
+
+    /// Writes `HashmapE n X`
+    pub fn store(&self, builder: &mut CellBuilder) -> Result<(), TonCellError> {
+        builder.store_ref_cell_optional(self.root.as_ref())?;
+        Ok(())
+    }
+
+    pub fn root(&self) -> Option<&ArcCell> {
+        self.root.as_ref()
+    }
+
+    pub fn key_len_bits(&self) -> usize {
+        self.key_len_bits
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    pub fn get(&self, key: &K) -> Result<Option<V>, TonCellError> {
+        let key = self.key_bits(key)?;
+        let mut node = match &self.root {
+            Some(root) => root.clone(),
+            None => return Ok(None),
+        };
+        let mut key = key.as_slice();
+        loop {
+            let mut parser = node.parser();
+            let label = load_label(&mut parser, key.len())?;
+            if !key.starts_with(&label) {
+                return Ok(None);
+            }
+            if label.len() == key.len() {
+                return (self.val_reader)(&mut parser).map(Some);
+            }
+            let bit = key[label.len()];
+            if bit {
+                parser.next_reference()?;
+            }
+            let next = parser.next_reference()?;
+            key = &key[label.len() + 1..];
+            node = next;
+        }
+    }
+
+    pub fn contains_key(&self, key: &K) -> Result<bool, TonCellError> {
+        Ok(self.get(key)?.is_some())
+    }
+
+    pub fn set(&mut self, key: K, value: V) -> Result<(), TonCellError> {
+        let key = self.key_bits(&key)?;
+        let root = self.insert(self.root.as_ref(), &key, value)?;
+        self.root = Some(root.to_arc());
+        Ok(())
+    }
+
+    /// Removes `key` and returns its value
+    pub fn remove(&mut self, key: &K) -> Result<Option<V>, TonCellError> {
+        let key = self.key_bits(key)?;
+        let root = match &self.root {
+            Some(root) => root,
+            None => return Ok(None),
+        };
+        match self.remove_impl(root, &key)? {
+            Some((root, value)) => {
+                self.root = root.map(Cell::to_arc);
+                Ok(Some(value))
+            }
+            None => Ok(None),
+        }
+    }
+
+    pub fn min(&self) -> Result<Option<(K, V)>, TonCellError> {
+        match &self.root {
+            Some(root) => self.find_edge(root, vec![], false).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    pub fn max(&self) -> Result<Option<(K, V)>, TonCellError> {
+        match &self.root {
+            Some(root) => self.find_edge(root, vec![], true).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns entry with the smallest key greater than `key`
+    pub fn get_next(&self, key: &K) -> Result<Option<(K, V)>, TonCellError> {
+        self.find_nearest(key, false)
+    }
+
+    /// Returns entry with the greatest key less than `key`
+    pub fn get_prev(&self, key: &K) -> Result<Option<(K, V)>, TonCellError> {
+        self.find_nearest(key, true)
+    }
+
+    /// Returns dictionary of entries whose keys start with `prefix_len` bits of `prefix`
+    pub fn prefix_subdict(
+        &self,
+        prefix: &BigUint,
+        prefix_len: usize,
+    ) -> Result<TonDict<K, V>, TonCellError> {
+        if prefix_len > self.key_len_bits {
+            return Err(InvalidInput(format!(
+                "Prefix length {prefix_len} exceeds key length {}",
+                self.key_len_bits
+            )));
+        }
+        let prefix = uint_to_bits(prefix, prefix_len)?;
+        let root = match &self.root {
+            Some(root) => self.find_subdict_root(root, &prefix)?,
+            None => None,
+        };
+        Ok(Self::with_root(
+            self.key_len_bits,
+            root,
+            self.key_reader,
+            self.val_reader,
+            self.val_writer,
+        ))
+    }
+
+    /// Iterates over entries in ascending key order
+    pub fn iter(&self) -> TonDictIter<'_, K, V> {
+        TonDictIter {
+            dict: self,
+            stack: self
+                .root
+                .iter()
+                .map(|root| (root.clone(), vec![]))
+                .collect(),
+        }
+    }
+
+    fn key_bits(&self, key: &K) -> Result<Vec<bool>, TonCellError> {
+        uint_to_bits(&BigUint::from(key.clone()), self.key_len_bits)
+    }
+
+    fn read_entry(&self, parser: &mut CellParser, key: &[bool]) -> Result<(K, V), TonCellError> {
+        let key = (self.key_reader)(&bits_to_uint(key))?;
+        let value = (self.val_reader)(parser)?;
+        Ok((key, value))
+    }
+
+    fn leaf(&self, label: &[bool], value: V) -> Result<Cell, TonCellError> {
+        let mut builder = CellBuilder::new();
+        store_label(&mut builder, label, label.len())?;
+        (self.val_writer)(&mut builder, value)?;
+        builder.build()
+    }
+
+    fn insert(&self, node: Option<&ArcCell>, key: &[bool], value: V) -> Result<Cell, TonCellError> {
+        let node = match node {
+            Some(node) => node,
+            None => return self.leaf(key, value),
+        };
+        let max_len = key.len();
+        let mut parser = node.parser();
+        let label = load_label(&mut parser, max_len)?;
+        let common = common_prefix_len(&label, key);
+        if common == label.len() {
+            if common == max_len {
+                return self.leaf(key, value);
+            }
+            let bit = key[common] as usize;
+            let mut children = [parser.next_reference()?, parser.next_reference()?];
+            children[bit] = self
+                .insert(Some(&children[bit]), &key[common + 1..], value)?
+                .to_arc();
+            return build_fork(&label, max_len, &children);
+        }
+
+        // labels diverge: split node into fork with old node and new leaf
+        let child_max_len = max_len - common - 1;
+        let old = relabel(node, max_len, &[], common + 1, child_max_len)?.to_arc();
+        let new = self.leaf(&key[common + 1..], value)?.to_arc();
+        let children = if key[common] { [old, new] } else { [new, old] };
+        build_fork(&label[..common], max_len, &children)
+    }
+
+    /// Returns `None` if there is no `key`, otherwise new node (if any) and removed value
+    #[allow(clippy::type_complexity)]
+    fn remove_impl(
+        &self,
+        node: &Cell,
+        key: &[bool],
+    ) -> Result<Option<(Option<Cell>, V)>, TonCellError> {
+        let max_len = key.len();
+        let mut parser = node.parser();
+        let label = load_label(&mut parser, max_len)?;
+        if !key.starts_with(&label) {
+            return Ok(None);
+        }
+        if label.len() == max_len {
+            let value = (self.val_reader)(&mut parser)?;
+            return Ok(Some((None, value)));
+        }
+        let bit = key[label.len()] as usize;
+        let mut children = [parser.next_reference()?, parser.next_reference()?];
+        let (child, value) = match self.remove_impl(&children[bit], &key[label.len() + 1..])? {
+            Some(removed) => removed,
+            None => return Ok(None),
+        };
+        let node = match child {
+            Some(child) => {
+                children[bit] = child.to_arc();
+                build_fork(&label, max_len, &children)?
+            }
+            None => {
+                // fork with a single child is merged into it
+                let mut prefix = label.clone();
+                prefix.push(bit == 0);
+                let child_max_len = max_len - label.len() - 1;
+                relabel(&children[1 - bit], child_max_len, &prefix, 0, max_len)?
+            }
+        };
+        Ok(Some((Some(node), value)))
+    }
+
+    fn find_edge(
+        &self,
+        node: &ArcCell,
+        mut path: Vec<bool>,
+        rightmost: bool,
+    ) -> Result<(K, V), TonCellError> {
+        let mut node = node.clone();
+        loop {
+            let mut parser = node.parser();
+            let label = load_label(&mut parser, self.key_len_bits - path.len())?;
+            path.extend(label);
+            if path.len() == self.key_len_bits {
+                return self.read_entry(&mut parser, &path);
+            }
+            if rightmost {
+                parser.next_reference()?;
+            }
+            let next = parser.next_reference()?;
+            path.push(rightmost);
+            node = next;
+        }
+    }
+
+    fn find_nearest(&self, key: &K, backwards: bool) -> Result<Option<(K, V)>, TonCellError> {
+        let key = self.key_bits(key)?;
+        match &self.root {
+            Some(root) => self.find_nearest_impl(root, &key, vec![], backwards),
+            None => Ok(None),
+        }
+    }
+
+    fn find_nearest_impl(
+        &self,
+        node: &ArcCell,
+        key: &[bool],
+        mut path: Vec<bool>,
+        backwards: bool,
+    ) -> Result<Option<(K, V)>, TonCellError> {
+        let max_len = key.len();
+        let mut parser = node.parser();
+        let label = load_label(&mut parser, max_len)?;
+        if let Some(pos) = (0..label.len()).find(|&i| key[i] != label[i]) {
+            // the whole subtree is either before or after the key
+            return if label[pos] != backwards {
+                self.find_edge(node, path, backwards).map(Some)
+            } else {
+                Ok(None)
+            };
+        }
+        if label.len() == max_len {
+            return Ok(None);
+        }
+
+        let bit = key[label.len()];
+        let children = [parser.next_reference()?, parser.next_reference()?];
+        path.extend(label.iter());
+        let mut child_path = path.clone();
+        child_path.push(bit);
+        let rest = &key[label.len() + 1..];
+        if let Some(found) =
+            self.find_nearest_impl(&children[bit as usize], rest, child_path, backwards)?
+        {
+            return Ok(Some(found));
+        }
+        if bit == backwards {
+            path.push(!bit);
+            return self
+                .find_edge(&children[!bit as usize], path, backwards)
+                .map(Some);
+        }
+        Ok(None)
+    }
+
+    fn find_subdict_root(
+        &self,
+        root: &ArcCell,
+        prefix: &[bool],
+    ) -> Result<Option<ArcCell>, TonCellError> {
+        let mut node = root.clone();
+        let mut path = vec![];
+        loop {
+            let max_len = self.key_len_bits - path.len();
+            let mut parser = node.parser();
+            let label = load_label(&mut parser, max_len)?;
+            let rest = &prefix[path.len()..];
+            if rest.len() <= label.len() {
+                if !label.starts_with(rest) {
+                    return Ok(None);
+                }
+                if path.is_empty() {
+                    return Ok(Some(node));
+                }
+                let root = relabel(&node, max_len, &path, 0, self.key_len_bits)?;
+                return Ok(Some(root.to_arc()));
+            }
+            if !rest.starts_with(&label) {
+                return Ok(None);
+            }
+            let bit = rest[label.len()];
+            if bit {
+                parser.next_reference()?;
+            }
+            let next = parser.next_reference()?;
+            path.extend(label);
+            path.push(bit);
+            node = next;
+        }
+    }
+}
+
+impl<K, V> Clone for TonDict<K, V> {
+    fn clone(&self) -> Self {
+        TonDict {
+            key_len_bits: self.key_len_bits,
+            root: self.root.clone(),
+            key_reader: self.key_reader,
+            val_reader: self.val_reader,
+            val_writer: self.val_writer,
+        }
+    }
+}
+
+impl<'a, K, V> IntoIterator for &'a TonDict<K, V>
+where
+    K: Clone,
+    BigUint: From<K>,
+{
+    type Item = Result<(K, V), TonCellError>;
+    type IntoIter = TonDictIter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Iterator over [`TonDict`] entries, decodes values lazily
+pub struct TonDictIter<'a, K, V> {
+    dict: &'a TonDict<K, V>,
+    stack: Vec<(ArcCell, Vec<bool>)>,
+}
+
+impl<K, V> Iterator for TonDictIter<'_, K, V>
+where
+    K: Clone,
+    BigUint: From<K>,
+{
+    type Item = Result<(K, V), TonCellError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((node, mut path)) = self.stack.pop() {
+            let result = (|| {
+                let mut parser = node.parser();
+                let label = load_label(&mut parser, self.dict.key_len_bits - path.len())?;
+                path.extend(label);
+                if path.len() == self.dict.key_len_bits {
+                    return self.dict.read_entry(&mut parser, &path).map(Some);
+                }
+                let left = parser.next_reference()?;
+                let right = parser.next_reference()?;
+                let mut right_path = path.clone();
+                right_path.push(true);
+                path.push(false);
+                self.stack.push((right, right_path));
+                self.stack.push((left, path));
+                Ok(None)
+            })();
+            match result {
+                Ok(Some(entry)) => return Some(Ok(entry)),
+                Ok(None) => continue,
+                Err(error) => {
+                    self.stack.clear();
+                    return Some(Err(error));
+                }
+            }
+        }
+        None
+    }
+}
+
+fn build_fork(
+    label: &[bool],
+    max_len: usize,
+    children: &[ArcCell; 2],
+) -> Result<Cell, TonCellError> {
+    let mut builder = CellBuilder::new();
+    store_label(&mut builder, label, max_len)?;
+    builder.store_references(children)?;
+    builder.build()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::cell::dict::predefined_readers::{key_reader_u16, val_reader_uint};
+    use crate::cell::dict::predefined_writers::val_writer_unsigned_min_size;
+
+    fn build_dict_cell(data: &HashMap<u16, BigUint>) -> Result<Cell, TonCellError> {
+        let mut builder = CellBuilder::new();
+        builder.store_dict(16, val_writer_unsigned_min_size, data.clone())?;
+        builder.build()
+    }
+
+    fn load_ton_dict(cell: &Cell) -> Result<TonDict<u16, BigUint>, TonCellError> {
+        TonDict::load(
+            &mut cell.parser(),
+            16,
+            key_reader_u16,
+            val_reader_uint,
+            val_writer_unsigned_min_size,
+        )
+    }
+
+    fn store_ton_dict(dict: &TonDict<u16, BigUint>) -> Result<Cell, TonCellError> {
+        let mut builder = CellBuilder::new();
+        dict.store(&mut builder)?;
+        builder.build()
+    }
+
+    fn test_data() -> HashMap<u16, BigUint> {
+        [0u16, 1, 2, 7, 100, 255, 256, 1000, 40000, 65535]
+            .into_iter()
+            .map(|k| (k, BigUint::from(k as u32 * 3 + 1)))
+            .collect()
+    }
+
+    #[test]
+    fn test_ton_dict_get() -> Result<(), TonCellError> {
+        let data = test_data();
+        let dict = load_ton_dict(&build_dict_cell(&data)?)?;
+        for (key, value) in &data {
+            assert_eq!(dict.get(key)?.as_ref(), Some(value));
+        }
+        assert_eq!(dict.get(&3)?, None);
+        assert_eq!(dict.get(&65534)?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_ton_dict_set_remove() -> Result<(), TonCellError> {
+        let mut data = test_data();
+        let mut dict = load_ton_dict(&build_dict_cell(&data)?)?;
+
+        for key in [3u16, 254, 1000, 50000] {
+            let value = BigUint::from(key as u32 + 5);
+            dict.set(key, value.clone())?;
+            data.insert(key, value);
+            assert_eq!(store_ton_dict(&dict)?, build_dict_cell(&data)?);
+        }
+        for key in [0u16, 1000, 65535, 256, 42] {
+            let removed = dict.remove(&key)?;
+            assert_eq!(removed, data.remove(&key));
+            assert_eq!(store_ton_dict(&dict)?, build_dict_cell(&data)?);
+        }
+        for key in data.keys().copied().collect::<Vec<_>>() {
+            dict.remove(&key)?;
+        }
+        assert!(dict.is_empty());
+
+        let mut dict = TonDict::new(
+            16,
+            key_reader_u16,
+            val_reader_uint,
+            val_writer_unsigned_min_size,
+        );
+        dict.set(7, BigUint::from(1u32))?;
+        assert_eq!(dict.get(&7)?, Some(BigUint::from(1u32)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_ton_dict_order() -> Result<(), TonCellError> {
+        let data = test_data();
+        let dict = load_ton_dict(&build_dict_cell(&data)?)?;
+        let mut expected: Vec<_> = data.into_iter().collect();
+        expected.sort();
+        let entries = dict.iter().collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(entries, expected);
+
+        assert_eq!(dict.min()?.map(|e| e.0), Some(0));
+        assert_eq!(dict.max()?.map(|e| e.0), Some(65535));
+        assert_eq!(dict.get_next(&2)?.map(|e| e.0), Some(7));
+        assert_eq!(dict.get_next(&3)?.map(|e| e.0), Some(7));
+        assert_eq!(dict.get_next(&65535)?, None);
+        assert_eq!(dict.get_prev(&7)?.map(|e| e.0), Some(2));
+        assert_eq!(dict.get_prev(&39999)?.map(|e| e.0), Some(1000));
+        assert_eq!(dict.get_prev(&0)?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_ton_dict_prefix_subdict() -> Result<(), TonCellError> {
+        let data = test_data();
+        let dict = load_ton_dict(&build_dict_cell(&data)?)?;
+
+        let low = dict.prefix_subdict(&BigUint::from(0u32), 8)?;
+        let keys = low
+            .iter()
+            .map(|e| e.map(|e| e.0))
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(keys, [0, 1, 2, 7, 100, 255]);
+        assert_eq!(low.get(&255)?, data.get(&255).cloned());
+        assert_eq!(low.get(&256)?, None);
+
+        let high = dict.prefix_subdict(&BigUint::from(1u32), 1)?;
+        let keys = high
+            .iter()
+            .map(|e| e.map(|e| e.0))
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(keys, [40000, 65535]);
+
+        assert!(dict.prefix_subdict(&BigUint::from(3u32), 16)?.is_empty());
+        assert!(dict.prefix_subdict(&BigUint::from(0u32), 17).is_err());
+        Ok(())
+    }
+}
@@ -16,3 +16,16 @@ pub type SnakeFormatDict = HashMap<TonHash, Vec<u8>>;
 pub type KeyReader<K> = fn(&BigUint) -> Result<K, TonCellError>;
 pub type ValReader<V> = fn(&mut CellParser) -> Result<V, TonCellError>;
 pub type ValWriter<V> = fn(&mut CellBuilder, V) -> Result<(), TonCellError>;
+/// Writes extra value of `HashmapAug` node, it's written to both leaves and forks
+pub type AugWriter<A> = fn(&mut CellBuilder, &A) -> Result<(), TonCellError>;
+/// Calculates extra value of `HashmapAug` fork from extra values of its children
+pub type AugMerger<A> = fn(&A, &A) -> Result<A, TonCellError>;
+/// Entries of `HashmapAug n X Y`: key => (value, extra)
+pub type AugDictData<K, V, A> = HashMap<K, (V, A)>;
+
+/// Key of `PfxHashmap` and `VarHashmap`, it may be shorter than dictionary key length
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct VarDictKey {
+    pub bit_len: usize,
+    pub value: BigUint,
+}
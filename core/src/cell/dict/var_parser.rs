@@ -0,0 +1,131 @@
+use std::collections::HashMap;
+
+use super::label::{bits_to_uint, load_label};
+use crate::cell::dict::{ValReader, VarDictKey};
+use crate::cell::{Cell, CellParser, TonCellError};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum VarDictKind {
+    /// `PfxHashmap n X`, values are stored in leaves only
+    Pfx,
+    /// `VarHashmap n X`, values may be stored in forks too
+    Var,
+}
+
+/// Parser of dictionaries with keys of variable length
+pub(crate) struct VarDictParser<V> {
+    kind: VarDictKind,
+    key_len_bits: usize,
+    val_reader: ValReader<V>,
+}
+
+impl<V> VarDictParser<V> {
+    pub(crate) fn new(
+        kind: VarDictKind,
+        key_len_bits: usize,
+        val_reader: ValReader<V>,
+    ) -> VarDictParser<V> {
+        VarDictParser {
+            kind,
+            key_len_bits,
+            val_reader,
+        }
+    }
+
+    pub(crate) fn parse(
+        &self,
+        parser: &mut CellParser,
+    ) -> Result<HashMap<VarDictKey, V>, TonCellError> {
+        let mut result = HashMap::new();
+        self.parse_impl(parser, &mut vec![], &mut result)?;
+        Ok(result)
+    }
+
+    fn parse_impl(
+        &self,
+        parser: &mut CellParser,
+        path: &mut Vec<bool>,
+        dst: &mut HashMap<VarDictKey, V>,
+    ) -> Result<(), TonCellError> {
+        // will rollback path to original length at the end of the function
+        let origin_path_len = path.len();
+
+        let label = load_label(parser, self.key_len_bits - path.len())?;
+        path.extend(label);
+        match self.kind {
+            VarDictKind::Pfx => {
+                if parser.load_bit()? {
+                    self.parse_fork(parser, path, dst)?;
+                } else {
+                    self.parse_value(parser, path, dst)?;
+                }
+            }
+            VarDictKind::Var => {
+                if parser.load_bit()? {
+                    // vhmn_cont$1
+                    let branch = parser.load_bit()?;
+                    let child = parser.next_reference()?;
+                    self.parse_value(parser, path, dst)?;
+                    self.parse_child(&child, branch, path, dst)?;
+                } else if parser.load_bit()? {
+                    // vhmn_fork$01
+                    self.parse_fork(parser, path, dst)?;
+                    if parser.load_bit()? {
+                        self.parse_value(parser, path, dst)?;
+                    }
+                } else {
+                    // vhmn_leaf$00
+                    self.parse_value(parser, path, dst)?;
+                }
+            }
+        }
+        path.truncate(origin_path_len);
+        Ok(())
+    }
+
+    fn parse_fork(
+        &self,
+        parser: &mut CellParser,
+        path: &mut Vec<bool>,
+        dst: &mut HashMap<VarDictKey, V>,
+    ) -> Result<(), TonCellError> {
+        let left = parser.next_reference()?;
+        let right = parser.next_reference()?;
+        self.parse_child(&left, false, path, dst)?;
+        self.parse_child(&right, true, path, dst)
+    }
+
+    fn parse_child(
+        &self,
+        child: &Cell,
+        bit: bool,
+        path: &mut Vec<bool>,
+        dst: &mut HashMap<VarDictKey, V>,
+    ) -> Result<(), TonCellError> {
+        if path.len() == self.key_len_bits {
+            return Err(TonCellError::InvalidCellData(format!(
+                "Dict fork exceeds key length {}",
+                self.key_len_bits
+            )));
+        }
+        path.push(bit);
+        self.parse_impl(&mut child.parser(), path, dst)?;
+        path.pop();
+        Ok(())
+    }
+
+    fn parse_value(
+        &self,
+        parser: &mut CellParser,
+        path: &[bool],
+        dst: &mut HashMap<VarDictKey, V>,
+    ) -> Result<(), TonCellError> {
+        let key = VarDictKey {
+            bit_len: path.len(),
+            value: bits_to_uint(path),
+        };
+        let value = (self.val_reader)(parser)?;
+        dst.insert(key, value);
+        Ok(())
+    }
+}
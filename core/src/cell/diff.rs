@@ -0,0 +1,268 @@
+//! Structural comparison of cell trees.
+//!
+//! Identical subtrees are skipped by `cell_hash`, so comparing large states
+//! with a few modified cells walks only the modified paths. There's no field-level
+//! diff of TL-B objects: [`compare_tlb`] only tells whether parsed values differ.
+use std::fmt::{Display, Formatter};
+use std::ops::Range;
+
+use crate::cell::{Cell, TonCellError};
+use crate::tlb_types::tlb::TLB;
+use crate::TonHash;
+
+/// Change of a cell located at `path` from the root
+#[derive(Debug, Clone, PartialEq)]
+pub struct CellChange {
+    /// Reference indices leading from the root to the changed cell
+    pub path: Vec<usize>,
+    pub kind: CellChangeKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CellChangeKind {
+    /// Data bits differ in `ranges`, bits present in only one of cells are included
+    Data {
+        old_bit_len: usize,
+        new_bit_len: usize,
+        ranges: Vec<Range<usize>>,
+    },
+    /// Cell became exotic or ordinary
+    Exotic {
+        old: bool,
+        new: bool,
+    },
+    ReferenceAdded {
+        idx: usize,
+        hash: TonHash,
+    },
+    ReferenceRemoved {
+        idx: usize,
+        hash: TonHash,
+    },
+}
+
+/// Differing values of TL-B object `T` along with structural changes of their cells,
+/// see [`compare_tlb`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct TlbMismatch<T> {
+    pub old: T,
+    pub new: T,
+    pub changes: Vec<CellChange>,
+}
+
+/// Returns changes turning `old` tree into `new` one, it's empty for equal trees
+pub fn diff_cells(old: &Cell, new: &Cell) -> Vec<CellChange> {
+    let mut changes = vec![];
+    diff_impl(old, new, &mut vec![], &mut changes);
+    changes
+}
+
+/// Parses both cells as `T` and compares the values, returns `None` if they're equal.
+///
+/// Values are compared as a whole, changed fields aren't located: compare `old` and `new`
+/// of the result or use `changes` to find changed cells.
+pub fn compare_tlb<T: TLB + PartialEq>(
+    old: &Cell,
+    new: &Cell,
+) -> Result<Option<TlbMismatch<T>>, TonCellError> {
+    let old_value = T::from_cell(old)?;
+    let new_value = T::from_cell(new)?;
+    if old_value == new_value {
+        return Ok(None);
+    }
+    Ok(Some(TlbMismatch {
+        old: old_value,
+        new: new_value,
+        changes: diff_cells(old, new),
+    }))
+}
+
+fn diff_impl(old: &Cell, new: &Cell, path: &mut Vec<usize>, changes: &mut Vec<CellChange>) {
+    if old.cell_hash() == new.cell_hash() {
+        return;
+    }
+    let mut push = |kind| {
+        changes.push(CellChange {
+            path: path.clone(),
+            kind,
+        })
+    };
+
+    if old.is_exotic() != new.is_exotic() {
+        push(CellChangeKind::Exotic {
+            old: old.is_exotic(),
+            new: new.is_exotic(),
+        });
+    }
+    let ranges = diff_bits(old, new);
+    if !ranges.is_empty() {
+        push(CellChangeKind::Data {
+            old_bit_len: old.bit_len(),
+            new_bit_len: new.bit_len(),
+            ranges,
+        });
+    }
+
+    let ref_count = old.references().len().max(new.references().len());
+    for idx in 0..ref_count {
+        match (old.references().get(idx), new.references().get(idx)) {
+            (Some(old_ref), Some(new_ref)) => {
+                path.push(idx);
+                diff_impl(old_ref, new_ref, path, changes);
+                path.pop();
+            }
+            (Some(old_ref), None) => changes.push(CellChange {
+                path: path.clone(),
+                kind: CellChangeKind::ReferenceRemoved {
+                    idx,
+                    hash: Cell::cell_hash(old_ref),
+                },
+            }),
+            (None, Some(new_ref)) => changes.push(CellChange {
+                path: path.clone(),
+                kind: CellChangeKind::ReferenceAdded {
+                    idx,
+                    hash: Cell::cell_hash(new_ref),
+                },
+            }),
+            (None, None) => {}
+        }
+    }
+}
+
+fn diff_bits(old: &Cell, new: &Cell) -> Vec<Range<usize>> {
+    let bit = |cell: &Cell, i: usize| cell.data()[i / 8] >> (7 - i % 8) & 1 == 1;
+    let common_len = old.bit_len().min(new.bit_len());
+    let mut ranges: Vec<Range<usize>> = vec![];
+    let mut add = |i: usize| match ranges.last_mut() {
+        Some(range) if range.end == i => range.end += 1,
+        _ => ranges.push(i..i + 1),
+    };
+    for i in 0..common_len {
+        if bit(old, i) != bit(new, i) {
+            add(i);
+        }
+    }
+    let max_len = old.bit_len().max(new.bit_len());
+    match ranges.last_mut() {
+        _ if common_len == max_len => {}
+        Some(range) if range.end == common_len => range.end = max_len,
+        _ => ranges.push(common_len..max_len),
+    }
+    ranges
+}
+
+impl Display for CellChange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}: ", self.path)?;
+        match &self.kind {
+            CellChangeKind::Data {
+                old_bit_len,
+                new_bit_len,
+                ranges,
+            } => write!(
+                f,
+                "data bits {ranges:?} changed, bit_len: {old_bit_len} -> {new_bit_len}"
+            ),
+            CellChangeKind::Exotic { old, new } => write!(f, "exotic: {old} -> {new}"),
+            CellChangeKind::ReferenceAdded { idx, hash } => {
+                write!(f, "reference {idx} added: {hash}")
+            }
+            CellChangeKind::ReferenceRemoved { idx, hash } => {
+                write!(f, "reference {idx} removed: {hash}")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use num_bigint::BigUint;
+
+    use super::*;
+    use crate::cell::CellBuilder;
+    use crate::tlb_types::block::coins::Grams;
+
+    fn tree(value: u16, extra_ref: bool) -> Result<Cell, TonCellError> {
+        let shared = CellBuilder::new().store_u32(32, 0xc0de)?.build()?;
+        let leaf = CellBuilder::new().store_u16(16, value)?.build()?;
+        let mut builder = CellBuilder::new();
+        builder
+            .store_u8(8, 1)?
+            .store_child(shared)?
+            .store_child(CellBuilder::new().store_child(leaf)?.build()?)?;
+        if extra_ref {
+            builder.store_child(Cell::default())?;
+        }
+        builder.build()
+    }
+
+    #[test]
+    fn test_diff_equal() -> Result<(), TonCellError> {
+        assert!(diff_cells(&tree(1, false)?, &tree(1, false)?).is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_diff_cells() -> Result<(), TonCellError> {
+        let changes = diff_cells(&tree(0x0f00, false)?, &tree(0x0e01, true)?);
+        assert_eq!(
+            changes,
+            [
+                CellChange {
+                    path: vec![1, 0],
+                    kind: CellChangeKind::Data {
+                        old_bit_len: 16,
+                        new_bit_len: 16,
+                        ranges: vec![7..8, 15..16],
+                    },
+                },
+                CellChange {
+                    path: vec![],
+                    kind: CellChangeKind::ReferenceAdded {
+                        idx: 2,
+                        hash: Cell::default().cell_hash(),
+                    },
+                },
+            ]
+        );
+        assert_eq!(
+            changes[0].to_string(),
+            "[1, 0]: data bits [7..8, 15..16] changed, bit_len: 16 -> 16"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_diff_bit_len() -> Result<(), TonCellError> {
+        let old = CellBuilder::new().store_u8(8, 0b1010_1010)?.build()?;
+        let new = CellBuilder::new()
+            .store_u8(7, 0b101_0100)?
+            .store_u8(4, 0)?
+            .build()?;
+        let changes = diff_cells(&old, &new);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(
+            changes[0].kind,
+            CellChangeKind::Data {
+                old_bit_len: 8,
+                new_bit_len: 11,
+                ranges: vec![6..7, 8..11],
+            }
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_compare_tlb() -> Result<(), TonCellError> {
+        let old = Grams::new(BigUint::from(100u32)).to_cell()?;
+        let new = Grams::new(BigUint::from(200u32)).to_cell()?;
+        assert!(compare_tlb::<Grams>(&old, &old)?.is_none());
+
+        let diff = compare_tlb::<Grams>(&old, &new)?.unwrap();
+        assert_eq!(diff.old, Grams::new(BigUint::from(100u32)));
+        assert_eq!(diff.new, Grams::new(BigUint::from(200u32)));
+        assert_eq!(diff.changes.len(), 1);
+        Ok(())
+    }
+}
@@ -11,6 +11,9 @@ pub enum TonCellError {
     #[error("Bag of cells serialization error ({0})")]
     BagOfCellsSerializationError(String),
 
+    #[error("Bag of cells crc32c mismatch (Expected: {expected:#010x}, actual: {actual:#010x})")]
+    BagOfCellsCrcMismatch { expected: u32, actual: u32 },
+
     #[error("Cell builder error ({0})")]
     CellBuilderError(String),
 
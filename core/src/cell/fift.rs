@@ -0,0 +1,281 @@
+use std::fmt::Write;
+
+use crate::cell::{ArcCell, Cell, TonCellError};
+
+const SPECIAL_PREFIX: &str = "SPECIAL ";
+/// Maximum depth of cell tree
+const MAX_CELL_DEPTH: usize = 1024;
+
+/// Writes cell tree in the format of Fift `csr.`: one `x{...}` line per cell,
+/// references are indented by one space per level.
+pub(crate) fn write_cell_fift<W: Write>(
+    f: &mut W,
+    cell: &Cell,
+    indent_level: usize,
+) -> std::fmt::Result {
+    for _ in 0..indent_level {
+        f.write_char(' ')?;
+    }
+    if cell.is_exotic() {
+        f.write_str(SPECIAL_PREFIX)?;
+    }
+    writeln!(f, "x{{{}}}", to_fift_hex(&cell.data, cell.bit_len))?;
+    for reference in &cell.references {
+        write_cell_fift(f, reference, indent_level + 1)?;
+    }
+    Ok(())
+}
+
+/// Parses cell tree printed by [`write_cell_fift`], `b{...}` binary cells are accepted too
+pub(crate) fn parse_cell_fift(text: &str) -> Result<Cell, TonCellError> {
+    let lines: Vec<(usize, &str)> = text
+        .lines()
+        .map(str::trim_end)
+        .filter(|line| !line.trim_start().is_empty())
+        .map(|line| {
+            let content = line.trim_start();
+            (line.len() - content.len(), content)
+        })
+        .collect();
+    if lines.is_empty() {
+        return Err(TonCellError::InvalidInput(
+            "Fift cell text is empty".to_string(),
+        ));
+    }
+
+    // cells of the current branch with their indents, their references are still being read
+    let mut branch: Vec<(usize, PendingCell)> = vec![];
+    let mut root = None;
+    for &(indent, line) in &lines {
+        while branch.last().is_some_and(|(last, _)| *last >= indent) {
+            close_cell(&mut branch, &mut root)?;
+        }
+        if branch.is_empty() && root.is_some() {
+            return Err(TonCellError::InvalidInput(format!(
+                "Fift cell text has several roots, second root at line: {line}"
+            )));
+        }
+        if branch.len() > MAX_CELL_DEPTH {
+            return Err(TonCellError::InvalidInput(format!(
+                "Fift cell text is nested deeper than {MAX_CELL_DEPTH} levels"
+            )));
+        }
+        branch.push((indent, PendingCell::parse(line)?));
+    }
+    while !branch.is_empty() {
+        close_cell(&mut branch, &mut root)?;
+    }
+    // unwrap is safe: there is at least one line, so the root is closed above
+    Ok(root.unwrap())
+}
+
+/// Cell parsed from its line, which is built once all its references are read
+struct PendingCell {
+    data: Vec<u8>,
+    bit_len: usize,
+    is_exotic: bool,
+    references: Vec<ArcCell>,
+}
+
+impl PendingCell {
+    fn parse(line: &str) -> Result<Self, TonCellError> {
+        let (is_exotic, line) = match line.strip_prefix(SPECIAL_PREFIX) {
+            Some(rest) => (true, rest.trim_start()),
+            None => (false, line),
+        };
+        let (data, bit_len) = if let Some(hex) = strip_braces(line, "x{") {
+            from_fift_hex(hex)?
+        } else if let Some(bin) = strip_braces(line, "b{") {
+            from_fift_bin(bin)?
+        } else {
+            return Err(TonCellError::InvalidInput(format!(
+                "Expected x{{...}} or b{{...}}, got: {line}"
+            )));
+        };
+        Ok(Self {
+            data,
+            bit_len,
+            is_exotic,
+            references: vec![],
+        })
+    }
+}
+
+/// Builds the last cell of `branch` and adds it to the references of its parent
+fn close_cell(
+    branch: &mut Vec<(usize, PendingCell)>,
+    root: &mut Option<Cell>,
+) -> Result<(), TonCellError> {
+    let Some((_, pending)) = branch.pop() else {
+        return Ok(());
+    };
+    let cell = Cell::new(
+        pending.data,
+        pending.bit_len,
+        pending.references,
+        pending.is_exotic,
+    )?;
+    match branch.last_mut() {
+        Some((_, parent)) => parent.references.push(cell.to_arc()),
+        None => *root = Some(cell),
+    }
+    Ok(())
+}
+
+fn strip_braces<'a>(line: &'a str, open: &str) -> Option<&'a str> {
+    line.strip_prefix(open)?.strip_suffix('}')
+}
+
+/// Hex representation with completion tag: if the bit length is not divisible by 4,
+/// the data is padded with `1` and zeros and `_` is appended
+pub(crate) fn to_fift_hex(data: &[u8], bit_len: usize) -> String {
+    let mut hex = hex::encode_upper(data);
+    hex.truncate(bit_len.div_ceil(4));
+    let rest_bits = bit_len % 4;
+    if rest_bits != 0 {
+        // unwrap is safe: there is at least one nibble if rest_bits != 0
+        let last = hex.pop().unwrap().to_digit(16).unwrap() as u8;
+        let last = (last & (0xF0 >> rest_bits)) | (0x8 >> rest_bits);
+        hex.push_str(&format!("{last:X}_"));
+    }
+    hex
+}
+
+fn from_fift_hex(hex: &str) -> Result<(Vec<u8>, usize), TonCellError> {
+    let (hex, has_tag) = match hex.strip_suffix('_') {
+        Some(hex) => (hex, true),
+        None => (hex, false),
+    };
+    let bits = hex
+        .chars()
+        .map(|c| {
+            c.to_digit(16).map(|d| d as u8).ok_or_else(|| {
+                TonCellError::InvalidInput(format!("Invalid hex digit '{c}' in x{{{hex}}}"))
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter()
+        .flat_map(|nibble| (0..4).rev().map(move |i| nibble >> i & 1 == 1))
+        .collect();
+    bits_to_data(bits, has_tag)
+}
+
+fn from_fift_bin(bin: &str) -> Result<(Vec<u8>, usize), TonCellError> {
+    let (bin, has_tag) = match bin.strip_suffix('_') {
+        Some(bin) => (bin, true),
+        None => (bin, false),
+    };
+    let bits = bin
+        .chars()
+        .map(|c| match c {
+            '0' => Ok(false),
+            '1' => Ok(true),
+            _ => Err(TonCellError::InvalidInput(format!(
+                "Invalid binary digit '{c}' in b{{{bin}}}"
+            ))),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    bits_to_data(bits, has_tag)
+}
+
+fn bits_to_data(mut bits: Vec<bool>, has_tag: bool) -> Result<(Vec<u8>, usize), TonCellError> {
+    if has_tag {
+        // remove padding: trailing zeros and the leading one
+        while bits.last() == Some(&false) {
+            bits.pop();
+        }
+        if bits.pop().is_none() {
+            return Err(TonCellError::InvalidInput(
+                "Completion tag without terminating bit".to_string(),
+            ));
+        }
+    }
+    let mut data = vec![0u8; bits.len().div_ceil(8)];
+    for (i, _) in bits.iter().enumerate().filter(|(_, &bit)| bit) {
+        data[i / 8] |= 0x80 >> (i % 8);
+    }
+    Ok((data, bits.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cell::CellBuilder;
+
+    #[test]
+    fn test_fift_hex() {
+        assert_eq!(to_fift_hex(&[], 0), "");
+        assert_eq!(to_fift_hex(&[0xAB], 8), "AB");
+        assert_eq!(to_fift_hex(&[0xAB], 4), "A");
+        assert_eq!(to_fift_hex(&[0x80], 1), "C_");
+        assert_eq!(to_fift_hex(&[0xAE], 7), "AF_");
+        assert_eq!(to_fift_hex(&[0x00, 0x00], 9), "004_");
+
+        for hex in ["", "AB", "A", "C_", "AF_", "004_"] {
+            let (data, bit_len) = from_fift_hex(hex).unwrap();
+            assert_eq!(to_fift_hex(&data, bit_len), hex);
+        }
+        assert_eq!(from_fift_hex("8_").unwrap(), (vec![], 0));
+        assert!(from_fift_hex("0_").is_err());
+        assert!(from_fift_hex("G").is_err());
+    }
+
+    #[test]
+    fn test_fift_tree() -> Result<(), TonCellError> {
+        let leaf = CellBuilder::new().store_u8(3, 5)?.build()?;
+        let middle = CellBuilder::new()
+            .store_u32(32, 0xB5EE9C72)?
+            .store_child(leaf.clone())?
+            .build()?;
+        let root = CellBuilder::new()
+            .store_bit(true)?
+            .store_child(middle)?
+            .store_child(leaf)?
+            .build()?;
+
+        let text = root.to_fift_string();
+        assert_eq!(text, "x{C_}\n x{B5EE9C72}\n  x{B_}\n x{B_}\n");
+        assert_eq!(root.to_string(), text);
+        assert_eq!(Cell::from_fift(&text)?, root);
+
+        let text = "x{C_}\n  x{B5EE9C72}\n    b{101}\n  b{101}\n";
+        assert_eq!(Cell::from_fift(text)?, root);
+        Ok(())
+    }
+
+    #[test]
+    fn test_fift_exotic() -> Result<(), TonCellError> {
+        let mut data = vec![2u8];
+        data.extend([0x11; 32]);
+        let cell = Cell::new(data, 264, vec![], true)?;
+        assert!(cell.is_library());
+        let text = cell.to_fift_string();
+        assert_eq!(text, format!("SPECIAL x{{02{}}}\n", "11".repeat(32)));
+        assert_eq!(Cell::from_fift(&text)?, cell);
+        Ok(())
+    }
+
+    #[test]
+    fn test_fift_invalid() {
+        assert!(Cell::from_fift("").is_err());
+        assert!(Cell::from_fift("x{AB").is_err());
+        assert!(Cell::from_fift("x{AB}\nx{CD}").is_err());
+        assert!(Cell::from_fift("x{AB}\n x{CD}\nx{EF}").is_err());
+        assert!(Cell::from_fift("y{AB}").is_err());
+    }
+
+    #[test]
+    fn test_fift_depth_limit() -> Result<(), TonCellError> {
+        let nested = |depth: usize| -> String {
+            (0..=depth)
+                .map(|level| format!("{}x{{}}\n", " ".repeat(level)))
+                .collect()
+        };
+        assert_eq!(Cell::from_fift(&nested(1024))?.cell_depth(), 1024);
+        assert!(matches!(
+            Cell::from_fift(&nested(1025)),
+            Err(TonCellError::InvalidInput(_))
+        ));
+        Ok(())
+    }
+}
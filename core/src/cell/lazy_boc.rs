@@ -0,0 +1,356 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::Cursor;
+
+use bitstream_io::{BigEndian, ByteReader};
+
+use crate::cell::raw::{read_cell, serialized_cell_len, RawBocHeader};
+use crate::cell::{ArcCell, CellParser, MapTonCellError, RawCell, TonCellError};
+
+/// Bag of cells which decodes cells on demand.
+///
+/// Borrows serialized BoC, so it works on top of memory-mapped files without copying them.
+/// Parsing only locates cells, using BoC index if it's present or a single scan of cell
+/// descriptors otherwise. Cells are decoded when they are reached with [`LazyCell::reference`].
+/// Absent cells of incomplete BoCs are decoded like in [`BagOfCells::parse`](crate::cell::BagOfCells::parse).
+#[derive(Debug, Clone)]
+pub struct LazyBoc<'a> {
+    serial: &'a [u8],
+    size: u8,
+    roots: Vec<usize>,
+    /// Start offsets of cells in `serial`, followed by the end of cell data
+    offsets: Vec<usize>,
+}
+
+/// Cell of [`LazyBoc`], its references are decoded only when they are followed
+#[derive(Debug, Clone)]
+pub struct LazyCell<'a> {
+    boc: &'a LazyBoc<'a>,
+    index: usize,
+    raw: RawCell,
+}
+
+impl<'a> LazyBoc<'a> {
+    pub fn parse(serial: &'a [u8]) -> Result<LazyBoc<'a>, TonCellError> {
+        if serial.is_empty() {
+            return Err(TonCellError::boc_deserialization_error("Empty BoC"));
+        }
+        let header = RawBocHeader::parse(serial)?;
+        let data_end = header.data_start + header.tot_cells_size;
+        if data_end > serial.len() {
+            return Err(TonCellError::boc_deserialization_error(format!(
+                "BoC is truncated: cell data ends at {data_end}, but BoC has {} bytes",
+                serial.len()
+            )));
+        }
+
+        let mut offsets = Vec::with_capacity(header.cells + 1);
+        offsets.push(header.data_start);
+        if header.index.is_empty() {
+            let mut offset = header.data_start;
+            for _ in 0..header.cells {
+                if offset + 2 > data_end {
+                    break;
+                }
+                offset += serialized_cell_len(serial[offset], serial[offset + 1], header.size);
+                offsets.push(offset);
+            }
+        } else {
+            for &end in &header.index {
+                let offset = header.data_start + end;
+                if offset <= offsets[offsets.len() - 1] {
+                    return Err(TonCellError::boc_deserialization_error(format!(
+                        "Index entry {end} of cell {} is not increasing",
+                        offsets.len() - 1
+                    )));
+                }
+                offsets.push(offset);
+            }
+        }
+        if offsets.len() != header.cells + 1 || offsets[header.cells] != data_end {
+            return Err(TonCellError::boc_deserialization_error(format!(
+                "Cells don't match declared cell data size {}",
+                header.tot_cells_size
+            )));
+        }
+        if let Some(root) = header.roots.iter().find(|&&root| root >= header.cells) {
+            return Err(TonCellError::boc_deserialization_error(format!(
+                "Invalid root index: {root}, BoC contains {} cells",
+                header.cells
+            )));
+        }
+
+        Ok(LazyBoc {
+            serial,
+            size: header.size,
+            roots: header.roots,
+            offsets,
+        })
+    }
+
+    pub fn num_cells(&self) -> usize {
+        self.offsets.len() - 1
+    }
+
+    pub fn num_roots(&self) -> usize {
+        self.roots.len()
+    }
+
+    pub fn root(&self, idx: usize) -> Result<LazyCell<'_>, TonCellError> {
+        let index = self.roots.get(idx).ok_or_else(|| {
+            TonCellError::boc_deserialization_error(format!(
+                "Invalid root index: {}, BoC contains {} roots",
+                idx,
+                self.roots.len()
+            ))
+        })?;
+        self.cell(*index)
+    }
+
+    pub fn single_root(&self) -> Result<LazyCell<'_>, TonCellError> {
+        let roots_count = self.roots.len();
+        if roots_count == 1 {
+            self.cell(self.roots[0])
+        } else {
+            let err_msg = format!("Single root expected, got {roots_count}");
+            Err(TonCellError::CellParserError(err_msg))
+        }
+    }
+
+    /// Decodes cell with position `index` in BoC
+    pub fn cell(&self, index: usize) -> Result<LazyCell<'_>, TonCellError> {
+        let raw = self.raw_cell(index)?;
+        Ok(LazyCell {
+            boc: self,
+            index,
+            raw,
+        })
+    }
+
+    /// Decodes cell with position `index` and its whole subtree
+    pub fn load_cell(&self, index: usize) -> Result<ArcCell, TonCellError> {
+        let mut raw_cells = BTreeMap::new();
+        let mut visited = HashSet::from([index]);
+        let mut stack = vec![index];
+        while let Some(cell_index) = stack.pop() {
+            let raw = self.raw_cell(cell_index)?;
+            for &ref_index in &raw.references {
+                if ref_index <= cell_index {
+                    return Err(TonCellError::boc_deserialization_error(
+                        "References to previous cells are not supported",
+                    ));
+                }
+                if visited.insert(ref_index) {
+                    stack.push(ref_index);
+                }
+            }
+            raw_cells.insert(cell_index, raw);
+        }
+
+        // references point to cells with greater indices, so build cells from the end
+        let mut cells: HashMap<usize, ArcCell> = HashMap::with_capacity(raw_cells.len());
+        for (cell_index, raw) in raw_cells.into_iter().rev() {
+            let references = raw
+                .references
+                .iter()
+                .map(|ref_index| cells[ref_index].clone())
+                .collect();
+            let cell = raw.into_cell(references).map_boc_deserialization_error()?;
+            cells.insert(cell_index, cell.to_arc());
+        }
+        Ok(cells.remove(&index).unwrap()) // unwrap is safe: the cell itself is built above
+    }
+
+    fn raw_cell(&self, index: usize) -> Result<RawCell, TonCellError> {
+        if index >= self.num_cells() {
+            return Err(TonCellError::boc_deserialization_error(format!(
+                "Invalid cell index: {}, BoC contains {} cells",
+                index,
+                self.num_cells()
+            )));
+        }
+        let serial = &self.serial[self.offsets[index]..self.offsets[index + 1]];
+        let mut reader = ByteReader::endian(Cursor::new(serial), BigEndian);
+        let (raw, _) = read_cell(&mut reader, self.size)?;
+        if reader.reader().position() as usize != serial.len() {
+            return Err(TonCellError::boc_deserialization_error(format!(
+                "Cell {index} doesn't match its index entry"
+            )));
+        }
+        Ok(raw)
+    }
+}
+
+impl<'a> LazyCell<'a> {
+    /// Position of the cell in BoC
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    pub fn data(&self) -> &[u8] {
+        if self.raw.is_absent {
+            &[]
+        } else {
+            self.raw.data.as_slice()
+        }
+    }
+
+    pub fn bit_len(&self) -> usize {
+        if self.raw.is_absent {
+            0
+        } else {
+            self.raw.bit_len
+        }
+    }
+
+    pub fn is_exotic(&self) -> bool {
+        self.raw.is_exotic || self.raw.is_absent
+    }
+
+    /// Whether the cell is absent in an incomplete BoC, see [`Cell::is_absent`](crate::cell::Cell::is_absent)
+    pub fn is_absent(&self) -> bool {
+        self.raw.is_absent
+    }
+
+    pub fn reference_count(&self) -> usize {
+        self.raw.references.len()
+    }
+
+    /// Decodes referenced cell
+    pub fn reference(&self, idx: usize) -> Result<LazyCell<'a>, TonCellError> {
+        let index = self
+            .raw
+            .references
+            .get(idx)
+            .ok_or(TonCellError::InvalidIndex {
+                idx,
+                ref_count: self.raw.references.len(),
+            })?;
+        self.boc.cell(*index)
+    }
+
+    /// Parses cell data.
+    ///
+    /// The parser doesn't see references of ordinary cells, so reading them fails: follow
+    /// references with [`LazyCell::reference`] or decode the subtree with [`LazyCell::to_cell`].
+    /// Merkle proofs and updates can't be built without their references, so their
+    /// referenced subtrees are decoded.
+    pub fn parse_data<F, T>(&self, parse: F) -> Result<T, TonCellError>
+    where
+        F: FnOnce(&mut CellParser) -> Result<T, TonCellError>,
+    {
+        let references = if self.raw.is_exotic {
+            self.raw
+                .references
+                .iter()
+                .map(|&index| self.boc.load_cell(index))
+                .collect::<Result<_, _>>()?
+        } else {
+            vec![]
+        };
+        let cell = self.raw.clone().into_cell(references)?;
+        let mut parser = cell.parser();
+        parse(&mut parser)
+    }
+
+    /// Decodes the whole subtree of the cell
+    pub fn to_cell(&self) -> Result<ArcCell, TonCellError> {
+        self.boc.load_cell(self.index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cell::{BagOfCells, BocCellOrder, BocSerializeOptions, Cell, CellBuilder};
+
+    fn tree() -> Result<Cell, TonCellError> {
+        let leaf = CellBuilder::new().store_u32(32, 0xcafe)?.build()?;
+        let left = CellBuilder::new()
+            .store_u8(4, 3)?
+            .store_child(leaf.clone())?
+            .build()?;
+        let right = CellBuilder::new()
+            .store_u16(16, 0xbeef)?
+            .store_child(leaf)?
+            .build()?;
+        CellBuilder::new()
+            .store_u8(8, 42)?
+            .store_child(left)?
+            .store_child(right)?
+            .build()
+    }
+
+    #[test]
+    fn test_lazy_boc() -> Result<(), TonCellError> {
+        let root = tree()?;
+        let boc = BagOfCells::from_root(root.clone());
+        for options in [
+            BocSerializeOptions::default(),
+            BocSerializeOptions {
+                has_idx: true,
+                has_cache_bits: true,
+                has_crc32c: true,
+                cell_order: BocCellOrder::DepthFirst,
+            },
+        ] {
+            let serialized = boc.serialize_with_options(&options)?;
+            let lazy = LazyBoc::parse(&serialized)?;
+            assert_eq!(lazy.num_cells(), 4);
+            assert_eq!(lazy.num_roots(), 1);
+
+            let lazy_root = lazy.single_root()?;
+            assert_eq!(lazy_root.reference_count(), 2);
+            assert_eq!(lazy_root.parse_data(|p| p.load_u8(8))?, 42);
+            let right = lazy_root.reference(1)?;
+            assert_eq!(right.parse_data(|p| p.load_u16(16))?, 0xbeef);
+            let leaf = right.reference(0)?;
+            assert_eq!(leaf.parse_data(|p| p.load_u32(32))?, 0xcafe);
+            assert_eq!(leaf.index(), lazy_root.reference(0)?.reference(0)?.index());
+            assert!(matches!(
+                leaf.reference(0),
+                Err(TonCellError::InvalidIndex { idx: 0, .. })
+            ));
+
+            assert_eq!(lazy_root.to_cell()?.cell_hash(), root.cell_hash());
+            assert_eq!(right.to_cell()?.as_ref(), root.reference(1)?.as_ref());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_lazy_boc_exotic() -> Result<(), TonCellError> {
+        let inner = tree()?;
+        let mut builder = CellBuilder::new();
+        builder.set_cell_is_exotic(true);
+        let proof = builder
+            .store_u8(8, 3)?
+            .store_slice(inner.cell_hash().as_slice())?
+            .store_u16(16, inner.get_depth(0))?
+            .store_child(inner)?
+            .build()?;
+        let serialized = BagOfCells::from_root(proof.clone()).serialize(false)?;
+        let lazy = LazyBoc::parse(&serialized)?;
+
+        let lazy_root = lazy.single_root()?;
+        assert!(lazy_root.is_exotic());
+        let (is_exotic, cell_type) =
+            lazy_root.parse_data(|p| Ok((p.cell.is_exotic(), p.load_u8(8)?)))?;
+        assert!(is_exotic);
+        assert_eq!(cell_type, 3);
+        assert!(!lazy_root.reference(0)?.is_exotic());
+        assert_eq!(lazy_root.to_cell()?.cell_hash(), proof.cell_hash());
+        Ok(())
+    }
+
+    #[test]
+    fn test_lazy_boc_invalid() -> Result<(), TonCellError> {
+        let serialized = BagOfCells::from_root(tree()?).serialize(false)?;
+        assert!(LazyBoc::parse(&serialized[..serialized.len() - 1]).is_err());
+        assert!(LazyBoc::parse(&[]).is_err());
+
+        let lazy = LazyBoc::parse(&serialized)?;
+        assert!(lazy.root(1).is_err());
+        assert!(lazy.cell(4).is_err());
+        Ok(())
+    }
+}
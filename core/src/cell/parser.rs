@@ -7,7 +7,10 @@ use bitstream_io::{BigEndian, BitRead, BitReader};
 use num_bigint::{BigInt, BigUint};
 use num_traits::identities::Zero;
 
-use super::dict::{DictParser, KeyReader, SnakeFormatDict, ValReader};
+use super::dict::{
+    AugDictData, AugDictParser, DictParser, KeyReader, SnakeFormatDict, ValReader, VarDictKey,
+    VarDictKind, VarDictParser,
+};
 use super::{ArcCell, Cell, CellBuilder, TonCellNum};
 use crate::cell::dict::predefined_readers::{key_reader_256bit, val_reader_snake_formatted_string};
 use crate::cell::util::*;
@@ -100,10 +103,30 @@ impl<'a> CellParser<'a> {
         self.load_number(bit_len)
     }
 
+    /// Loads `bit_len` bits without extending the sign bit, so numbers shorter than 64 bits
+    /// are never negative, unlike [`CellParser::load_i128`]
     pub fn load_i64(&mut self, bit_len: usize) -> Result<i64, TonCellError> {
         Ok(self.load_number::<u64>(bit_len)? as i64)
     }
 
+    pub fn load_u128(&mut self, bit_len: usize) -> Result<u128, TonCellError> {
+        self.load_number(bit_len)
+    }
+
+    /// Loads `bit_len`-bit two's complement number, extending its sign bit.
+    ///
+    /// Narrower signed loads such as [`CellParser::load_i64`] don't extend it.
+    pub fn load_i128(&mut self, bit_len: usize) -> Result<i128, TonCellError> {
+        let value = self.load_number::<u128>(bit_len)?;
+        match bit_len {
+            1..128 => {
+                let shift = 128 - bit_len as u32;
+                Ok(((value << shift) as i128) >> shift)
+            }
+            _ => Ok(value as i128),
+        }
+    }
+
     pub fn load_uint(&mut self, bit_len: usize) -> Result<BigUint, TonCellError> {
         self.ensure_enough_bits(bit_len)?;
         let num_words = bit_len.div_ceil(32);
@@ -250,6 +273,82 @@ impl<'a> CellParser<'a> {
             reference_parser.load_dict_data(key_len, key_reader, val_reader)
         }
     }
+    /// Reads `HashmapAug n X Y`, returns values with their extras
+    pub fn load_dict_aug_data<K: Eq + Hash, V, A>(
+        &mut self,
+        key_len: usize,
+        key_reader: KeyReader<K>,
+        val_reader: ValReader<V>,
+        aug_reader: ValReader<A>,
+    ) -> Result<AugDictData<K, V, A>, TonCellError> {
+        let dict_parser = AugDictParser::new(key_len, key_reader, val_reader, aug_reader);
+        dict_parser.parse(self)
+    }
+
+    /// Reads `HashmapAugE n X Y`, returns entries and extra value of the whole dict
+    pub fn load_dict_aug<K: Eq + Hash, V, A>(
+        &mut self,
+        key_len: usize,
+        key_reader: KeyReader<K>,
+        val_reader: ValReader<V>,
+        aug_reader: ValReader<A>,
+    ) -> Result<(AugDictData<K, V, A>, A), TonCellError> {
+        let data = match self.load_maybe_cell_ref()? {
+            Some(reference_cell) => reference_cell
+                .parser()
+                .load_dict_aug_data(key_len, key_reader, val_reader, aug_reader)?,
+            None => HashMap::new(),
+        };
+        let extra = aug_reader(self)?;
+        Ok((data, extra))
+    }
+
+    /// Reads `PfxHashmap n X`
+    pub fn load_pfx_dict_data<V>(
+        &mut self,
+        key_len: usize,
+        val_reader: ValReader<V>,
+    ) -> Result<HashMap<VarDictKey, V>, TonCellError> {
+        VarDictParser::new(VarDictKind::Pfx, key_len, val_reader).parse(self)
+    }
+
+    /// Reads `PfxHashmapE n X`
+    pub fn load_pfx_dict<V>(
+        &mut self,
+        key_len: usize,
+        val_reader: ValReader<V>,
+    ) -> Result<HashMap<VarDictKey, V>, TonCellError> {
+        match self.load_maybe_cell_ref()? {
+            Some(reference_cell) => reference_cell
+                .parser()
+                .load_pfx_dict_data(key_len, val_reader),
+            None => Ok(HashMap::new()),
+        }
+    }
+
+    /// Reads `VarHashmap n X`
+    pub fn load_var_dict_data<V>(
+        &mut self,
+        key_len: usize,
+        val_reader: ValReader<V>,
+    ) -> Result<HashMap<VarDictKey, V>, TonCellError> {
+        VarDictParser::new(VarDictKind::Var, key_len, val_reader).parse(self)
+    }
+
+    /// Reads `VarHashmapE n X`
+    pub fn load_var_dict<V>(
+        &mut self,
+        key_len: usize,
+        val_reader: ValReader<V>,
+    ) -> Result<HashMap<VarDictKey, V>, TonCellError> {
+        match self.load_maybe_cell_ref()? {
+            Some(reference_cell) => reference_cell
+                .parser()
+                .load_var_dict_data(key_len, val_reader),
+            None => Ok(HashMap::new()),
+        }
+    }
+
     ///Snake format when we store part of the data in a cell and the rest of the data in the first child cell (and so recursively).
     ///
     ///Must be prefixed with 0x00 byte.
@@ -1,11 +1,14 @@
-use std::io::Cursor;
+use std::io::{Cursor, Read, Write};
 
 use bitstream_io::{BigEndian, BitWrite, BitWriter, ByteRead, ByteReader};
 use crc::Crc;
 use lazy_static::lazy_static;
 
 use crate::cell::level_mask::LevelMask;
-use crate::cell::{MapTonCellError, TonCellError};
+use crate::cell::raw_stream::{RawBocDecoder, RawBocEncoder};
+use crate::cell::{ArcCell, BocSerializeOptions, Cell, MapTonCellError, TonCellError, DEPTH_BYTES};
+use crate::types::TON_HASH_LEN;
+use crate::TonHash;
 
 lazy_static! {
     pub static ref CRC_32_ISCSI: Crc<u32> = Crc::<u32>::new(&crc::CRC_32_ISCSI);
@@ -20,6 +23,8 @@ pub(crate) struct RawCell {
     pub(crate) bit_len: usize,
     pub(crate) references: Vec<usize>,
     pub(crate) is_exotic: bool,
+    /// Absent cell of an incomplete BoC, `data` holds its hashes followed by its depths
+    pub(crate) is_absent: bool,
     level_mask: u32,
 }
 
@@ -37,8 +42,39 @@ impl RawCell {
             references,
             level_mask: level_mask & 7,
             is_exotic,
+            is_absent: false,
         }
     }
+
+    /// Absent cell, `hashes_and_depths` are stored as they're serialized
+    pub(crate) fn absent(level_mask: u32, hashes_and_depths: Vec<u8>) -> Self {
+        Self {
+            bit_len: hashes_and_depths.len() * 8,
+            data: hashes_and_depths,
+            references: vec![],
+            level_mask: level_mask & 7,
+            is_exotic: false,
+            is_absent: true,
+        }
+    }
+
+    pub(crate) fn into_cell(self, references: Vec<ArcCell>) -> Result<Cell, TonCellError> {
+        if !self.is_absent {
+            return Cell::new(self.data, self.bit_len, references, self.is_exotic);
+        }
+        let hash_count = LevelMask::new(self.level_mask).hash_count();
+        let (hashes, depths) = self.data.split_at(hash_count * TON_HASH_LEN);
+        let hashes = hashes
+            .chunks(TON_HASH_LEN)
+            .map(TonHash::try_from)
+            .collect::<Result<_, _>>()
+            .map_boc_deserialization_error()?;
+        let depths = depths
+            .chunks(DEPTH_BYTES)
+            .map(|depth| u16::from_be_bytes([depth[0], depth[1]]))
+            .collect();
+        Cell::absent(self.level_mask, hashes, depths)
+    }
 }
 
 /// Raw representation of BagOfCells.
@@ -50,20 +86,36 @@ pub(crate) struct RawBagOfCells {
     pub(crate) roots: Vec<usize>,
 }
 
-const GENERIC_BOC_MAGIC: u32 = 0xb5ee9c72;
-const _INDEXED_BOC_MAGIC: u32 = 0x68ff65f3;
-const _INDEXED_CRC32_MAGIC: u32 = 0xacc3a728;
-
-impl RawBagOfCells {
-    pub(crate) fn parse(serial: &[u8]) -> Result<RawBagOfCells, TonCellError> {
-        let cursor = Cursor::new(serial);
+pub(crate) const GENERIC_BOC_MAGIC: u32 = 0xb5ee9c72;
+const INDEXED_BOC_MAGIC: u32 = 0x68ff65f3;
+const INDEXED_CRC32_MAGIC: u32 = 0xacc3a728;
+
+/// Reference count of an absent cell, which is stored as its hashes and depths only
+const ABSENT_CELL_REFS: u8 = 7;
+
+/// Length of magic, flags and `off_bytes` fields of BoC header
+pub(crate) const BOC_FLAGS_LEN: usize = 6;
+
+/// Leading fields of BoC header, they determine the length of the rest of it
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub(crate) struct RawBocFlags {
+    magic: u32,
+    has_idx: bool,
+    pub(crate) has_crc32c: bool,
+    has_cache_bits: bool,
+    /// Size of cell references in bytes
+    pub(crate) size: u8,
+    off_bytes: u8,
+}
 
-        let mut reader: ByteReader<Cursor<&[u8]>, BigEndian> =
-            ByteReader::endian(cursor, BigEndian);
+impl RawBocFlags {
+    pub(crate) fn read<R: Read>(
+        reader: &mut ByteReader<R, BigEndian>,
+    ) -> Result<RawBocFlags, TonCellError> {
         // serialized_boc#b5ee9c72
         let magic = reader.read::<u32>().map_boc_deserialization_error()?;
 
-        let (has_idx, has_crc32c, _has_cache_bits, size) = match magic {
+        let (has_idx, has_crc32c, has_cache_bits, size) = match magic {
             GENERIC_BOC_MAGIC => {
                 // has_idx:(## 1) has_crc32c:(## 1) has_cache_bits:(## 1) flags:(## 2) { flags = 0 }
                 let header = reader.read::<u8>().map_boc_deserialization_error()?;
@@ -73,14 +125,14 @@ impl RawBagOfCells {
 
                 // size:(## 3) { size <= 4 }
                 let size = header & 0b0000_0111;
-                if size > 4 {
-                    return Err(TonCellError::boc_deserialization_error(format!(
-                        "Invalid size {size}. Size should be <= 4."
-                    )));
-                }
-
                 (has_idx, has_crc32c, has_cache_bits, size)
             }
+            // serialized_boc_idx#68ff65f3 size:(## 8) { size <= 4 }
+            // serialized_boc_idx_crc32c#acc3a728 size:(## 8) { size <= 4 }
+            INDEXED_BOC_MAGIC | INDEXED_CRC32_MAGIC => {
+                let size = reader.read::<u8>().map_boc_deserialization_error()?;
+                (true, magic == INDEXED_CRC32_MAGIC, false, size)
+            }
             magic => {
                 return Err(TonCellError::boc_deserialization_error(format!(
                     "Unsupported cell magic number: {:#}",
@@ -88,140 +140,196 @@ impl RawBagOfCells {
                 )));
             }
         };
+        if size > 4 {
+            return Err(TonCellError::boc_deserialization_error(format!(
+                "Invalid size {size}. Size should be <= 4."
+            )));
+        }
+        if has_cache_bits && !has_idx {
+            return Err(TonCellError::boc_deserialization_error(
+                "Cache bits are set without index",
+            ));
+        }
         //   off_bytes:(## 8) { off_bytes <= 8 }
         let off_bytes = reader.read::<u8>().map_boc_deserialization_error()?;
+        if off_bytes > 8 {
+            return Err(TonCellError::boc_deserialization_error(format!(
+                "Invalid off_bytes {off_bytes}. Off_bytes should be <= 8."
+            )));
+        }
+
+        Ok(RawBocFlags {
+            magic,
+            has_idx,
+            has_crc32c,
+            has_cache_bits,
+            size,
+            off_bytes,
+        })
+    }
+
+    /// Length of `cells`, `roots`, `absent` and `tot_cells_size` fields
+    pub(crate) fn counters_len(&self) -> usize {
+        3 * self.size as usize + self.off_bytes as usize
+    }
+
+    /// Length of root list and index
+    pub(crate) fn lists_len(&self, cells: usize, roots: usize) -> usize {
+        let root_list_len = if self.magic == GENERIC_BOC_MAGIC {
+            roots * self.size as usize
+        } else {
+            0
+        };
+        let index_len = if self.has_idx {
+            cells * self.off_bytes as usize
+        } else {
+            0
+        };
+        root_list_len + index_len
+    }
+}
+
+/// Header of serialized BoC, everything that precedes cell data
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub(crate) struct RawBocHeader {
+    /// Size of cell references in bytes
+    pub(crate) size: u8,
+    pub(crate) has_crc32c: bool,
+    pub(crate) cells: usize,
+    pub(crate) absent: usize,
+    pub(crate) tot_cells_size: usize,
+    pub(crate) roots: Vec<usize>,
+    /// End offsets of cells relative to `data_start`, empty if BoC has no index
+    pub(crate) index: Vec<usize>,
+    /// Offset of cell data in serialized BoC
+    pub(crate) data_start: usize,
+}
+
+impl RawBocHeader {
+    /// Parses the header and verifies crc32c if BoC has one
+    pub(crate) fn parse(serial: &[u8]) -> Result<RawBocHeader, TonCellError> {
+        let mut reader = ByteReader::endian(Cursor::new(serial), BigEndian);
+        let header = Self::read(&mut reader)?;
+        if header.has_crc32c {
+            verify_crc32c(serial)?;
+        }
+        Ok(header)
+    }
+
+    pub(crate) fn read<R: Read>(
+        reader: &mut ByteReader<R, BigEndian>,
+    ) -> Result<RawBocHeader, TonCellError> {
+        let flags = RawBocFlags::read(reader)?;
+        let size = flags.size;
+        let off_bytes = flags.off_bytes;
         //cells:(##(size * 8))
-        let cells = read_var_size(&mut reader, size)?;
+        let cells = read_var_size(reader, size)?;
         //   roots:(##(size * 8)) { roots >= 1 }
-        let roots = read_var_size(&mut reader, size)?;
+        let roots = read_var_size(reader, size)?;
         //   absent:(##(size * 8)) { roots + absent <= cells }
-        let _absent = read_var_size(&mut reader, size)?;
+        let absent = read_var_size(reader, size)?;
+        if roots + absent > cells {
+            return Err(TonCellError::boc_deserialization_error(format!(
+                "Invalid header: {roots} roots and {absent} absent cells out of {cells} cells"
+            )));
+        }
         //   tot_cells_size:(##(off_bytes * 8))
-        let _tot_cells_size = read_var_size(&mut reader, off_bytes)?;
+        let tot_cells_size = read_var_size(reader, off_bytes)?;
         //   root_list:(roots * ##(size * 8))
         let mut root_list = vec![];
-        for _ in 0..roots {
-            root_list.push(read_var_size(&mut reader, size)?)
+        if flags.magic == GENERIC_BOC_MAGIC {
+            for _ in 0..roots {
+                root_list.push(read_var_size(reader, size)?)
+            }
+        } else if roots == 1 {
+            // indexed formats have the only root at index 0
+            root_list.push(0);
+        } else {
+            return Err(TonCellError::boc_deserialization_error(format!(
+                "Indexed BoC must have exactly one root, got {roots}"
+            )));
         }
         //   index:has_idx?(cells * ##(off_bytes * 8))
         let mut index = vec![];
-        if has_idx {
+        if flags.has_idx {
             for _ in 0..cells {
-                index.push(read_var_size(&mut reader, off_bytes)?)
+                let entry = read_var_size(reader, off_bytes)?;
+                // the lowest bit is a cache bit, it's only a hint for deserializers
+                index.push(if flags.has_cache_bits {
+                    entry >> 1
+                } else {
+                    entry
+                })
             }
         }
-        //   cell_data:(tot_cells_size * [ uint8 ])
-        let mut cell_vec = Vec::with_capacity(cells);
-
-        for _ in 0..cells {
-            let cell = read_cell(&mut reader, size)?;
-            cell_vec.push(cell);
-        }
-        //   crc32c:has_crc32c?uint32
-        let _crc32c = if has_crc32c {
-            reader.read::<u32>().map_boc_deserialization_error()?
-        } else {
-            0
-        };
-        // TODO: Check crc32
-
-        Ok(RawBagOfCells {
-            cells: cell_vec,
+        let data_start = BOC_FLAGS_LEN + flags.counters_len() + flags.lists_len(cells, roots);
+
+        Ok(RawBocHeader {
+            size,
+            has_crc32c: flags.has_crc32c,
+            cells,
+            absent,
+            tot_cells_size,
             roots: root_list,
+            index,
+            data_start,
         })
     }
+}
 
-    pub(crate) fn serialize(&self, has_crc32: bool) -> Result<Vec<u8>, TonCellError> {
-        //Based on https://github.com/toncenter/tonweb/blob/c2d5d0fc23d2aec55a0412940ce6e580344a288c/src/boc/Cell.js#L198
-
-        let root_count = self.roots.len();
-        let num_ref_bits = 32 - (self.cells.len() as u32).leading_zeros();
-        let num_ref_bytes = num_ref_bits.div_ceil(8);
-        let has_idx = false;
-
-        let mut full_size = 0u32;
-
-        for cell in &self.cells {
-            full_size += raw_cell_size(cell, num_ref_bytes);
-        }
-
-        let num_offset_bits = 32 - full_size.leading_zeros();
-        let num_offset_bytes = num_offset_bits.div_ceil(8);
-
-        let total_size = 4 + // magic
-            1 + // flags and s_bytes
-            1 + // offset_bytes
-            3 * num_ref_bytes + // cells_num, roots, complete
-            num_offset_bytes + // full_size
-            num_ref_bytes + // root_idx
-            (if has_idx { self.cells.len() as u32 * num_offset_bytes } else { 0 }) +
-            full_size +
-            (if has_crc32 { 4 } else { 0 });
-
-        let mut writer = BitWriter::endian(Vec::with_capacity(total_size as usize), BigEndian);
-
-        writer
-            .write_var(32, GENERIC_BOC_MAGIC)
-            .map_boc_serialization_error()?;
-
-        //write flags byte
-        let has_cache_bits = false;
-        let flags: u8 = 0;
-        writer.write_bit(has_idx).map_boc_serialization_error()?;
-        writer.write_bit(has_crc32).map_boc_serialization_error()?;
-        writer
-            .write_bit(has_cache_bits)
-            .map_boc_serialization_error()?;
-        writer.write_var(2, flags).map_boc_serialization_error()?;
-        writer
-            .write_var(3, num_ref_bytes)
-            .map_boc_serialization_error()?;
-        writer
-            .write_var(8, num_offset_bytes)
-            .map_boc_serialization_error()?;
-        writer
-            .write_var(8 * num_ref_bytes, self.cells.len() as u32)
-            .map_boc_serialization_error()?;
-        writer
-            .write_var(8 * num_ref_bytes, root_count as u32)
-            .map_boc_serialization_error()?;
-        writer
-            .write_var(8 * num_ref_bytes, 0)
-            .map_boc_serialization_error()?; // Complete BOCs only
-        writer
-            .write_var(8 * num_offset_bytes, full_size)
-            .map_boc_serialization_error()?;
-        for &root in &self.roots {
-            writer
-                .write_var(8 * num_ref_bytes, root as u32)
-                .map_boc_serialization_error()?;
+impl RawBagOfCells {
+    pub(crate) fn parse(serial: &[u8]) -> Result<RawBagOfCells, TonCellError> {
+        let mut decoder = RawBocDecoder::new();
+        let mut rest = serial;
+        while decoder.needed() > 0 {
+            let needed = decoder.needed();
+            if rest.len() < needed {
+                return Err(TonCellError::boc_deserialization_error(format!(
+                    "BoC is truncated: {} more bytes expected at offset {}",
+                    needed - rest.len(),
+                    serial.len() - rest.len()
+                )));
+            }
+            let (bytes, tail) = rest.split_at(needed);
+            decoder.feed(bytes)?;
+            rest = tail;
         }
+        decoder.finish()
+    }
 
-        for cell in &self.cells {
-            write_raw_cell(&mut writer, cell, num_ref_bytes)?;
+    pub(crate) fn serialize(&self, options: &BocSerializeOptions) -> Result<Vec<u8>, TonCellError> {
+        let mut encoder = RawBocEncoder::new(self, options)?;
+        let mut result = Vec::with_capacity(encoder.total_size());
+        while let Some(chunk) = encoder.next_chunk()? {
+            result.extend_from_slice(chunk);
         }
+        Ok(result)
+    }
+}
 
-        if has_crc32 {
-            let bytes = writer.writer().ok_or_else(|| {
-                TonCellError::boc_serialization_error("Stream is not byte-aligned")
-            })?;
-            let cs = CRC_32_ISCSI.checksum(bytes.as_slice());
-            writer
-                .write_bytes(cs.to_le_bytes().as_slice())
-                .map_boc_serialization_error()?;
-        }
-        writer.byte_align().map_boc_serialization_error()?;
-        let res = writer
-            .writer()
-            .ok_or_else(|| TonCellError::boc_serialization_error("Stream is not byte-aligned"))?;
-        Ok(res.clone())
+/// Checks crc32c stored in the last 4 bytes of `serial`
+fn verify_crc32c(serial: &[u8]) -> Result<(), TonCellError> {
+    let Some(data_len) = serial.len().checked_sub(4) else {
+        return Err(TonCellError::boc_deserialization_error(
+            "BoC is too short to contain crc32c",
+        ));
+    };
+    let (data, crc) = serial.split_at(data_len);
+    let mut expected = [0u8; 4];
+    expected.copy_from_slice(crc);
+    let expected = u32::from_le_bytes(expected);
+    let actual = CRC_32_ISCSI.checksum(data);
+    if expected != actual {
+        return Err(TonCellError::BagOfCellsCrcMismatch { expected, actual });
     }
+    Ok(())
 }
 
-fn read_cell(
-    reader: &mut ByteReader<Cursor<&[u8]>, BigEndian>,
+/// Reads a cell, the flag is set for absent cells, which are returned with their hashes and depths
+pub(crate) fn read_cell<R: Read>(
+    reader: &mut ByteReader<R, BigEndian>,
     size: u8,
-) -> Result<RawCell, TonCellError> {
+) -> Result<(RawCell, bool), TonCellError> {
     let d1 = reader.read::<u8>().map_boc_deserialization_error()?;
     let d2 = reader.read::<u8>().map_boc_deserialization_error()?;
 
@@ -232,6 +340,16 @@ fn read_cell(
     let data_size = ((d2 >> 1) + (d2 & 1)).into();
     let full_bytes = (d2 & 0x01) == 0;
 
+    if ref_num == ABSENT_CELL_REFS && has_hashes {
+        let cell = read_absent_cell(reader, level_mask, data_size)?;
+        return Ok((cell, true));
+    }
+    if ref_num > 4 {
+        return Err(TonCellError::boc_deserialization_error(format!(
+            "Invalid cell descriptor {d1:#04x}: cell can't have {ref_num} references"
+        )));
+    }
+
     if has_hashes {
         let hash_count = LevelMask::new(level_mask).hash_count();
         let skip_size = hash_count * (32 + 2);
@@ -267,20 +385,62 @@ fn read_cell(
         references.push(read_var_size(reader, size)?);
     }
     let cell = RawCell::new(data, bit_len, references, level_mask, is_exotic);
-    Ok(cell)
+    Ok((cell, false))
 }
 
-fn raw_cell_size(cell: &RawCell, ref_size_bytes: u32) -> u32 {
+/// Length of serialized cell with descriptor bytes `d1` and `d2`
+pub(crate) fn serialized_cell_len(d1: u8, d2: u8, size: u8) -> usize {
+    let ref_num = d1 & 0b111;
+    let has_hashes = (d1 & 0b10000) != 0;
+    let data_size = ((d2 >> 1) + (d2 & 1)) as usize;
+    let hashes_size = if has_hashes {
+        LevelMask::new((d1 >> 5) as u32).hash_count() * (32 + 2)
+    } else {
+        0
+    };
+    let refs_size = if ref_num == ABSENT_CELL_REFS && has_hashes {
+        0
+    } else {
+        ref_num as usize * size as usize
+    };
+    2 + hashes_size + data_size + refs_size
+}
+
+fn read_absent_cell<R: Read>(
+    reader: &mut ByteReader<R, BigEndian>,
+    level_mask: u32,
+    data_size: usize,
+) -> Result<RawCell, TonCellError> {
+    let hash_count = LevelMask::new(level_mask).hash_count();
+    let hashes_and_depths = reader
+        .read_to_vec(hash_count * (TON_HASH_LEN + DEPTH_BYTES))
+        .map_boc_deserialization_error()?;
+    reader
+        .skip(data_size as u32)
+        .map_boc_deserialization_error()?;
+    Ok(RawCell::absent(level_mask, hashes_and_depths))
+}
+
+pub(crate) fn raw_cell_size(cell: &RawCell, ref_size_bytes: u32) -> u32 {
     let data_len = cell.bit_len.div_ceil(8);
     2 + data_len as u32 + cell.references.len() as u32 * ref_size_bytes
 }
 
-fn write_raw_cell(
-    writer: &mut BitWriter<Vec<u8>, BigEndian>,
+pub(crate) fn write_raw_cell<W: Write>(
+    writer: &mut BitWriter<W, BigEndian>,
     cell: &RawCell,
     ref_size_bytes: u32,
 ) -> Result<(), TonCellError> {
     let level = cell.level_mask;
+    if cell.is_absent {
+        let d1 = ABSENT_CELL_REFS as u32 + 16 + level * 32;
+        writer.write_var(8, d1).map_boc_serialization_error()?;
+        writer.write_var(8, 0u8).map_boc_serialization_error()?;
+        writer
+            .write_bytes(&cell.data)
+            .map_boc_serialization_error()?;
+        return Ok(());
+    }
     let is_exotic = cell.is_exotic as u32;
     let num_refs = cell.references.len() as u32;
     let d1 = num_refs + is_exotic * 8 + level * 32;
@@ -314,8 +474,8 @@ fn write_raw_cell(
     Ok(())
 }
 
-fn read_var_size(
-    reader: &mut ByteReader<Cursor<&[u8]>, BigEndian>,
+pub(crate) fn read_var_size<R: Read>(
+    reader: &mut ByteReader<R, BigEndian>,
     n: u8,
 ) -> Result<usize, TonCellError> {
     let bytes = reader
@@ -341,6 +501,6 @@ mod tests {
             cells: vec![raw_cell],
             roots: vec![0],
         };
-        assert!(raw_bag.serialize(false).is_ok());
+        assert!(raw_bag.serialize(&BocSerializeOptions::default()).is_ok());
     }
 }
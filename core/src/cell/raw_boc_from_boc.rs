@@ -1,8 +1,8 @@
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
-use crate::cell::{ArcCell, BagOfCells, Cell, RawBagOfCells, RawCell, TonCellError};
+use crate::cell::{ArcCell, BagOfCells, BocCellOrder, Cell, RawBagOfCells, RawCell, TonCellError};
 use crate::TonHash;
 
 #[derive(Debug, Clone)]
@@ -11,12 +11,18 @@ struct IndexedCell {
     cell: ArcCell,
 }
 
-pub(crate) fn convert_to_raw_boc(boc: &BagOfCells) -> Result<RawBagOfCells, TonCellError> {
-    let cells_by_hash = build_and_verify_index(&boc.roots);
+pub(crate) fn convert_to_raw_boc(
+    boc: &BagOfCells,
+    order: BocCellOrder,
+) -> Result<RawBagOfCells, TonCellError> {
+    let cells_by_hash = match order {
+        BocCellOrder::BreadthFirst => build_and_verify_index(&boc.roots),
+        BocCellOrder::DepthFirst => build_depth_first_index(&boc.roots),
+    };
 
     // Sort indexed cells by their index value.
     let mut index_slice: Vec<_> = cells_by_hash.values().collect();
-    index_slice.sort_unstable_by(|a, b| a.borrow().index.cmp(&b.borrow().index));
+    index_slice.sort_unstable_by_key(|a| a.borrow().index);
 
     // Remove gaps in indices.
     index_slice
@@ -88,6 +94,42 @@ fn build_and_verify_index(roots: &[ArcCell]) -> HashMap<TonHash, RefCell<Indexed
     cells_by_hash
 }
 
+/// Indexes cells in reverse postorder, so every cell precedes its references
+fn build_depth_first_index(roots: &[ArcCell]) -> HashMap<TonHash, RefCell<IndexedCell>> {
+    let mut postorder = Vec::new();
+    let mut visited = HashSet::new();
+    // `true` means that all references of the cell are already visited
+    let mut stack: Vec<(ArcCell, bool)> = roots.iter().rev().map(|r| (r.clone(), false)).collect();
+
+    while let Some((cell, expanded)) = stack.pop() {
+        let hash = cell.cell_hash();
+        if expanded {
+            postorder.push((hash, cell));
+            continue;
+        }
+        if !visited.insert(hash) {
+            continue;
+        }
+        stack.push((cell.clone(), true));
+        for reference in cell.references.iter().rev() {
+            stack.push((reference.clone(), false));
+        }
+    }
+
+    let cells_count = postorder.len();
+    postorder
+        .into_iter()
+        .enumerate()
+        .map(|(index, (hash, cell))| {
+            let indexed = IndexedCell {
+                index: cells_count - 1 - index,
+                cell,
+            };
+            (hash, RefCell::new(indexed))
+        })
+        .collect()
+}
+
 fn root_indices(
     roots: &[ArcCell],
     cells_dict: &HashMap<TonHash, RefCell<IndexedCell>>,
@@ -121,6 +163,12 @@ fn raw_cell_from_cell(
     cell: &Cell,
     cells_by_hash: &HashMap<TonHash, RefCell<IndexedCell>>,
 ) -> Result<RawCell, TonCellError> {
+    if cell.is_absent() {
+        let (hashes, depths) = cell.significant_hashes_and_depths();
+        let mut hashes_and_depths: Vec<u8> = hashes.iter().flat_map(TonHash::to_vec).collect();
+        hashes_and_depths.extend(depths.iter().flat_map(|depth| depth.to_be_bytes()));
+        return Ok(RawCell::absent(cell.get_level_mask(), hashes_and_depths));
+    }
     raw_cell_reference_indices(cell, cells_by_hash).map(|reference_indices| {
         RawCell::new(
             cell.data.clone(),
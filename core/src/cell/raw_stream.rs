@@ -0,0 +1,378 @@
+use std::io::{Cursor, Read};
+
+use bitstream_io::{BigEndian, BitWrite, BitWriter, ByteReader};
+use crc::Digest;
+
+use crate::cell::raw::{
+    raw_cell_size, read_cell, read_var_size, serialized_cell_len, write_raw_cell, RawBocFlags,
+    RawBocHeader, BOC_FLAGS_LEN, CRC_32_ISCSI, GENERIC_BOC_MAGIC,
+};
+use crate::cell::{BocSerializeOptions, MapTonCellError, RawBagOfCells, RawCell, TonCellError};
+
+/// Size of chunks produced by `RawBocEncoder`
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Upper bound for cells preallocated before they are actually read
+const MAX_PREALLOCATED_CELLS: usize = 64 * 1024;
+
+/// Root list and index are fed in chunks of at most this size, so untrusted header
+/// counters can't make readers allocate more than the input actually contains
+const MAX_LISTS_CHUNK: usize = 64 * 1024;
+
+#[derive(Debug, Clone, Copy)]
+enum DecoderState {
+    Flags,
+    Counters(RawBocFlags),
+    /// Remaining length of root list and index
+    Lists(usize),
+    CellDescriptor,
+    CellBody([u8; 2], usize),
+    Crc32c,
+    Done,
+}
+
+/// Incremental BoC decoder, independent of the source of bytes.
+///
+/// Callers feed exactly `needed()` bytes at a time until it returns zero.
+pub(crate) struct RawBocDecoder {
+    state: DecoderState,
+    header_bytes: Vec<u8>,
+    header: Option<RawBocHeader>,
+    cells: Vec<RawCell>,
+    absent: usize,
+    offset: usize,
+    digest: Option<Digest<'static, u32>>,
+}
+
+impl RawBocDecoder {
+    pub(crate) fn new() -> Self {
+        Self {
+            state: DecoderState::Flags,
+            header_bytes: Vec::with_capacity(BOC_FLAGS_LEN),
+            header: None,
+            cells: vec![],
+            absent: 0,
+            offset: 0,
+            digest: None,
+        }
+    }
+
+    /// Number of bytes expected by the next `feed`, zero once BoC is complete
+    pub(crate) fn needed(&self) -> usize {
+        match self.state {
+            DecoderState::Flags => BOC_FLAGS_LEN,
+            DecoderState::Counters(flags) => flags.counters_len(),
+            DecoderState::Lists(remaining) => remaining.min(MAX_LISTS_CHUNK),
+            DecoderState::CellDescriptor => 2,
+            DecoderState::CellBody(_, len) => len,
+            DecoderState::Crc32c => 4,
+            DecoderState::Done => 0,
+        }
+    }
+
+    pub(crate) fn feed(&mut self, bytes: &[u8]) -> Result<(), TonCellError> {
+        if bytes.len() != self.needed() {
+            return Err(TonCellError::boc_deserialization_error(format!(
+                "Expected {} bytes, got {}",
+                self.needed(),
+                bytes.len()
+            )));
+        }
+        match self.state {
+            DecoderState::Flags => {
+                self.header_bytes.extend_from_slice(bytes);
+                let mut reader = ByteReader::endian(bytes, BigEndian);
+                let flags = RawBocFlags::read(&mut reader)?;
+                self.state = DecoderState::Counters(flags);
+            }
+            DecoderState::Counters(flags) => {
+                self.header_bytes.extend_from_slice(bytes);
+                let mut reader = ByteReader::endian(bytes, BigEndian);
+                let cells = read_var_size(&mut reader, flags.size)?;
+                let roots = read_var_size(&mut reader, flags.size)?;
+                match flags.lists_len(cells, roots) {
+                    0 => self.start_cells()?,
+                    len => self.state = DecoderState::Lists(len),
+                }
+            }
+            DecoderState::Lists(remaining) => {
+                self.header_bytes.extend_from_slice(bytes);
+                match remaining - bytes.len() {
+                    0 => self.start_cells()?,
+                    remaining => self.state = DecoderState::Lists(remaining),
+                }
+            }
+            DecoderState::CellDescriptor => {
+                let descriptor = [bytes[0], bytes[1]];
+                match serialized_cell_len(bytes[0], bytes[1], self.size()) - 2 {
+                    0 => self.read_cell(descriptor, &[])?,
+                    len => self.state = DecoderState::CellBody(descriptor, len),
+                }
+            }
+            DecoderState::CellBody(descriptor, _) => self.read_cell(descriptor, bytes)?,
+            DecoderState::Crc32c => {
+                let expected = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+                let actual = self.digest.take().map(|d| d.finalize()).unwrap_or_default();
+                if expected != actual {
+                    return Err(TonCellError::BagOfCellsCrcMismatch { expected, actual });
+                }
+                self.state = DecoderState::Done;
+            }
+            DecoderState::Done => {}
+        }
+        Ok(())
+    }
+
+    pub(crate) fn finish(self) -> Result<RawBagOfCells, TonCellError> {
+        match (self.state, self.header) {
+            (DecoderState::Done, Some(header)) => Ok(RawBagOfCells {
+                cells: self.cells,
+                roots: header.roots,
+            }),
+            _ => Err(TonCellError::boc_deserialization_error("BoC is incomplete")),
+        }
+    }
+
+    fn size(&self) -> u8 {
+        self.header.as_ref().map(|h| h.size).unwrap_or_default()
+    }
+
+    fn start_cells(&mut self) -> Result<(), TonCellError> {
+        let header_bytes = std::mem::take(&mut self.header_bytes);
+        let header = RawBocHeader::read(&mut ByteReader::endian(&header_bytes[..], BigEndian))?;
+        if header.has_crc32c {
+            let mut digest = CRC_32_ISCSI.digest();
+            digest.update(&header_bytes);
+            self.digest = Some(digest);
+        }
+        self.cells = Vec::with_capacity(header.cells.min(MAX_PREALLOCATED_CELLS));
+        let has_cells = header.cells > 0;
+        self.header = Some(header);
+        if has_cells {
+            self.state = DecoderState::CellDescriptor;
+            Ok(())
+        } else {
+            self.finish_cells()
+        }
+    }
+
+    fn read_cell(&mut self, descriptor: [u8; 2], body: &[u8]) -> Result<(), TonCellError> {
+        if let Some(digest) = self.digest.as_mut() {
+            digest.update(&descriptor);
+            digest.update(body);
+        }
+        let mut reader = ByteReader::endian(Cursor::new(descriptor).chain(body), BigEndian);
+        let (cell, is_absent) = read_cell(&mut reader, self.size())?;
+        self.cells.push(cell);
+        self.absent += is_absent as usize;
+        self.offset += descriptor.len() + body.len();
+
+        let header = self.header.as_ref().unwrap(); // unwrap is safe: cells are read after header
+        let cell_index = self.cells.len() - 1;
+        match header.index.get(cell_index) {
+            Some(&entry) if entry != self.offset => {
+                return Err(TonCellError::boc_deserialization_error(format!(
+                    "Index entry {entry} of cell {cell_index} doesn't match its end offset {}",
+                    self.offset
+                )));
+            }
+            _ => {}
+        }
+        if self.cells.len() == header.cells {
+            self.finish_cells()
+        } else {
+            self.state = DecoderState::CellDescriptor;
+            Ok(())
+        }
+    }
+
+    fn finish_cells(&mut self) -> Result<(), TonCellError> {
+        let header = self.header.as_ref().unwrap(); // unwrap is safe: cells are read after header
+        if self.offset != header.tot_cells_size {
+            return Err(TonCellError::boc_deserialization_error(format!(
+                "Cells take {} bytes, but header declares {}",
+                self.offset, header.tot_cells_size
+            )));
+        }
+        if self.absent != header.absent {
+            return Err(TonCellError::boc_deserialization_error(format!(
+                "Header declares {} absent cells, got {}",
+                header.absent, self.absent
+            )));
+        }
+        self.state = if header.has_crc32c {
+            DecoderState::Crc32c
+        } else {
+            DecoderState::Done
+        };
+        Ok(())
+    }
+}
+
+/// Incremental BoC encoder, produces serialized BoC chunk by chunk
+pub(crate) struct RawBocEncoder<'a> {
+    raw: &'a RawBagOfCells,
+    num_ref_bytes: u32,
+    total_size: usize,
+    header: Option<Vec<u8>>,
+    chunk: Vec<u8>,
+    next_cell: usize,
+    digest: Option<Digest<'static, u32>>,
+}
+
+impl<'a> RawBocEncoder<'a> {
+    pub(crate) fn new(
+        raw: &'a RawBagOfCells,
+        options: &BocSerializeOptions,
+    ) -> Result<Self, TonCellError> {
+        //Based on https://github.com/toncenter/tonweb/blob/c2d5d0fc23d2aec55a0412940ce6e580344a288c/src/boc/Cell.js#L198
+
+        let root_count = raw.roots.len();
+        let num_ref_bits = 32 - (raw.cells.len() as u32).leading_zeros();
+        let num_ref_bytes = num_ref_bits.div_ceil(8);
+        let has_idx = options.has_idx;
+        let has_crc32 = options.has_crc32c;
+        let has_cache_bits = options.has_cache_bits;
+        if has_cache_bits && !has_idx {
+            return Err(TonCellError::boc_serialization_error(
+                "Cache bits require index",
+            ));
+        }
+
+        let absent_count = raw.cells.iter().filter(|cell| cell.is_absent).count();
+        let mut full_size = 0u32;
+
+        for cell in &raw.cells {
+            full_size += raw_cell_size(cell, num_ref_bytes);
+        }
+
+        // index entries with cache bits are shifted by one bit
+        let max_offset = if has_cache_bits {
+            full_size * 2 + 1
+        } else {
+            full_size
+        };
+        let num_offset_bits = 32 - max_offset.leading_zeros();
+        let num_offset_bytes = num_offset_bits.div_ceil(8);
+
+        let header_size = 4 + // magic
+            1 + // flags and s_bytes
+            1 + // offset_bytes
+            3 * num_ref_bytes + // cells_num, roots, complete
+            num_offset_bytes + // full_size
+            root_count as u32 * num_ref_bytes + // root_idx
+            (if has_idx { raw.cells.len() as u32 * num_offset_bytes } else { 0 });
+        let total_size = header_size + full_size + (if has_crc32 { 4 } else { 0 });
+
+        let mut writer = BitWriter::endian(Vec::with_capacity(header_size as usize), BigEndian);
+
+        writer
+            .write_var(32, GENERIC_BOC_MAGIC)
+            .map_boc_serialization_error()?;
+
+        //write flags byte
+        let flags: u8 = 0;
+        writer.write_bit(has_idx).map_boc_serialization_error()?;
+        writer.write_bit(has_crc32).map_boc_serialization_error()?;
+        writer
+            .write_bit(has_cache_bits)
+            .map_boc_serialization_error()?;
+        writer.write_var(2, flags).map_boc_serialization_error()?;
+        writer
+            .write_var(3, num_ref_bytes)
+            .map_boc_serialization_error()?;
+        writer
+            .write_var(8, num_offset_bytes)
+            .map_boc_serialization_error()?;
+        writer
+            .write_var(8 * num_ref_bytes, raw.cells.len() as u32)
+            .map_boc_serialization_error()?;
+        writer
+            .write_var(8 * num_ref_bytes, root_count as u32)
+            .map_boc_serialization_error()?;
+        writer
+            .write_var(8 * num_ref_bytes, absent_count as u32)
+            .map_boc_serialization_error()?;
+        writer
+            .write_var(8 * num_offset_bytes, full_size)
+            .map_boc_serialization_error()?;
+        for &root in &raw.roots {
+            writer
+                .write_var(8 * num_ref_bytes, root as u32)
+                .map_boc_serialization_error()?;
+        }
+
+        if has_idx {
+            let mut parents = vec![0u32; raw.cells.len()];
+            for cell in &raw.cells {
+                for &r in &cell.references {
+                    parents[r] += 1;
+                }
+            }
+            let mut offset = 0u32;
+            for (cell, parents) in raw.cells.iter().zip(parents) {
+                offset += raw_cell_size(cell, num_ref_bytes);
+                // cells with several parents are worth caching by deserializer
+                let entry = if has_cache_bits {
+                    offset * 2 + (parents > 1) as u32
+                } else {
+                    offset
+                };
+                writer
+                    .write_var(8 * num_offset_bytes, entry)
+                    .map_boc_serialization_error()?;
+            }
+        }
+        let header = writer.into_writer();
+
+        Ok(Self {
+            raw,
+            num_ref_bytes,
+            total_size: total_size as usize,
+            header: Some(header),
+            chunk: Vec::with_capacity(CHUNK_SIZE),
+            next_cell: 0,
+            digest: has_crc32.then(|| CRC_32_ISCSI.digest()),
+        })
+    }
+
+    /// Length of the whole serialized BoC
+    pub(crate) fn total_size(&self) -> usize {
+        self.total_size
+    }
+
+    /// Returns the next chunk of serialized BoC, `None` when it's fully written
+    pub(crate) fn next_chunk(&mut self) -> Result<Option<&[u8]>, TonCellError> {
+        if let Some(header) = self.header.take() {
+            self.chunk = header;
+        } else if self.next_cell < self.raw.cells.len() {
+            self.chunk.clear();
+            let mut writer = BitWriter::endian(&mut self.chunk, BigEndian);
+            while self.next_cell < self.raw.cells.len() {
+                write_raw_cell(
+                    &mut writer,
+                    &self.raw.cells[self.next_cell],
+                    self.num_ref_bytes,
+                )?;
+                self.next_cell += 1;
+                if writer
+                    .writer()
+                    .is_some_and(|chunk| chunk.len() >= CHUNK_SIZE)
+                {
+                    break;
+                }
+            }
+        } else if let Some(digest) = self.digest.take() {
+            self.chunk.clear();
+            self.chunk
+                .extend_from_slice(&digest.finalize().to_le_bytes());
+            return Ok(Some(&self.chunk));
+        } else {
+            return Ok(None);
+        }
+        if let Some(digest) = self.digest.as_mut() {
+            digest.update(&self.chunk);
+        }
+        Ok(Some(&self.chunk))
+    }
+}
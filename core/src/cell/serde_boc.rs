@@ -0,0 +1,169 @@
+//! Serde support for cells as BoC strings.
+//!
+//! `Cell`, `ArcCell` and `BagOfCells` are serialized as base64 BoC by default.
+//! Use `#[serde(with = "tonlib_core::cell::serde_boc::hex")]` to get hex BoC instead.
+use std::sync::Arc;
+
+use serde::de::Error as _;
+use serde::ser::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::cell::{ArcCell, BagOfCells, Cell, TonCellError};
+
+/// Value which can be stored as single BoC
+pub trait BocRepr: Sized {
+    fn to_boc_bytes(&self) -> Result<Vec<u8>, TonCellError>;
+    fn from_boc_bytes(bytes: &[u8]) -> Result<Self, TonCellError>;
+}
+
+impl BocRepr for Cell {
+    fn to_boc_bytes(&self) -> Result<Vec<u8>, TonCellError> {
+        BagOfCells::from_root(self.clone()).serialize(false)
+    }
+
+    fn from_boc_bytes(bytes: &[u8]) -> Result<Self, TonCellError> {
+        let root = BagOfCells::parse(bytes)?.single_root()?;
+        Ok(Arc::unwrap_or_clone(root))
+    }
+}
+
+impl BocRepr for ArcCell {
+    fn to_boc_bytes(&self) -> Result<Vec<u8>, TonCellError> {
+        BagOfCells::new(std::slice::from_ref(self)).serialize(false)
+    }
+
+    fn from_boc_bytes(bytes: &[u8]) -> Result<Self, TonCellError> {
+        BagOfCells::parse(bytes)?.single_root()
+    }
+}
+
+impl BocRepr for BagOfCells {
+    fn to_boc_bytes(&self) -> Result<Vec<u8>, TonCellError> {
+        self.serialize(false)
+    }
+
+    fn from_boc_bytes(bytes: &[u8]) -> Result<Self, TonCellError> {
+        BagOfCells::parse(bytes)
+    }
+}
+
+/// Base64 BoC, the format used by TON HTTP API
+pub mod base64 {
+    use ::base64::engine::general_purpose::STANDARD;
+    use ::base64::Engine;
+
+    use super::*;
+
+    pub fn serialize<T: BocRepr, S: Serializer>(
+        value: &T,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let boc = value.to_boc_bytes().map_err(S::Error::custom)?;
+        serializer.serialize_str(&STANDARD.encode(boc))
+    }
+
+    pub fn deserialize<'de, T: BocRepr, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<T, D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        let boc = STANDARD.decode(encoded).map_err(D::Error::custom)?;
+        T::from_boc_bytes(&boc).map_err(D::Error::custom)
+    }
+}
+
+/// Hex BoC
+pub mod hex {
+    use super::*;
+
+    pub fn serialize<T: BocRepr, S: Serializer>(
+        value: &T,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let boc = value.to_boc_bytes().map_err(S::Error::custom)?;
+        serializer.serialize_str(&::hex::encode(boc))
+    }
+
+    pub fn deserialize<'de, T: BocRepr, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<T, D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        let boc = ::hex::decode(encoded).map_err(D::Error::custom)?;
+        T::from_boc_bytes(&boc).map_err(D::Error::custom)
+    }
+}
+
+impl Serialize for Cell {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        base64::serialize(self, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Cell {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        base64::deserialize(deserializer)
+    }
+}
+
+impl Serialize for BagOfCells {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        base64::serialize(self, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for BagOfCells {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        base64::deserialize(deserializer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ::base64::engine::general_purpose::STANDARD;
+    use ::base64::Engine;
+    use serde::{Deserialize, Serialize};
+
+    use crate::cell::{ArcCell, BagOfCells, Cell, CellBuilder};
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Fields {
+        cell: Cell,
+        arc_cell: ArcCell,
+        #[serde(with = "crate::cell::serde_boc::hex")]
+        hex_cell: ArcCell,
+        #[serde(with = "crate::cell::serde_boc::hex")]
+        boc: BagOfCells,
+    }
+
+    #[test]
+    fn test_serde_boc() -> anyhow::Result<()> {
+        let child = CellBuilder::new().store_u32(32, 0xc0de)?.build()?;
+        let cell = CellBuilder::new()
+            .store_u8(8, 42)?
+            .store_child(child)?
+            .build()?;
+        let fields = Fields {
+            cell: cell.clone(),
+            arc_cell: cell.clone().to_arc(),
+            hex_cell: cell.clone().to_arc(),
+            boc: BagOfCells::from_root(cell.clone()),
+        };
+
+        let json = serde_json::to_value(&fields)?;
+        let base64 = STANDARD.encode(BagOfCells::from_root(cell.clone()).serialize(false)?);
+        let hex = hex::encode(BagOfCells::from_root(cell).serialize(false)?);
+        assert_eq!(json["cell"], base64);
+        assert_eq!(json["arc_cell"], base64);
+        assert_eq!(json["hex_cell"], hex);
+        assert_eq!(json["boc"], hex);
+
+        let parsed: Fields = serde_json::from_value(json)?;
+        assert_eq!(parsed, fields);
+        Ok(())
+    }
+
+    #[test]
+    fn test_serde_boc_invalid() {
+        assert!(serde_json::from_str::<Cell>("\"not a boc\"").is_err());
+        assert!(serde_json::from_str::<Cell>("42").is_err());
+    }
+}
@@ -6,7 +6,9 @@ use base64::prelude::BASE64_STANDARD;
 use base64::Engine;
 
 use crate::cell::raw_boc_from_boc::convert_to_raw_boc;
-use crate::cell::{BagOfCells, CellBuilder, TonCellError};
+use crate::cell::{
+    BagOfCells, BocCellOrder, BocSerializeOptions, CellBuilder, LazyBoc, TonCellError,
+};
 use crate::message::ZERO_COINS;
 
 #[test]
@@ -216,7 +218,7 @@ fn it_constructs_raw() -> Result<(), TonCellError> {
         .store_child(inter)?
         .build()?;
     let boc = BagOfCells::from_root(root);
-    let _raw = convert_to_raw_boc(&boc)?;
+    let _raw = convert_to_raw_boc(&boc, BocCellOrder::BreadthFirst)?;
     Ok(())
 }
 
@@ -301,12 +303,273 @@ fn many_cells() {
     typical_boc_test(entry, expected_hash);
 }
 
+#[test]
+fn serialize_with_options_round_trip() -> Result<(), TonCellError> {
+    let bytes = BASE64_STANDARD.decode(ACCOUNT_STATE_BOC).unwrap();
+    let boc = BagOfCells::parse(&bytes)?;
+    let hash = boc.single_root()?.cell_hash();
+    let boc = BagOfCells::parse(&bytes)?;
+
+    for cell_order in [BocCellOrder::BreadthFirst, BocCellOrder::DepthFirst] {
+        for (has_idx, has_cache_bits) in [(false, false), (true, false), (true, true)] {
+            for has_crc32c in [false, true] {
+                let options = BocSerializeOptions {
+                    has_idx,
+                    has_crc32c,
+                    has_cache_bits,
+                    cell_order,
+                };
+                let serialized = boc.serialize_with_options(&options)?;
+                let parsed = BagOfCells::parse(&serialized)?.single_root()?;
+                assert_eq!(parsed.cell_hash(), hash, "{options:?}");
+            }
+        }
+    }
+
+    let default = boc.serialize_with_options(&BocSerializeOptions::default())?;
+    assert_eq!(default, boc.serialize(false)?);
+    Ok(())
+}
+
+#[test]
+fn serialize_cache_bits_without_index() {
+    let boc = BagOfCells::from_root(CellBuilder::new().build().unwrap());
+    let options = BocSerializeOptions {
+        has_cache_bits: true,
+        ..Default::default()
+    };
+    assert!(matches!(
+        boc.serialize_with_options(&options),
+        Err(TonCellError::BagOfCellsSerializationError(_))
+    ));
+}
+
+#[test]
+fn parse_verifies_crc32c() -> Result<(), TonCellError> {
+    let boc = BagOfCells::parse_base64(ACCOUNT_STATE_BOC)?;
+    let mut serialized = boc.serialize(true)?;
+    let last = serialized.len() - 5;
+    serialized[last] ^= 1;
+    assert!(matches!(
+        BagOfCells::parse(&serialized),
+        Err(TonCellError::BagOfCellsCrcMismatch { .. })
+    ));
+    Ok(())
+}
+
+#[test]
+fn parse_verifies_index() -> Result<(), TonCellError> {
+    let boc = BagOfCells::parse_base64(ACCOUNT_STATE_BOC)?;
+    let options = BocSerializeOptions {
+        has_idx: true,
+        ..Default::default()
+    };
+    let mut serialized = boc.serialize_with_options(&options)?;
+    // header takes 10 bytes, followed by a single byte root index
+    let first_index_entry = 11;
+    serialized[first_index_entry] ^= 1;
+    assert!(matches!(
+        BagOfCells::parse(&serialized),
+        Err(TonCellError::BagOfCellsDeserializationError(_))
+    ));
+    Ok(())
+}
+
+#[test]
+fn parse_absent_cells() -> Result<(), TonCellError> {
+    let child = CellBuilder::new().store_u32(32, 0xdeadbeef)?.build()?;
+    let root = CellBuilder::new()
+        .store_u8(8, 1)?
+        .store_child(child.clone())?
+        .build()?;
+
+    let mut serialized = vec![
+        0xb5, 0xee, 0x9c, 0x72, // magic
+        0x01, // size
+        0x01, // off_bytes
+        0x02, // cells
+        0x01, // roots
+        0x01, // absent
+        0x28, // tot_cells_size
+        0x00, // root index
+        0x01, 0x02, 0x01, 0x01, // root: one reference and one data byte
+        0x17, 0x00, // absent cell: 7 references and hashes
+    ];
+    serialized.extend_from_slice(child.cell_hash().as_slice());
+    serialized.extend_from_slice(&child.cell_depth().to_be_bytes());
+
+    let parsed = BagOfCells::parse(&serialized)?.single_root()?;
+    assert_eq!(parsed.cell_hash(), root.cell_hash());
+    assert_eq!(parsed.cell_depth(), root.cell_depth());
+    let absent = parsed.reference(0)?;
+    assert!(absent.is_absent());
+    assert!(absent.data().is_empty());
+    assert_eq!(absent.cell_hash(), child.cell_hash());
+
+    let lazy = LazyBoc::parse(&serialized)?;
+    assert!(lazy.root(0)?.reference(0)?.is_absent());
+    assert_eq!(lazy.root(0)?.to_cell()?.cell_hash(), root.cell_hash());
+
+    // absent cell is written back as it was read
+    let reserialized = BagOfCells::from_root(parsed.as_ref().clone()).serialize(false)?;
+    assert_eq!(reserialized, serialized);
+
+    // absent counter must match the number of absent cells
+    serialized[8] = 0;
+    assert!(BagOfCells::parse(&serialized).is_err());
+    Ok(())
+}
+
+#[test]
+fn parse_absent_cells_with_level() -> Result<(), TonCellError> {
+    let pruned_hash = [0xab; 32];
+    let mut builder = CellBuilder::new();
+    builder.set_cell_is_exotic(true);
+    let pruned = builder
+        .store_u8(8, 1)?
+        .store_u8(8, 1)?
+        .store_slice(&pruned_hash)?
+        .store_u16(16, 5)?
+        .build()?;
+    let child = CellBuilder::new().store_child(pruned)?.build()?;
+    let root = CellBuilder::new().store_child(child.clone())?.build()?;
+
+    let mut serialized = vec![
+        0xb5, 0xee, 0x9c, 0x72, // magic
+        0x01, // size
+        0x01, // off_bytes
+        0x02, // cells
+        0x01, // roots
+        0x01, // absent
+        0x49, // tot_cells_size
+        0x00, // root index
+        0x21, 0x00, 0x01, // root: level 1 and one reference
+        0x37, 0x00, // absent cell of level 1: two hashes and depths
+    ];
+    serialized.extend_from_slice(child.get_hash(0).as_slice());
+    serialized.extend_from_slice(child.get_hash(1).as_slice());
+    serialized.extend_from_slice(&child.get_depth(0).to_be_bytes());
+    serialized.extend_from_slice(&child.get_depth(1).to_be_bytes());
+
+    let parsed = BagOfCells::parse(&serialized)?.single_root()?;
+    for level in 0..4 {
+        assert_eq!(parsed.get_hash(level), root.get_hash(level));
+        assert_eq!(parsed.get_depth(level), root.get_depth(level));
+    }
+    let reserialized = BagOfCells::from_root(parsed.as_ref().clone()).serialize(false)?;
+    assert_eq!(reserialized, serialized);
+    Ok(())
+}
+
+#[test]
+fn parse_indexed_magic() -> Result<(), TonCellError> {
+    let child = CellBuilder::new().store_u8(8, 0xaa)?.build()?;
+    let root = CellBuilder::new().store_child(child)?.build()?;
+    let serialized = vec![
+        0x68, 0xff, 0x65, 0xf3, // serialized_boc_idx
+        0x01, // size
+        0x01, // off_bytes
+        0x02, // cells
+        0x01, // roots
+        0x00, // absent
+        0x06, // tot_cells_size
+        0x03, 0x06, // index
+        0x01, 0x00, 0x01, // root
+        0x00, 0x02, 0xaa, // child
+    ];
+    let parsed = BagOfCells::parse(&serialized)?.single_root()?;
+    assert_eq!(parsed.cell_hash(), root.cell_hash());
+    Ok(())
+}
+
+/// Chain of cells large enough to be written in several chunks
+fn long_chain_boc() -> Result<BagOfCells, TonCellError> {
+    let mut cell = CellBuilder::new().build()?;
+    for i in 0..1000u32 {
+        cell = CellBuilder::new()
+            .store_u32(32, i)?
+            .store_slice(&[0xa5; 120])?
+            .store_child(cell)?
+            .build()?;
+    }
+    Ok(BagOfCells::from_root(cell))
+}
+
+#[test]
+fn stream_round_trip() -> Result<(), TonCellError> {
+    let boc = long_chain_boc()?;
+    let hash = boc.root(0)?.cell_hash();
+    for options in [
+        BocSerializeOptions::default(),
+        BocSerializeOptions {
+            has_idx: true,
+            has_crc32c: true,
+            has_cache_bits: true,
+            cell_order: BocCellOrder::DepthFirst,
+        },
+    ] {
+        let mut written = vec![];
+        boc.write(&mut written, &options)?;
+        assert_eq!(written, boc.serialize_with_options(&options)?);
+
+        let read = BagOfCells::read(written.as_slice())?;
+        assert_eq!(read.single_root()?.cell_hash(), hash);
+    }
+    Ok(())
+}
+
+#[test]
+fn stream_read_errors() -> Result<(), TonCellError> {
+    let boc = BagOfCells::parse_base64(ACCOUNT_STATE_BOC)?;
+    let mut serialized = boc.serialize(true)?;
+    assert!(BagOfCells::read(&serialized[..serialized.len() - 1]).is_err());
+
+    let last = serialized.len() - 5;
+    serialized[last] ^= 1;
+    assert!(matches!(
+        BagOfCells::read(serialized.as_slice()),
+        Err(TonCellError::BagOfCellsCrcMismatch { .. })
+    ));
+
+    // header declares 2^32 - 1 cells with 4-byte index entries, but the input ends right after it
+    let mut huge_index = vec![0xb5, 0xee, 0x9c, 0x72, 0x84, 0x04];
+    huge_index.extend_from_slice(&u32::MAX.to_be_bytes());
+    huge_index.extend_from_slice(&[0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+    assert!(BagOfCells::read(huge_index.as_slice()).is_err());
+    assert!(BagOfCells::parse(&huge_index).is_err());
+    Ok(())
+}
+
+#[cfg(feature = "tokio")]
+#[test]
+fn stream_async_round_trip() -> Result<(), TonCellError> {
+    tokio_test::block_on(async {
+        let boc = long_chain_boc()?;
+        let options = BocSerializeOptions {
+            has_crc32c: true,
+            ..Default::default()
+        };
+        let mut written = vec![];
+        boc.write_async(&mut written, &options).await?;
+        assert_eq!(written, boc.serialize(true)?);
+
+        let read = BagOfCells::read_async(written.as_slice()).await?;
+        assert_eq!(read, boc);
+        Ok(())
+    })
+}
+
+const ACCOUNT_STATE_BOC: &str = "te6ccgEBBAEArwAJRgPIr248LcbQSSCsDD5Rb27WLhRGYiTEGG+uChgAAXoNHAAIASJxwAtrH/x8t+GjDO5/X/f1fk4Rw3oYx+9S1gRE8vya04qzwiyFkEMdYglgAAAaNN8fbBluIJfFw9NAAgMoSAEB/rX/aCDi/w2Ug+fg1iyBfYRniftK5YDIeIZtlZ2r1cAAByhIAQEg0z54hgTX/ohMEnHs6qluCydagWgxQoxSyLwK8qfAOQAA";
+
 fn typical_boc_test(entry: &str, expected_hash: &str) {
     let bytes_entry = BASE64_STANDARD.decode(entry).unwrap();
     let boc = BagOfCells::parse(&bytes_entry).unwrap();
     assert_eq!(boc.roots.len(), 1);
     let hash = boc.roots[0].cell_hash().to_hex();
     assert_eq!(hash, expected_hash);
+    let lazy = LazyBoc::parse(&bytes_entry).unwrap();
+    let lazy_hash = lazy.single_root().unwrap().to_cell().unwrap().cell_hash();
+    assert_eq!(lazy_hash.to_hex(), expected_hash);
     let bytes_entry_again = boc.serialize(false).unwrap();
     let boc_again = BagOfCells::parse(&bytes_entry_again).unwrap();
     let hash_again = boc_again.roots[0].cell_hash().to_hex();
@@ -0,0 +1,563 @@
+//! Human-readable JSON for `Message`, `StateInit` and `JettonTransferMessage`.
+//!
+//! Field names follow TON HTTP API (toncenter v3): addresses are raw `wc:hex` strings
+//! (`null` for `addr_none`), amounts and logical times are decimal strings,
+//! cells are base64 BoC. Fields stored as `Either X ^X` are followed by optional `layout`
+//! (`"inline"` or `"ref"`), so parsed messages are serialized back to the same cells.
+use std::str::FromStr;
+
+use serde::de::Error as _;
+use serde::ser::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::cell::{ArcCell, TonCellError};
+use crate::tlb_types::block::coins::{CurrencyCollection, Grams};
+use crate::tlb_types::block::message::{
+    CommonMsgInfo, ExtInMsgInfo, ExtOutMsgInfo, IntMsgInfo, Message,
+};
+use crate::tlb_types::block::msg_address::{MsgAddrNone, MsgAddress, MsgAddressExt, MsgAddressInt};
+use crate::tlb_types::block::state_init::{StateInit, TickTock};
+use crate::tlb_types::primitives::either::{EitherRef, EitherRefLayout};
+use crate::tlb_types::primitives::reference::Ref;
+use crate::tlb_types::tep::jetton::JettonTransferMessage;
+use crate::TonAddress;
+
+#[derive(Serialize, Deserialize)]
+struct MessageJson {
+    source: Option<String>,
+    destination: Option<String>,
+    value: Option<String>,
+    fwd_fee: Option<String>,
+    ihr_fee: Option<String>,
+    import_fee: Option<String>,
+    created_lt: Option<String>,
+    created_at: Option<String>,
+    ihr_disabled: Option<bool>,
+    bounce: Option<bool>,
+    bounced: Option<bool>,
+    message_content: MessageContentJson,
+    init_state: Option<InitStateJson>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct MessageContentJson {
+    body: ArcCell,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    layout: Option<LayoutJson>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct InitStateJson {
+    #[serde(with = "crate::tlb_types::serde_tlb::base64")]
+    body: StateInit,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    layout: Option<LayoutJson>,
+}
+
+/// Layout of `EitherRef`, missing for `EitherRefLayout::Native`
+#[derive(Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+enum LayoutJson {
+    Inline,
+    Ref,
+}
+
+#[derive(Serialize, Deserialize)]
+struct StateInitJson {
+    split_depth: Option<u8>,
+    special: Option<TickTockJson>,
+    code: Option<ArcCell>,
+    data: Option<ArcCell>,
+    library: Option<ArcCell>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct TickTockJson {
+    tick: bool,
+    tock: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+struct JettonTransferJson {
+    query_id: String,
+    amount: String,
+    destination: Option<String>,
+    response_destination: Option<String>,
+    custom_payload: Option<ArcCell>,
+    forward_ton_amount: String,
+    forward_payload: ArcCell,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    forward_payload_layout: Option<LayoutJson>,
+}
+
+impl TryFrom<&Message> for MessageJson {
+    type Error = TonCellError;
+
+    fn try_from(msg: &Message) -> Result<Self, Self::Error> {
+        let mut json = MessageJson {
+            source: None,
+            destination: None,
+            value: None,
+            fwd_fee: None,
+            ihr_fee: None,
+            import_fee: None,
+            created_lt: None,
+            created_at: None,
+            ihr_disabled: None,
+            bounce: None,
+            bounced: None,
+            message_content: MessageContentJson {
+                body: msg.body.value.clone(),
+                layout: layout_to_json(msg.body.layout),
+            },
+            init_state: msg.init.as_ref().map(|init| InitStateJson {
+                body: init.value.clone(),
+                layout: layout_to_json(init.layout),
+            }),
+        };
+        match &msg.info {
+            CommonMsgInfo::Int(info) => {
+                if info.value.other.is_some() {
+                    return Err(TonCellError::InvalidInput(
+                        "Extra currencies are not supported in message JSON".to_string(),
+                    ));
+                }
+                json.source = address_to_json(&info.src)?;
+                json.destination = address_to_json(&info.dest)?;
+                json.value = Some(info.value.grams.amount.to_string());
+                json.fwd_fee = Some(info.fwd_fee.amount.to_string());
+                json.ihr_fee = Some(info.ihr_fee.amount.to_string());
+                json.created_lt = Some(info.created_lt.to_string());
+                json.created_at = Some(info.created_at.to_string());
+                json.ihr_disabled = Some(info.ihr_disabled);
+                json.bounce = Some(info.bounce);
+                json.bounced = Some(info.bounced);
+            }
+            CommonMsgInfo::ExtIn(info) => {
+                json.source = ext_address_to_json(&info.src)?;
+                json.destination = address_to_json(&MsgAddress::Int(info.dest.clone()))?;
+                json.import_fee = Some(info.import_fee.amount.to_string());
+            }
+            CommonMsgInfo::ExtOut(info) => {
+                json.source = address_to_json(&MsgAddress::Int(info.src.clone()))?;
+                json.destination = ext_address_to_json(&info.dest)?;
+                json.created_lt = Some(info.created_lt.to_string());
+                json.created_at = Some(info.created_at.to_string());
+            }
+        }
+        Ok(json)
+    }
+}
+
+impl TryFrom<MessageJson> for Message {
+    type Error = TonCellError;
+
+    // internal messages always have `value`, external incoming ones have no `source`
+    fn try_from(json: MessageJson) -> Result<Self, Self::Error> {
+        let info = if json.value.is_some() {
+            CommonMsgInfo::Int(IntMsgInfo {
+                ihr_disabled: json.ihr_disabled.unwrap_or(true),
+                bounce: json.bounce.unwrap_or(false),
+                bounced: json.bounced.unwrap_or(false),
+                src: address_from_json(json.source.as_deref())?,
+                dest: address_from_json(json.destination.as_deref())?,
+                value: CurrencyCollection::new(parse_field(&json.value, "value")?),
+                ihr_fee: Grams::new(parse_field_or_default(&json.ihr_fee, "ihr_fee")?),
+                fwd_fee: Grams::new(parse_field_or_default(&json.fwd_fee, "fwd_fee")?),
+                created_lt: parse_field_or_default(&json.created_lt, "created_lt")?,
+                created_at: parse_field_or_default(&json.created_at, "created_at")?,
+            })
+        } else if json.source.is_none() {
+            CommonMsgInfo::ExtIn(ExtInMsgInfo {
+                src: MsgAddressExt::None(MsgAddrNone {}),
+                dest: address_int_from_json(json.destination.as_deref(), "destination")?,
+                import_fee: Grams::new(parse_field_or_default(&json.import_fee, "import_fee")?),
+            })
+        } else {
+            if json.destination.is_some() {
+                return Err(TonCellError::InvalidInput(
+                    "External outbound message destination must be null".to_string(),
+                ));
+            }
+            CommonMsgInfo::ExtOut(ExtOutMsgInfo {
+                src: address_int_from_json(json.source.as_deref(), "source")?,
+                dest: MsgAddressExt::None(MsgAddrNone {}),
+                created_lt: parse_field_or_default(&json.created_lt, "created_lt")?,
+                created_at: parse_field_or_default(&json.created_at, "created_at")?,
+            })
+        };
+        Ok(Message {
+            info,
+            init: json
+                .init_state
+                .map(|init| either_ref_from_json(init.body, init.layout)),
+            body: either_ref_from_json(json.message_content.body, json.message_content.layout),
+        })
+    }
+}
+
+impl From<&StateInit> for StateInitJson {
+    fn from(state_init: &StateInit) -> Self {
+        StateInitJson {
+            split_depth: state_init.split_depth,
+            special: state_init.tick_tock.as_ref().map(|tt| TickTockJson {
+                tick: tt.tick,
+                tock: tt.tock,
+            }),
+            code: state_init.code.as_ref().map(|code| code.0.clone()),
+            data: state_init.data.as_ref().map(|data| data.0.clone()),
+            library: state_init.library.as_ref().map(|lib| lib.0.clone()),
+        }
+    }
+}
+
+impl From<StateInitJson> for StateInit {
+    fn from(json: StateInitJson) -> Self {
+        StateInit {
+            split_depth: json.split_depth,
+            tick_tock: json.special.map(|tt| TickTock {
+                tick: tt.tick,
+                tock: tt.tock,
+            }),
+            code: json.code.map(Ref),
+            data: json.data.map(Ref),
+            library: json.library.map(Ref),
+        }
+    }
+}
+
+impl TryFrom<&JettonTransferMessage> for JettonTransferJson {
+    type Error = TonCellError;
+
+    fn try_from(msg: &JettonTransferMessage) -> Result<Self, Self::Error> {
+        Ok(JettonTransferJson {
+            query_id: msg.query_id.to_string(),
+            amount: msg.amount.to_string(),
+            destination: address_to_json(&msg.destination)?,
+            response_destination: address_to_json(&msg.response_destination)?,
+            custom_payload: msg.custom_payload.as_ref().map(|p| p.0.clone()),
+            forward_ton_amount: msg.forward_ton_amount.to_string(),
+            forward_payload: msg.forward_payload.value.clone(),
+            forward_payload_layout: layout_to_json(msg.forward_payload.layout),
+        })
+    }
+}
+
+impl TryFrom<JettonTransferJson> for JettonTransferMessage {
+    type Error = TonCellError;
+
+    fn try_from(json: JettonTransferJson) -> Result<Self, Self::Error> {
+        Ok(JettonTransferMessage {
+            query_id: parse_str(&json.query_id, "query_id")?,
+            amount: parse_str(&json.amount, "amount")?,
+            destination: address_from_json(json.destination.as_deref())?,
+            response_destination: address_from_json(json.response_destination.as_deref())?,
+            custom_payload: json.custom_payload.map(Ref),
+            forward_ton_amount: parse_str(&json.forward_ton_amount, "forward_ton_amount")?,
+            forward_payload: either_ref_from_json(
+                json.forward_payload,
+                json.forward_payload_layout,
+            ),
+        })
+    }
+}
+
+impl Serialize for Message {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        MessageJson::try_from(self)
+            .map_err(S::Error::custom)?
+            .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Message {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        MessageJson::deserialize(deserializer)?
+            .try_into()
+            .map_err(D::Error::custom)
+    }
+}
+
+impl Serialize for StateInit {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        StateInitJson::from(self).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for StateInit {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(StateInitJson::deserialize(deserializer)?.into())
+    }
+}
+
+impl Serialize for JettonTransferMessage {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        JettonTransferJson::try_from(self)
+            .map_err(S::Error::custom)?
+            .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for JettonTransferMessage {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        JettonTransferJson::deserialize(deserializer)?
+            .try_into()
+            .map_err(D::Error::custom)
+    }
+}
+
+fn layout_to_json(layout: EitherRefLayout) -> Option<LayoutJson> {
+    match layout {
+        EitherRefLayout::ToCell => Some(LayoutJson::Inline),
+        EitherRefLayout::ToRef => Some(LayoutJson::Ref),
+        EitherRefLayout::Native => None,
+    }
+}
+
+fn either_ref_from_json<T>(value: T, layout: Option<LayoutJson>) -> EitherRef<T> {
+    let layout = match layout {
+        Some(LayoutJson::Inline) => EitherRefLayout::ToCell,
+        Some(LayoutJson::Ref) => EitherRefLayout::ToRef,
+        None => EitherRefLayout::Native,
+    };
+    EitherRef { value, layout }
+}
+
+/// Only `addr_none` and `addr_std` without anycast can be represented in JSON
+fn address_to_json(address: &MsgAddress) -> Result<Option<String>, TonCellError> {
+    match address {
+        MsgAddress::Ext(ext) => ext_address_to_json(ext),
+        MsgAddress::Int(MsgAddressInt::Std(std)) if std.anycast.is_none() => {
+            let address = TonAddress::from_msg_address(address.clone())
+                .map_err(|err| TonCellError::InvalidInput(err.to_string()))?;
+            Ok(Some(address.to_hex()))
+        }
+        _ => Err(TonCellError::InvalidInput(format!(
+            "Address is not supported in JSON: {address:?}"
+        ))),
+    }
+}
+
+fn ext_address_to_json(address: &MsgAddressExt) -> Result<Option<String>, TonCellError> {
+    match address {
+        MsgAddressExt::None(_) => Ok(None),
+        MsgAddressExt::Extern(_) => Err(TonCellError::InvalidInput(format!(
+            "External address is not supported in JSON: {address:?}"
+        ))),
+    }
+}
+
+fn address_from_json(address: Option<&str>) -> Result<MsgAddress, TonCellError> {
+    match address {
+        None => Ok(MsgAddress::NONE),
+        Some(address) => Ok(MsgAddress::Int(address_int_from_json(
+            Some(address),
+            "address",
+        )?)),
+    }
+}
+
+fn address_int_from_json(address: Option<&str>, name: &str) -> Result<MsgAddressInt, TonCellError> {
+    let address =
+        address.ok_or_else(|| TonCellError::InvalidInput(format!("Missing field: {name}")))?;
+    let address =
+        TonAddress::from_str(address).map_err(|err| TonCellError::InvalidInput(err.to_string()))?;
+    Ok(address.to_msg_address_int())
+}
+
+fn parse_str<T: FromStr>(value: &str, name: &str) -> Result<T, TonCellError> {
+    value
+        .parse()
+        .map_err(|_| TonCellError::InvalidInput(format!("Invalid {name}: {value}")))
+}
+
+fn parse_field<T: FromStr>(value: &Option<String>, name: &str) -> Result<T, TonCellError> {
+    match value {
+        Some(value) => parse_str(value, name),
+        None => Err(TonCellError::InvalidInput(format!("Missing field: {name}"))),
+    }
+}
+
+fn parse_field_or_default<T: FromStr + Default>(
+    value: &Option<String>,
+    name: &str,
+) -> Result<T, TonCellError> {
+    match value {
+        Some(value) => parse_str(value, name),
+        None => Ok(T::default()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use num_bigint::BigUint;
+    use serde_json::json;
+
+    use super::*;
+    use crate::cell::{BagOfCells, CellBuilder};
+    use crate::tlb_types::tlb::TLB;
+
+    const ADDRESS: &str = "EQB3ncyBUTjZUA5EnFKR5_EnOMI9V1tTEAAPaiU71gc4TiUt";
+
+    fn body() -> Result<ArcCell, TonCellError> {
+        Ok(CellBuilder::new().store_u32(32, 0)?.build()?.to_arc())
+    }
+
+    #[test]
+    fn test_message_json_int() -> anyhow::Result<()> {
+        let msg_cell = BagOfCells::parse_hex("b5ee9c720101010100580000ab69fe00000000000000000000000000000000000000000000000000000000000000013fccccccccccccccccccccccccccccccccccccccccccccccccccccccccccccccccd3050ec744000000617bc90dda80cf41ab8e40")?.single_root()?;
+        let message = Message::from_cell(&msg_cell)?;
+
+        let json = serde_json::to_value(&message)?;
+        assert_eq!(
+            json["source"],
+            "-1:0000000000000000000000000000000000000000000000000000000000000000"
+        );
+        assert_eq!(
+            json["destination"],
+            "-1:3333333333333333333333333333333333333333333333333333333333333333"
+        );
+        assert_eq!(json["value"], "3242439121");
+        assert_eq!(json["created_lt"], "53592141000000");
+        assert_eq!(json["created_at"], "1738593735");
+        assert_eq!(json["bounce"], true);
+        assert_eq!(json["init_state"], serde_json::Value::Null);
+
+        let parsed: Message = serde_json::from_value(json)?;
+        assert_eq!(parsed, message);
+        assert_eq!(parsed.to_cell()?, *msg_cell);
+        Ok(())
+    }
+
+    #[test]
+    fn test_message_json_external() -> anyhow::Result<()> {
+        let dest = TonAddress::from_str(ADDRESS)?;
+        let mut message = Message::new(
+            CommonMsgInfo::ExtIn(ExtInMsgInfo {
+                src: MsgAddressExt::None(MsgAddrNone {}),
+                dest: dest.to_msg_address_int(),
+                import_fee: Grams::new(BigUint::from(0u32)),
+            }),
+            body()?,
+        );
+        message.with_state_init(StateInit::new(body()?, body()?));
+
+        let json = serde_json::to_value(&message)?;
+        assert_eq!(json["source"], serde_json::Value::Null);
+        assert_eq!(json["destination"], dest.to_hex());
+        assert_eq!(json["import_fee"], "0");
+        assert_eq!(
+            json["init_state"]["body"],
+            StateInit::new(body()?, body()?).to_boc_b64(false)?
+        );
+        assert_eq!(serde_json::from_value::<Message>(json)?, message);
+
+        let ext_out = Message::new(
+            CommonMsgInfo::ExtOut(ExtOutMsgInfo {
+                src: dest.to_msg_address_int(),
+                dest: MsgAddressExt::None(MsgAddrNone {}),
+                created_lt: 7,
+                created_at: 8,
+            }),
+            body()?,
+        );
+        let json = serde_json::to_value(&ext_out)?;
+        assert_eq!(serde_json::from_value::<Message>(json)?, ext_out);
+        Ok(())
+    }
+
+    #[test]
+    fn test_state_init_json() -> anyhow::Result<()> {
+        let mut state_init = StateInit::new(body()?, CellBuilder::new().build()?.to_arc());
+        state_init.tick_tock = Some(TickTock {
+            tick: true,
+            tock: false,
+        });
+        let json = serde_json::to_value(&state_init)?;
+        assert_eq!(json["special"], json!({"tick": true, "tock": false}));
+        assert_eq!(json["code"], body()?.to_boc_b64(false)?);
+        assert_eq!(json["library"], serde_json::Value::Null);
+        assert_eq!(serde_json::from_value::<StateInit>(json)?, state_init);
+        Ok(())
+    }
+
+    #[test]
+    fn test_jetton_transfer_json() -> anyhow::Result<()> {
+        let destination = TonAddress::from_str(ADDRESS)?;
+        let transfer = JettonTransferMessage::new(
+            8819263745311958,
+            &BigUint::from(1_000_000_000u64),
+            destination.to_msg_address(),
+            MsgAddress::NONE,
+            None,
+            &BigUint::from(1u32),
+            &body()?,
+        );
+        let json = serde_json::to_value(&transfer)?;
+        assert_eq!(json["query_id"], "8819263745311958");
+        assert_eq!(json["amount"], "1000000000");
+        assert_eq!(json["destination"], destination.to_hex());
+        assert_eq!(json["response_destination"], serde_json::Value::Null);
+        assert_eq!(
+            serde_json::from_value::<JettonTransferMessage>(json)?,
+            transfer
+        );
+
+        let mut invalid = serde_json::to_value(&transfer)?;
+        invalid["amount"] = json!("-1");
+        assert!(serde_json::from_value::<JettonTransferMessage>(invalid).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_json_ref_layout() -> anyhow::Result<()> {
+        // small bodies are stored inline by default, so refs must be kept by JSON
+        let destination = TonAddress::from_str(ADDRESS)?;
+        let mut transfer = JettonTransferMessage::new(
+            1,
+            &BigUint::from(1u32),
+            destination.to_msg_address(),
+            MsgAddress::NONE,
+            None,
+            &BigUint::from(0u32),
+            &body()?,
+        );
+        transfer.forward_payload.layout = EitherRefLayout::ToRef;
+        let cell = transfer.to_cell()?;
+        let parsed = JettonTransferMessage::from_cell(&cell)?;
+        let json = serde_json::to_value(&parsed)?;
+        assert_eq!(json["forward_payload_layout"], "ref");
+        let restored: JettonTransferMessage = serde_json::from_value(json)?;
+        assert_eq!(restored.to_cell()?.cell_hash(), cell.cell_hash());
+
+        let mut message = Message::new(
+            CommonMsgInfo::ExtIn(ExtInMsgInfo {
+                src: MsgAddressExt::None(MsgAddrNone {}),
+                dest: destination.to_msg_address_int(),
+                import_fee: Grams::new(BigUint::from(0u32)),
+            }),
+            transfer.to_cell()?.to_arc(),
+        );
+        message.with_state_init(StateInit::new(body()?, body()?));
+        message.body.layout = EitherRefLayout::ToRef;
+        let cell = message.to_cell()?;
+        let parsed = Message::from_cell(&cell)?;
+        let json = serde_json::to_value(&parsed)?;
+        assert_eq!(json["message_content"]["layout"], "ref");
+        assert_eq!(json["init_state"]["layout"], "inline");
+        let restored: Message = serde_json::from_value(json)?;
+        assert_eq!(restored.to_cell()?.cell_hash(), cell.cell_hash());
+
+        // layout is optional
+        let mut json = serde_json::to_value(&parsed)?;
+        json["message_content"]
+            .as_object_mut()
+            .unwrap()
+            .remove("layout");
+        let restored: Message = serde_json::from_value(json)?;
+        assert!(matches!(restored.body.layout, EitherRefLayout::Native));
+        Ok(())
+    }
+}
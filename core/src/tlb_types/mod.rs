@@ -1,5 +1,9 @@
 pub mod block;
+#[cfg(feature = "serde")]
+mod json;
 pub mod primitives;
+#[cfg(feature = "serde")]
+pub mod serde_tlb;
 pub mod tep;
 pub mod tlb;
 mod tlb_cell;
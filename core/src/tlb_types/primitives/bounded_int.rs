@@ -0,0 +1,320 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+pub use ethnum::{I256, U256};
+use num_bigint::{BigInt, BigUint, Sign};
+
+use crate::cell::{CellBuilder, CellParser, TonCellError};
+use crate::tlb_types::tlb::TLB;
+
+/// `uint N` for `N` in `1..=256`, stored inline without heap allocation.
+///
+/// The value always fits into `N` bits: constructors and arithmetic reject overflowing values.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Uint<const N: usize>(U256);
+
+/// `int N` for `N` in `1..=256`, stored inline without heap allocation.
+///
+/// The value always fits into `N` bits: constructors and arithmetic reject overflowing values.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Int<const N: usize>(I256);
+
+impl<const N: usize> Uint<N> {
+    pub const ZERO: Self = Self(U256::ZERO);
+
+    /// Fails to compile for `N` out of `1..=256`
+    const BITS: u32 = {
+        assert!(N > 0 && N <= 256, "N must be in 1..=256");
+        N as u32
+    };
+
+    pub fn new(value: U256) -> Result<Self, TonCellError> {
+        Self::checked(value).ok_or_else(|| {
+            TonCellError::InvalidInput(format!("Number {value} doesn't fit into uint{N}"))
+        })
+    }
+
+    pub fn max_value() -> Self {
+        Self(U256::MAX >> (256 - Self::BITS))
+    }
+
+    pub fn value(&self) -> U256 {
+        self.0
+    }
+
+    pub fn checked_add(self, rhs: Self) -> Option<Self> {
+        self.0.checked_add(rhs.0).and_then(Self::checked)
+    }
+
+    pub fn checked_sub(self, rhs: Self) -> Option<Self> {
+        self.0.checked_sub(rhs.0).map(Self)
+    }
+
+    pub fn checked_mul(self, rhs: Self) -> Option<Self> {
+        self.0.checked_mul(rhs.0).and_then(Self::checked)
+    }
+
+    pub fn checked_div(self, rhs: Self) -> Option<Self> {
+        self.0.checked_div(rhs.0).map(Self)
+    }
+
+    pub fn checked_rem(self, rhs: Self) -> Option<Self> {
+        self.0.checked_rem(rhs.0).map(Self)
+    }
+
+    fn checked(value: U256) -> Option<Self> {
+        (value <= Self::max_value().0).then_some(Self(value))
+    }
+}
+
+impl<const N: usize> Int<N> {
+    pub const ZERO: Self = Self(I256::ZERO);
+
+    /// Fails to compile for `N` out of `1..=256`
+    const BITS: u32 = {
+        assert!(N > 0 && N <= 256, "N must be in 1..=256");
+        N as u32
+    };
+
+    pub fn new(value: I256) -> Result<Self, TonCellError> {
+        Self::checked(value).ok_or_else(|| {
+            TonCellError::InvalidInput(format!("Number {value} doesn't fit into int{N}"))
+        })
+    }
+
+    pub fn min_value() -> Self {
+        Self(I256::MIN >> (256 - Self::BITS))
+    }
+
+    pub fn max_value() -> Self {
+        Self(I256::MAX >> (256 - Self::BITS))
+    }
+
+    pub fn value(&self) -> I256 {
+        self.0
+    }
+
+    pub fn checked_add(self, rhs: Self) -> Option<Self> {
+        self.0.checked_add(rhs.0).and_then(Self::checked)
+    }
+
+    pub fn checked_sub(self, rhs: Self) -> Option<Self> {
+        self.0.checked_sub(rhs.0).and_then(Self::checked)
+    }
+
+    pub fn checked_mul(self, rhs: Self) -> Option<Self> {
+        self.0.checked_mul(rhs.0).and_then(Self::checked)
+    }
+
+    pub fn checked_div(self, rhs: Self) -> Option<Self> {
+        self.0.checked_div(rhs.0).and_then(Self::checked)
+    }
+
+    pub fn checked_rem(self, rhs: Self) -> Option<Self> {
+        self.0.checked_rem(rhs.0).and_then(Self::checked)
+    }
+
+    pub fn checked_neg(self) -> Option<Self> {
+        self.0.checked_neg().and_then(Self::checked)
+    }
+
+    fn checked(value: I256) -> Option<Self> {
+        (Self::min_value().0 <= value && value <= Self::max_value().0).then_some(Self(value))
+    }
+}
+
+impl<const N: usize> TLB for Uint<N> {
+    fn read_definition(parser: &mut CellParser) -> Result<Self, TonCellError> {
+        let bytes = load_aligned(parser, N)?;
+        Self::new(U256::from_be_bytes(bytes) >> (256 - Self::BITS))
+    }
+
+    fn write_definition(&self, dst: &mut CellBuilder) -> Result<(), TonCellError> {
+        let bytes = (self.0 << (256 - Self::BITS)).to_be_bytes();
+        dst.write_bits(bytes, N)?;
+        Ok(())
+    }
+}
+
+impl<const N: usize> TLB for Int<N> {
+    fn read_definition(parser: &mut CellParser) -> Result<Self, TonCellError> {
+        let bytes = load_aligned(parser, N)?;
+        // arithmetic shift extends the sign bit
+        Self::new(I256::from_be_bytes(bytes) >> (256 - Self::BITS))
+    }
+
+    fn write_definition(&self, dst: &mut CellBuilder) -> Result<(), TonCellError> {
+        let bytes = (self.0 << (256 - Self::BITS)).to_be_bytes();
+        dst.write_bits(bytes, N)?;
+        Ok(())
+    }
+}
+
+/// Loads `bit_len` bits aligned to the most significant bit of 256-bit number
+fn load_aligned(parser: &mut CellParser, bit_len: usize) -> Result<[u8; 32], TonCellError> {
+    let mut bytes = [0u8; 32];
+    parser.load_bits_to_slice(bit_len, &mut bytes)?;
+    Ok(bytes)
+}
+
+impl<const N: usize> Display for Uint<N> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&self.0, f)
+    }
+}
+
+impl<const N: usize> Display for Int<N> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&self.0, f)
+    }
+}
+
+impl<const N: usize> FromStr for Uint<N> {
+    type Err = TonCellError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let value = U256::from_str(s)
+            .map_err(|err| TonCellError::InvalidInput(format!("Invalid uint{N} '{s}': {err}")))?;
+        Self::new(value)
+    }
+}
+
+impl<const N: usize> FromStr for Int<N> {
+    type Err = TonCellError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let value = I256::from_str(s)
+            .map_err(|err| TonCellError::InvalidInput(format!("Invalid int{N} '{s}': {err}")))?;
+        Self::new(value)
+    }
+}
+
+impl<const N: usize> TryFrom<u128> for Uint<N> {
+    type Error = TonCellError;
+
+    fn try_from(value: u128) -> Result<Self, Self::Error> {
+        Self::new(U256::from(value))
+    }
+}
+
+impl<const N: usize> TryFrom<i128> for Int<N> {
+    type Error = TonCellError;
+
+    fn try_from(value: i128) -> Result<Self, Self::Error> {
+        Self::new(I256::from(value))
+    }
+}
+
+impl<const N: usize> TryFrom<&BigUint> for Uint<N> {
+    type Error = TonCellError;
+
+    fn try_from(value: &BigUint) -> Result<Self, Self::Error> {
+        if value.bits() > N as u64 {
+            return Err(TonCellError::InvalidInput(format!(
+                "Number {value} doesn't fit into uint{N}"
+            )));
+        }
+        let bytes = value.to_bytes_be();
+        let mut padded = [0u8; 32];
+        padded[32 - bytes.len()..].copy_from_slice(&bytes);
+        Self::new(U256::from_be_bytes(padded))
+    }
+}
+
+impl<const N: usize> TryFrom<&BigInt> for Int<N> {
+    type Error = TonCellError;
+
+    fn try_from(value: &BigInt) -> Result<Self, Self::Error> {
+        let bytes = value.to_signed_bytes_be();
+        if bytes.len() > 32 {
+            return Err(TonCellError::InvalidInput(format!(
+                "Number {value} doesn't fit into int{N}"
+            )));
+        }
+        let fill = if value.sign() == Sign::Minus { 0xFF } else { 0 };
+        let mut padded = [fill; 32];
+        padded[32 - bytes.len()..].copy_from_slice(&bytes);
+        Self::new(I256::from_be_bytes(padded))
+    }
+}
+
+impl<const N: usize> From<Uint<N>> for BigUint {
+    fn from(value: Uint<N>) -> Self {
+        BigUint::from_bytes_be(&value.0.to_be_bytes())
+    }
+}
+
+impl<const N: usize> From<Int<N>> for BigInt {
+    fn from(value: Int<N>) -> Self {
+        BigInt::from_signed_bytes_be(&value.0.to_be_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_uint_bounds() -> Result<(), TonCellError> {
+        assert_eq!(Uint::<8>::max_value().value(), U256::from(255u8));
+        assert_eq!(Uint::<256>::max_value().value(), U256::MAX);
+        assert!(Uint::<8>::try_from(256u128).is_err());
+        assert!(Uint::<150>::new(U256::ONE << 150).is_err());
+
+        let a = Uint::<8>::try_from(200u128)?;
+        let b = Uint::<8>::try_from(100u128)?;
+        assert_eq!(a.checked_add(b), None);
+        assert_eq!(b.checked_sub(a), None);
+        assert_eq!(a.checked_sub(b), Some(b));
+        assert_eq!(a.checked_mul(b), None);
+        assert_eq!(a.checked_div(b), Some(Uint::try_from(2u128)?));
+        assert_eq!(a.checked_rem(Uint::ZERO), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_int_bounds() -> Result<(), TonCellError> {
+        assert_eq!(Int::<8>::min_value().value(), I256::from(-128));
+        assert_eq!(Int::<8>::max_value().value(), I256::from(127));
+        assert!(Int::<8>::try_from(128i128).is_err());
+        assert!(Int::<8>::try_from(-129i128).is_err());
+        assert_eq!(Int::<8>::min_value().checked_neg(), None);
+        assert_eq!(
+            Int::<8>::min_value().checked_div(Int::try_from(-1i128)?),
+            None
+        );
+
+        let a = Int::<8>::try_from(-100i128)?;
+        assert_eq!(a.checked_add(a), None);
+        assert_eq!(a.checked_neg(), Some(Int::try_from(100i128)?));
+        Ok(())
+    }
+
+    #[test]
+    fn test_bounded_int_tlb() -> Result<(), TonCellError> {
+        let uint = Uint::<150>::from_str("1234567890123456789012345678901234567890")?;
+        let int = Int::<77>::try_from(-1234567890123456789012i128)?;
+        let mut builder = CellBuilder::new();
+        builder.store_bit(true)?;
+        uint.write(&mut builder)?;
+        int.write(&mut builder)?;
+        let cell = builder.build()?;
+        assert_eq!(cell.bit_len(), 1 + 150 + 77);
+
+        let mut parser = cell.parser();
+        assert!(parser.load_bit()?);
+        let big_uint = parser.load_uint(150)?;
+        let big_int = parser.load_int(77)?;
+        assert_eq!(BigUint::from(uint), big_uint);
+        assert_eq!(BigInt::from(int), big_int);
+        assert_eq!(Uint::<150>::try_from(&big_uint)?, uint);
+        assert_eq!(Int::<77>::try_from(&big_int)?, int);
+
+        let mut parser = cell.parser();
+        parser.load_bit()?;
+        assert_eq!(parser.load_tlb::<Uint<150>>()?, uint);
+        assert_eq!(parser.load_tlb::<Int<77>>()?, int);
+        parser.ensure_empty()?;
+        Ok(())
+    }
+}
@@ -1,3 +1,4 @@
+pub mod bounded_int;
 pub mod either;
 pub mod option;
 pub mod reference;
@@ -0,0 +1,78 @@
+//! Serde attribute modules storing `TLB` objects as BoC strings:
+//!
+//! ```ignore
+//! #[derive(Serialize, Deserialize)]
+//! struct Request {
+//!     #[serde(with = "tonlib_core::tlb_types::serde_tlb::base64")]
+//!     state_init: StateInit,
+//! }
+//! ```
+use serde::de::Error as _;
+use serde::ser::Error as _;
+use serde::{Deserialize, Deserializer, Serializer};
+
+use crate::tlb_types::tlb::TLB;
+
+/// Base64 BoC, the format used by TON HTTP API
+pub mod base64 {
+    use super::*;
+
+    pub fn serialize<T: TLB, S: Serializer>(value: &T, serializer: S) -> Result<S::Ok, S::Error> {
+        let boc = value.to_boc_b64(false).map_err(S::Error::custom)?;
+        serializer.serialize_str(&boc)
+    }
+
+    pub fn deserialize<'de, T: TLB, D: Deserializer<'de>>(deserializer: D) -> Result<T, D::Error> {
+        let boc = String::deserialize(deserializer)?;
+        T::from_boc_b64(&boc).map_err(D::Error::custom)
+    }
+}
+
+/// Hex BoC
+pub mod hex {
+    use super::*;
+
+    pub fn serialize<T: TLB, S: Serializer>(value: &T, serializer: S) -> Result<S::Ok, S::Error> {
+        let boc = value.to_boc_hex(false).map_err(S::Error::custom)?;
+        serializer.serialize_str(&boc)
+    }
+
+    pub fn deserialize<'de, T: TLB, D: Deserializer<'de>>(deserializer: D) -> Result<T, D::Error> {
+        let boc = String::deserialize(deserializer)?;
+        T::from_boc_hex(&boc).map_err(D::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use num_bigint::BigUint;
+    use serde::{Deserialize, Serialize};
+
+    use crate::tlb_types::block::coins::Grams;
+    use crate::tlb_types::tlb::TLB;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Fields {
+        #[serde(with = "crate::tlb_types::serde_tlb::base64")]
+        b64: Grams,
+        #[serde(with = "crate::tlb_types::serde_tlb::hex")]
+        hex: Grams,
+    }
+
+    #[test]
+    fn test_serde_tlb() -> anyhow::Result<()> {
+        let grams = Grams::new(BigUint::from(1_000_000_000u32));
+        let fields = Fields {
+            b64: grams.clone(),
+            hex: grams.clone(),
+        };
+        let json = serde_json::to_value(&fields)?;
+        assert_eq!(json["b64"], grams.to_boc_b64(false)?);
+        assert_eq!(json["hex"], grams.to_boc_hex(false)?);
+        assert_eq!(serde_json::from_value::<Fields>(json)?, fields);
+
+        let invalid = serde_json::json!({"b64": "AAAA", "hex": "00"});
+        assert!(serde_json::from_value::<Fields>(invalid).is_err());
+        Ok(())
+    }
+}
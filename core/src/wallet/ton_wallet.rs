@@ -179,7 +179,7 @@ mod tests {
 
         let int_msg = CellBuilder::new().build()?.to_arc();
 
-        let ext_body_cell = wallet.create_external_body(13, 7, &[int_msg.clone()])?;
+        let ext_body_cell = wallet.create_external_body(13, 7, std::slice::from_ref(&int_msg))?;
         let body = WalletExtMsgBodyV3::from_cell(&ext_body_cell)?;
         let expected = WalletExtMsgBodyV3 {
             subwallet_id: DEFAULT_WALLET_ID,
@@ -199,7 +199,7 @@ mod tests {
 
         let int_msg = CellBuilder::new().build()?.to_arc();
 
-        let ext_body_cell = wallet.create_external_body(13, 7, &[int_msg.clone()])?;
+        let ext_body_cell = wallet.create_external_body(13, 7, std::slice::from_ref(&int_msg))?;
         let body = WalletExtMsgBodyV4::from_cell(&ext_body_cell)?;
         let expected = WalletExtMsgBodyV4 {
             subwallet_id: DEFAULT_WALLET_ID,
@@ -253,7 +253,7 @@ mod tests {
         let msg = CellBuilder::new().store_u32(32, 100)?.build()?.to_arc();
 
         for wallet in [wallet_v3, wallet_v5] {
-            let body = wallet.create_external_body(1, 3, &[msg.clone()])?;
+            let body = wallet.create_external_body(1, 3, std::slice::from_ref(&msg))?;
             let signed_msg = wallet.sign_external_body(&body)?;
 
             let mut parser = signed_msg.parser();